@@ -1,16 +1,20 @@
 use std::env;
+use std::path::PathBuf;
 
+use chrono::{NaiveDateTime, TimeDelta};
 use clap::ArgAction;
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
 use skim::SkimOptions;
 
 use crate::trash::error::AppError;
+use crate::trash::{parse_age, parse_since, parse_size, parse_until, DEFAULT_MAX_COLLISIONS};
 
 /// A command-line trash can utility that adheres to the FreeDesktop.org specification.
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
-    /// Files or directories to move to the trash
+    /// Files or directories to move to the trash (with --restore or --purge: original paths of trashed items)
     pub files: Vec<String>,
 
     /// When to use colors.
@@ -21,6 +25,10 @@ pub struct Args {
     #[arg(short = 'a', long, action = ArgAction::SetTrue)]
     pub all: bool,
 
+    /// List, empty or restore from only the trash directory at PATH (one with `files` and `info` subdirectories).
+    #[arg(long, value_name = "PATH", conflicts_with = "all")]
+    pub trash_dir: Option<PathBuf>,
+
     /// Display the contents of the trash directories.
     #[arg(short = 'd', long, action = ArgAction::SetTrue)]
     pub display: bool,
@@ -29,28 +37,443 @@ pub struct Args {
     #[arg(short = 'l', long, action = ArgAction::SetTrue)]
     pub long: bool,
 
+    /// Ask before trashing each item; for a directory, show how many items it contains and its size.
+    #[arg(short = 'i', long, action = ArgAction::SetTrue, overrides_with = "force")]
+    pub interactive: bool,
+
+    /// Allow trashing directories; without it they are skipped like `rm` does (-r is --restore).
+    #[arg(short = 'R', long, action = ArgAction::SetTrue)]
+    pub recursive: bool,
+
+    /// Never ask before trashing; overrides an earlier -i.
+    #[arg(short = 'f', long, action = ArgAction::SetTrue, overrides_with = "interactive")]
+    pub force: bool,
+
+    /// Allow trashing protected paths such as / or $HOME, and tracked files with --protect-tracked.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub allow_protected: bool,
+
+    /// Refuse to trash files tracked by git (found in the index of the repository around them);
+    /// with --allow-protected, only warn.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub protect_tracked: bool,
+
+    /// When trashing, ask before trashing a directory larger than SIZE, showing its size (-y or -f skip the question).
+    #[arg(long, value_name = "SIZE", default_value = "1G", value_parser = parse_size)]
+    pub confirm_larger_than: u64,
+
+    /// When an item cannot be moved into its trash because it is on another filesystem,
+    /// copy it there and delete the original instead of failing.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub copy_fallback: bool,
+
+    /// Also trash the paths read from stdin, one per line (e.g. `find . -name '*.tmp' | tt --stdin`).
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub stdin: bool,
+
+    /// Like --stdin, but the paths are separated by NUL bytes (for `find -print0`).
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with = "stdin")]
+    pub stdin0: bool,
+
+    /// Put a copy of each item into the trash and keep the original (a snapshot before editing).
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub copy: bool,
+
+    /// Print what trashing or emptying would do without changing anything on disk.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub dry_run: bool,
+
+    /// Match well-known file names (e.g. `Makefile`) case-sensitively when choosing their color.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub preserve_case: bool,
+
+    /// Sort the listing by name (case-insensitive), date (deletion date, newest first) or size (largest first).
+    #[arg(long, value_name = "KEY", value_parser = ["name", "date", "size"])]
+    pub sort: Option<String>,
+
+    /// Reverse the order of the listing.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub reverse: bool,
+
+    /// Add a column with the type of each entry's trash (home, shared or private) to long and porcelain listings.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub show_trash_type: bool,
+
+    /// In the plain listing, show each item's original path (from its .trashinfo file) instead of its name in the trash.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub print_original: bool,
+
+    /// Show paths under $HOME as `~/...` in listings, the restore UI and messages.
+    #[arg(long, action = ArgAction::SetTrue, visible_alias = "tilde")]
+    pub relative: bool,
+
+    /// Print `'src' -> 'dest'` for each trashed item, and diagnostic details to stderr
+    /// (e.g. why a trash name was skipped on collision).
+    #[arg(short = 'v', long, action = ArgAction::SetTrue)]
+    pub verbose: bool,
+
+    /// Report where the .trashinfo files deviate from the spec (format, key order, encoding, missing data),
+    /// without changing anything.
+    #[arg(
+        long,
+        action = ArgAction::SetTrue,
+        conflicts_with_all = ["files", "restore", "restore_latest", "put_back", "empty", "purge", "stats", "commit_empty", "cancel_empty"]
+    )]
+    pub trashinfo_lint: bool,
+
+    /// Print the metadata and start of the contents of the trashed item at PATH, as the restore UI previews it.
+    #[arg(
+        long,
+        value_name = "PATH",
+        hide = true,
+        conflicts_with_all = ["files", "restore", "restore_latest", "put_back", "empty", "purge", "stats", "trashinfo_lint"]
+    )]
+    pub preview: Option<PathBuf>,
+
+    /// Show the number of items and total size of the trash directories.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub stats: bool,
+
+    /// Print only the number of trashed items, as a single integer (e.g. for a status bar).
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with_all = ["stats", "trashinfo_lint"])]
+    pub count: bool,
+
+    /// Print how much space each trash directory uses and the total, without listing the items.
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with_all = ["stats", "count", "trashinfo_lint"])]
+    pub du: bool,
+
+    /// With --stats, redraw the summary every SECS seconds (default 2) until Ctrl-C.
+    #[arg(long, value_name = "SECS", num_args = 0..=1, default_missing_value = "2", requires = "stats")]
+    pub watch: Option<u64>,
+
     /// Permanently delete all contents of the trash directories.
     #[arg(short = 'e', long, action = ArgAction::SetTrue)]
     pub empty: bool,
 
+    /// With --empty, only delete items larger than SIZE (e.g. 500K, 10M, 1G).
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub larger_than: Option<u64>,
+
+    /// With --empty, only delete items trashed more than AGE ago (e.g. 12h, 30d, 2w).
+    #[arg(long, value_name = "AGE", value_parser = parse_age)]
+    pub older_than: Option<TimeDelta>,
+
+    /// With --empty, keep the emptied items recoverable for AGE (e.g. 10m, 1h) before deleting them.
+    #[arg(long, value_name = "AGE", value_parser = parse_age)]
+    pub grace: Option<TimeDelta>,
+
+    /// Permanently delete the items held back by earlier `--empty --grace` runs now.
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with = "cancel_empty")]
+    pub commit_empty: bool,
+
+    /// Put back the items held back by earlier `--empty --grace` runs that are still within their grace period.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub cancel_empty: bool,
+
     /// Empty the trash without prompting for confirmation.
     #[arg(short = 'y', long, action = ArgAction::SetTrue)]
     pub no_confirm: bool,
 
-    /// Interactively restore items from the trash.
+    /// Interactively restore items from the trash, or restore the given original paths directly.
     #[arg(short = 'r', long, action = ArgAction::SetTrue)]
     pub restore: bool,
 
+    /// Permanently delete the trashed items that were trashed from the given original paths.
+    #[arg(long, action = ArgAction::SetTrue, requires = "files", conflicts_with_all = ["restore", "empty"])]
+    pub purge: bool,
+
+    /// Permanently delete the trashed items whose deletion date falls between --since and --until
+    /// (either may be left out), asking once first unless -y is given.
+    #[arg(
+        long,
+        action = ArgAction::SetTrue,
+        conflicts_with_all = ["files", "restore", "restore_latest", "put_back", "empty", "purge", "stats", "commit_empty", "cancel_empty", "trashinfo_lint"]
+    )]
+    pub purge_by_date_range: bool,
+
+    /// With --purge-by-date-range, only items deleted at or after DATE (e.g. 2024-01-02 or 2024-01-02T15:00:00).
+    #[arg(long, value_name = "DATE", value_parser = parse_since, requires = "purge_by_date_range")]
+    pub since: Option<NaiveDateTime>,
+
+    /// With --purge-by-date-range, only items deleted before DATE; a plain date includes that whole day.
+    #[arg(long, value_name = "DATE", value_parser = parse_until, requires = "purge_by_date_range")]
+    pub until: Option<NaiveDateTime>,
+
+    /// Restore the most recently trashed item (from any trash), undoing the last trashing.
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with_all = ["files", "restore", "empty", "name"])]
+    pub restore_latest: bool,
+
+    /// Restore the item that was trashed from exactly PATH, without the interactive UI ("Put Back").
+    /// Fails, listing the candidates, if PATH was trashed more than once. Can be given more than once.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["files", "restore", "restore_latest", "empty", "name"]
+    )]
+    pub put_back: Vec<String>,
+
+    /// With --restore: restore the trashed item originally named NAME, without the interactive UI.
+    /// Can be given more than once.
+    #[arg(long, value_name = "NAME", requires = "restore", conflicts_with = "files")]
+    pub name: Vec<String>,
+
+    /// With --restore: restore every trashed item whose original file name matches PATTERN (e.g. '*.log'),
+    /// without the interactive UI. Can be given more than once.
+    #[arg(long, value_name = "PATTERN", requires = "restore", conflicts_with_all = ["files", "name"])]
+    pub glob: Vec<String>,
+
+    /// With --restore FILES, --name, --glob, --restore-latest or --put-back, list the matched items and ask once before restoring.
+    /// -y skips the question.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub confirm_restore: bool,
+
+    /// When restoring, pair info files with data files whose names were mangled (e.g. by a sync tool).
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub fuzzy_pair: bool,
+
+    /// With --restore, copy the selected items out to DEST (a directory or a new path) and keep them in the trash.
+    #[arg(long, value_name = "DEST")]
+    pub copy_out: Option<PathBuf>,
+
+    /// Limit the copy speed to RATE bytes per second (e.g. 500K, 10M) when items are copied across filesystems:
+    /// into the trash with --copy-fallback or --copy, or back out when restoring or with --copy-out.
+    #[arg(long, value_name = "RATE", value_parser = parse_size, visible_alias = "rate-limit")]
+    pub limit_rate: Option<u64>,
+
+    /// Treat output as a terminal (colors with --color=auto, grid layout) even when it is not.
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with = "no_tty")]
+    pub assume_tty: bool,
+
+    /// Treat output as not a terminal (no colors with --color=auto, one entry per line).
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub no_tty: bool,
+
+    /// After trashing, permanently delete the oldest items of each trash used until it holds at most SIZE (e.g. 10G).
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_size: Option<u64>,
+
+    /// When trashing, give up on an item after N names in the trash are found to be taken.
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_COLLISIONS, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_collisions: u32,
+
+    /// When trashing, percent-encode the info file `Path` exactly like TOOL does.
+    #[arg(long, value_name = "TOOL", default_value = "trash-tool", value_parser = ["trash-tool", "trash-cli", "gio"])]
+    pub trash_info_compat: String,
+
+    /// When trashing, also record the permission bits of each item and of its parent directory
+    /// in its info file (`Mode=` and `ParentMode=` lines), so a restore can re-apply them.
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with = "trash_info_compat")]
+    pub record_mode: bool,
+
+    /// Debugging only: with `raw`, write the info file `Path` without percent-encoding.
+    /// The resulting info files do not comply with the Trash specification.
+    #[arg(long, value_name = "ENCODING", hide = true, value_parser = ["raw"], conflicts_with = "trash_info_compat")]
+    pub info_encoding: Option<String>,
+
+    /// Never create trash directories; operations that would need to create one fail instead.
+    /// Listing and --stats always behave this way.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub no_create: bool,
+
+    /// With --restore, print each resulting path terminated by a NUL byte instead of a newline.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub print0: bool,
+
+    /// Write the listing or --stats output to FILE (truncating it) instead of stdout.
+    #[arg(short = 'o', long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Show the listing through $PAGER (default `less -R`): `auto` (the default for a bare --pager)
+    /// only when stdout is a terminal, `always` or `never`.
+    #[arg(
+        long,
+        value_name = "WHEN",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "auto",
+        value_parser = ["auto", "always", "never"]
+    )]
+    pub pager: Option<String>,
+
+    /// In the long listing or with --du, show sizes as exact byte counts instead of e.g. `1.2 MiB`.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub bytes: bool,
+
+    /// Use a stable, tab-separated, color-free output format intended for scripts.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub porcelain: bool,
+
     /// Optional subcommand for advanced configuration, e.g., 'skim'.
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
+impl Args {
+    /// Rejects option combinations that would otherwise be silently ignored.
+    fn validate(&self) -> Result<(), AppError> {
+        // `-y` on its own empties the trash, but with --purge(-by-date-range), a restore or files it only skips the confirmation.
+        let restore_mode = self.restore || self.restore_latest || !self.put_back.is_empty();
+        let purge_mode = self.purge || self.purge_by_date_range;
+        let reads_stdin = self.stdin || self.stdin0;
+        let has_files = !self.files.is_empty() || reads_stdin;
+        // With files to trash, `-y` only skips the large-directory question.
+        let empty_mode = self.empty || (self.no_confirm && !purge_mode && !restore_mode && !has_files);
+        let invalid = |detail: &str| {
+            Err(AppError::InvalidArgument {
+                detail: detail.to_string(),
+            })
+        };
+
+        if reads_stdin && (restore_mode || self.purge) {
+            return invalid("--stdin and --stdin0 only read files to trash");
+        }
+        if reads_stdin && self.interactive {
+            return invalid("-i cannot be combined with --stdin or --stdin0, which already read from stdin");
+        }
+        if has_files && empty_mode {
+            return invalid("files to trash cannot be combined with --empty");
+        }
+        if self.stats && (has_files || empty_mode || restore_mode) {
+            return invalid("--stats cannot be combined with files, --empty or --restore");
+        }
+        if self.count && (has_files || empty_mode || restore_mode || purge_mode) {
+            return invalid("--count cannot be combined with files, --empty, --purge or --restore");
+        }
+        if self.du && (has_files || empty_mode || restore_mode || purge_mode) {
+            return invalid("--du cannot be combined with files, --empty, --purge or --restore");
+        }
+        if (self.commit_empty || self.cancel_empty) && (empty_mode || restore_mode) {
+            return invalid("--commit-empty/--cancel-empty cannot be combined with --empty or --restore");
+        }
+        if !empty_mode && (self.larger_than.is_some() || self.older_than.is_some() || self.grace.is_some()) {
+            return invalid("--larger-than, --older-than and --grace require --empty");
+        }
+        if self.grace.is_some() && (self.larger_than.is_some() || self.older_than.is_some()) {
+            return invalid("--grace cannot be combined with --larger-than or --older-than");
+        }
+        if self.purge
+            && (self.stats
+                || self.commit_empty
+                || self.cancel_empty
+                || self.restore_latest
+                || !self.put_back.is_empty())
+        {
+            return invalid(
+                "--purge cannot be combined with --stats, --commit-empty, --cancel-empty, --restore-latest or --put-back",
+            );
+        }
+        if self.purge_by_date_range && self.since.is_none() && self.until.is_none() {
+            return invalid("--purge-by-date-range requires --since, --until or both");
+        }
+        if let (Some(since), Some(until)) = (self.since, self.until) {
+            if since >= until {
+                return invalid("--since must be earlier than --until");
+            }
+        }
+        let listing_mode = !has_files
+            && !empty_mode
+            && !restore_mode
+            && !self.purge_by_date_range
+            && !self.commit_empty
+            && !self.cancel_empty;
+        if self.output.is_some() && (!listing_mode || self.watch.is_some()) {
+            return invalid(
+                "--output can only be used when listing or with --stats, --count or --du (without --watch)",
+            );
+        }
+        if self.pager.is_some() && (!listing_mode || self.stats || self.count || self.du || self.output.is_some()) {
+            return invalid("--pager can only be used when listing (without --stats, --count, --du or --output)");
+        }
+        let long_listing = listing_mode && !self.stats && !self.count && self.long && !self.porcelain;
+        if self.bytes && !self.du && !long_listing {
+            return invalid("--bytes can only be used with the long listing (-l) or --du");
+        }
+        if self.show_trash_type && (!listing_mode || self.stats || self.count || self.du) {
+            return invalid("--show-trash-type can only be used when listing");
+        }
+        if self.trash_dir.is_some()
+            && !((listing_mode && !self.stats && !self.count && !self.du)
+                || empty_mode
+                || restore_mode
+                || self.purge_by_date_range)
+        {
+            return invalid("--trash-dir can only be used when listing, emptying or restoring");
+        }
+        if self.print_original && (!listing_mode || self.stats || self.long || self.porcelain) {
+            return invalid("--print-original can only be used with the plain listing (without -l or --porcelain)");
+        }
+        let trash_mode = has_files && !restore_mode && !self.purge;
+        if self.dry_run && !(trash_mode || empty_mode || self.purge_by_date_range) {
+            return invalid("--dry-run can only be used when trashing files, with --empty or --purge-by-date-range");
+        }
+        if self.copy && !trash_mode {
+            return invalid("--copy can only be used when trashing files");
+        }
+        if self.max_size.is_some() && !trash_mode {
+            return invalid("--max-size can only be used when trashing files");
+        }
+        if self.protect_tracked && !trash_mode {
+            return invalid("--protect-tracked can only be used when trashing files");
+        }
+        if self.allow_protected && !trash_mode {
+            return invalid("--allow-protected can only be used when trashing files");
+        }
+        if self.record_mode && !trash_mode {
+            return invalid("--record-mode can only be used when trashing files");
+        }
+        if self.limit_rate.is_some() && !(restore_mode || trash_mode) {
+            return invalid("--limit-rate can only be used when trashing or restoring files");
+        }
+        if !restore_mode && (self.fuzzy_pair || self.copy_out.is_some() || self.print0 || self.confirm_restore) {
+            return invalid(
+                "--fuzzy-pair, --copy-out, --print0 and --confirm-restore require --restore, --restore-latest or --put-back",
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the explicit TTY decision from `--assume-tty`/`--no-tty`, if any.
+    pub fn tty_override(&self) -> Option<bool> {
+        match (self.assume_tty, self.no_tty) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+// `Commands` is built once per run, so the size of the `UI` variant does not matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Parser)]
 pub enum Commands {
     /// Configure the fuzzy finder options for restoring.
     #[command(name = "ui")]
     UI(SkimOptions),
+    /// Print diagnostics about trash locations, mounts, permissions and terminal detection.
+    Doctor,
+    /// Report info files without an item and items without an info file in the trash.
+    Check {
+        /// Delete the orphaned info files and write a minimal info file for each orphaned item.
+        #[arg(long, action = ArgAction::SetTrue)]
+        fix: bool,
+    },
+    /// Print a completion script for SHELL.
+    #[command(hide = true)]
+    Completions {
+        #[arg(value_name = "SHELL")]
+        shell: Shell,
+    },
+}
+
+/// The name the completion scripts complete, which is the installed binary's.
+const BIN_NAME: &str = "tt";
+
+/// Returns the completion script for `shell`, generated from the current `Args` definition.
+/// It is built in memory because `clap_complete` panics when its writer fails, e.g. on a closed pipe.
+pub fn completion_script(shell: Shell) -> Vec<u8> {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut Args::command(), BIN_NAME, &mut script);
+    script
 }
 
 const TRASH_TOOL_OPTIONS: &str = "TRASH_TOOL_OPTIONS";
@@ -60,9 +483,8 @@ fn build_skim_options(cli_args: Vec<String>) -> Result<Option<Commands>, AppErro
 
     skim_args.extend(shlex::split(&env::var(TRASH_TOOL_OPTIONS).unwrap_or_default()).unwrap_or_default());
 
-    let subcmd_pos = cli_args.iter().position(|arg| arg == "ui");
-    if subcmd_pos.is_some() {
-        skim_args.extend_from_slice(&cli_args[subcmd_pos.unwrap() + 1..]);
+    if let Some(subcmd_pos) = cli_args.iter().position(|arg| arg == "ui") {
+        skim_args.extend_from_slice(&cli_args[subcmd_pos + 1..]);
     }
 
     let skim_options = SkimOptions::try_parse_from(skim_args).map_err(|e| AppError::Message(e.to_string()))?;
@@ -73,6 +495,7 @@ fn build_skim_options(cli_args: Vec<String>) -> Result<Option<Commands>, AppErro
 pub fn parse_args() -> Result<Args, AppError> {
     // Parse of all CLI arguments. A reason for this is to let `clap` handle subcommand help flags (e.g., `skim --help`) correctly.
     let mut args = Args::parse();
+    args.validate()?;
 
     if args.restore {
         args.command = build_skim_options(env::args().collect())?;
@@ -86,6 +509,330 @@ mod tests {
     use super::*;
     use serial_test::serial;
 
+    #[test]
+    fn test_validate_rejects_invalid_combinations() {
+        struct TestCase<'a> {
+            args: &'a [&'a str],
+            valid: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                args: &["tt", "file.txt"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-e", "--larger-than", "10M", "--older-than", "30d"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-e", "--grace", "10m"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-r", "file.txt"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-e", "file.txt"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--larger-than", "10M"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "-e", "--grace", "10m", "--older-than", "1d"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--fuzzy-pair"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "-al", "--show-trash-type"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "--show-trash-type", "file.txt"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "-a", "--print-original"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-l", "--print-original"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "-e", "--trash-dir", "/media/usb/.Trash-1000"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "--trash-dir", "/media/usb/.Trash-1000", "notes.txt"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--print0"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--purge", "--commit-empty", "a.txt"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--purge", "-y", "a.txt"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "--dry-run"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--restore", "--dry-run", "a.txt"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--dry-run", "a.txt"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-e", "--dry-run", "--older-than", "30d"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "--confirm-restore", "a.txt"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "-r", "--confirm-restore", "-y", "a.txt"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-R", "-y", "build"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-e", "-y", "build"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "-e", "--stdin"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "-i", "--stdin0"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--stdin", "--dry-run"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-e", "--copy"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--copy", "notes.txt"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "--max-size", "10G", "notes.txt"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-e", "--max-size", "10G"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "-r", "--max-size", "10G", "notes.txt"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--allow-protected", "-f", "/srv"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-e", "--allow-protected"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--copy-fallback", "--limit-rate", "10M", "big.iso"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-e", "--limit-rate", "10M"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--record-mode", "notes.txt"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-r", "--record-mode", "notes.txt"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "-r", "--glob", "*.log"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "--restore-latest", "--print0"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "--put-back", "/home/user/report.pdf", "--confirm-restore"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "--put-back", "/home/user/report.pdf", "--purge"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "-l", "--output", "report.txt"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-l", "--pager"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-l", "--bytes"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "--bytes"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--pager=always", "--stats"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "-a", "--count"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "-a", "--du", "--bytes"],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "--du", "-r"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--du", "--show-trash-type"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--count", "notes.txt"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "-e", "--count"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--count", "--pager"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--pager", "--output", "report.txt"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "-e", "--output", "report.txt"],
+                valid: false,
+            },
+            TestCase {
+                args: &["tt", "--cancel-empty", "-e"],
+                valid: false,
+            },
+            TestCase {
+                args: &[
+                    "tt",
+                    "--purge-by-date-range",
+                    "--since",
+                    "2024-01-02",
+                    "--until",
+                    "2024-01-02",
+                    "-y",
+                ],
+                valid: true,
+            },
+            TestCase {
+                args: &[
+                    "tt",
+                    "--purge-by-date-range",
+                    "--until",
+                    "2024-01-02T12:00:00",
+                    "--dry-run",
+                ],
+                valid: true,
+            },
+            TestCase {
+                args: &["tt", "--purge-by-date-range"],
+                valid: false,
+            },
+            TestCase {
+                args: &[
+                    "tt",
+                    "--purge-by-date-range",
+                    "--since",
+                    "2024-01-03",
+                    "--until",
+                    "2024-01-02",
+                ],
+                valid: false,
+            },
+        ];
+
+        for case in test_cases {
+            let args = Args::try_parse_from(case.args).unwrap();
+            let result = args.validate();
+            assert_eq!(result.is_ok(), case.valid, "Failed on: {:?}", case.args);
+            if let Err(e) = result {
+                assert!(matches!(e, AppError::InvalidArgument { .. }));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_doctor_subcommand() {
+        let args = Args::try_parse_from(["tt", "doctor"]).unwrap();
+        assert!(matches!(args.command, Some(Commands::Doctor)));
+        let args = Args::try_parse_from(["tt", "-a", "check", "--fix"]).unwrap();
+        assert!(args.all);
+        assert!(matches!(args.command, Some(Commands::Check { fix: true })));
+        assert!(args.files.is_empty());
+    }
+
+    #[test]
+    fn test_bash_completion_script() {
+        let args = Args::try_parse_from(["tt", "completions", "bash"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Commands::Completions { shell: Shell::Bash })
+        ));
+        assert!(args.validate().is_ok());
+        assert!(Args::try_parse_from(["tt", "completions", "tcsh"]).is_err());
+
+        let script = String::from_utf8(completion_script(Shell::Bash)).unwrap();
+        assert!(script.contains("_tt()"), "the script completes the `tt` binary");
+        for flag in [
+            "--restore",
+            "--empty",
+            "--long",
+            "--trash-dir",
+            "--max-size",
+            "doctor",
+            "check",
+        ] {
+            assert!(script.contains(flag), "missing {} in the bash completions", flag);
+        }
+    }
+
     #[test]
     fn test_cli_constants() {
         assert_eq!(TRASH_TOOL_OPTIONS, "TRASH_TOOL_OPTIONS");
@@ -169,7 +916,9 @@ mod tests {
 
         let result = build_skim_options(cli_args).unwrap().unwrap();
 
-        let Commands::UI(options) = result;
+        let Commands::UI(options) = result else {
+            panic!("Expected Commands::UI");
+        };
         assert!(options.multi, "Should inherit --multi from env");
         assert_eq!(options.height, "80%", "Should use --height from CLI");
 
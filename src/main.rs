@@ -1,53 +1,281 @@
+#[cfg(unix)]
 mod cli;
+#[cfg(windows)]
+mod recycle_bin;
+#[cfg(unix)]
 pub mod trash;
 
-use cli::{parse_args, Commands};
+#[cfg(unix)]
+use std::fs::File;
+#[cfg(unix)]
+use std::io::{self, BufWriter, IsTerminal, Write};
+#[cfg(unix)]
+use std::path::Path;
+#[cfg(unix)]
+use std::time::Duration;
 
+#[cfg(unix)]
+use cli::{completion_script, parse_args, Commands};
+
+#[cfg(unix)]
 use crate::trash::{
-    apply_color_setting, handle_display_trash, handle_empty_trash, handle_interactive_restore, handle_move_to_trash,
-    AppError, EmptyTrashOptions,
+    apply_color_setting, apply_ls_colors_from_env, apply_theme_from_env, handle_cancel_empty, handle_check,
+    handle_commit_empty, handle_count, handle_disk_usage, handle_display_trash, handle_doctor, handle_empty_trash,
+    handle_interactive_restore, handle_move_to_trash, handle_preview, handle_purge_paths, handle_put_back,
+    handle_restore_glob, handle_restore_latest, handle_restore_names, handle_restore_paths, handle_stats,
+    handle_trashinfo_lint, load_file_type_config, read_file_args, resolve_tty, set_file_type_classifier, set_no_create,
+    set_relative_paths, set_verbose, AppError, CheckOptions, DiskUsageOptions, DisplayTrashOptions, EmptyTrashOptions,
+    EntryFilter, FileTypeClassifier, ListOrder, Pager, PathEncoding, PurgeOptions, RestoreOptions, SortKey,
+    StatsOptions, TrashOptions,
 };
 
+#[cfg(unix)]
 fn main() {
     if let Err(e) = run() {
         match e {
-            AppError::Ignorable => {}
+            AppError::Ignorable | AppError::NothingToDo => {}
             _ => {
                 eprintln!("Error: {}", e);
             }
         }
-        std::process::exit(1)
+        std::process::exit(e.exit_code())
     }
     std::process::exit(0)
 }
 
 /// The primary function containing all application logic.
+#[cfg(unix)]
 fn run() -> Result<(), AppError> {
     let args = parse_args()?;
 
-    apply_color_setting(&args.color);
+    // Output written to a file is never a terminal, unless forced with `--assume-tty`.
+    let tty_override = args.tty_override().or(args.output.as_ref().map(|_| false));
+    // Porcelain output is always color-free, regardless of `--color`.
+    apply_color_setting(if args.porcelain { "never" } else { &args.color }, tty_override);
+    apply_theme_from_env();
+    apply_ls_colors_from_env();
+    set_file_type_classifier(FileTypeClassifier {
+        case_sensitive_names: args.preserve_case,
+    });
+    load_file_type_config();
+    set_verbose(args.verbose);
+    set_relative_paths(args.relative);
+    set_no_create(args.no_create || args.dry_run);
+    let tty = resolve_tty(tty_override);
+
+    if let Some(trashed_path) = &args.preview {
+        return handle_preview(trashed_path);
+    }
+    if let Some(Commands::Completions { shell }) = args.command {
+        io::stdout().write_all(&completion_script(shell))?;
+        return Ok(());
+    }
 
     match true {
-        _ if !args.files.is_empty() => {
-            handle_move_to_trash(&args.files)?;
+        _ if matches!(args.command, Some(Commands::Doctor)) => {
+            handle_doctor()?;
+        }
+        _ if matches!(args.command, Some(Commands::Check { .. })) => {
+            let fix = matches!(args.command, Some(Commands::Check { fix: true }));
+            set_no_create(true);
+            handle_check(CheckOptions {
+                all_trash: args.all,
+                fix,
+            })?;
+        }
+        _ if args.commit_empty => {
+            handle_commit_empty()?;
+        }
+        _ if args.cancel_empty => {
+            handle_cancel_empty()?;
+        }
+        _ if args.trashinfo_lint => {
+            set_no_create(true);
+            handle_trashinfo_lint(args.all)?;
+        }
+        _ if args.restore || args.restore_latest || !args.put_back.is_empty() => {
+            let opts = RestoreOptions {
+                all_trash: args.all,
+                trash_dir: args.trash_dir.clone(),
+                porcelain: args.porcelain,
+                fuzzy_pair: args.fuzzy_pair,
+                limit_rate: args.limit_rate,
+                copy_out: args.copy_out,
+                print0: args.print0,
+                confirm: args.confirm_restore && !args.no_confirm,
+            };
+            if args.restore_latest {
+                handle_restore_latest(opts)?;
+            } else if !args.put_back.is_empty() {
+                handle_put_back(&args.put_back, opts)?;
+            } else if !args.name.is_empty() {
+                handle_restore_names(&args.name, opts)?;
+            } else if !args.glob.is_empty() {
+                handle_restore_glob(&args.glob, opts)?;
+            } else if !args.files.is_empty() {
+                handle_restore_paths(&args.files, opts)?;
+            } else if let Some(Commands::UI(skim_options)) = args.command {
+                handle_interactive_restore(opts, skim_options)?;
+            }
+        }
+        _ if args.purge => {
+            handle_purge_paths(
+                &args.files,
+                PurgeOptions {
+                    no_confirm: args.no_confirm,
+                    porcelain: args.porcelain,
+                },
+            )?;
+        }
+        _ if args.purge_by_date_range => {
+            handle_empty_trash(EmptyTrashOptions {
+                all_trash: args.all,
+                trash_dir: args.trash_dir,
+                no_confirm: args.no_confirm,
+                display: false,
+                long_format: false,
+                porcelain: args.porcelain,
+                tty,
+                filter: EntryFilter {
+                    deleted_since: args.since,
+                    deleted_until: args.until,
+                    ..Default::default()
+                },
+                grace: None,
+                dry_run: args.dry_run,
+            })?;
         }
-        _ if args.restore => {
-            if let Some(Commands::UI(skim_options)) = args.command {
-                handle_interactive_restore(args.all, skim_options)?;
+        _ if !args.files.is_empty() || args.stdin || args.stdin0 => {
+            // Only ask about large directories when someone is there to answer.
+            let ask_about_large_dirs = !args.no_confirm && !args.force && !args.dry_run && io::stdin().is_terminal();
+            let mut files = args.files.clone();
+            if args.stdin || args.stdin0 {
+                files.extend(read_file_args(&mut io::stdin().lock(), args.stdin0)?);
             }
+            handle_move_to_trash(
+                &files,
+                &TrashOptions {
+                    porcelain: args.porcelain,
+                    path_encoding: match args.info_encoding.as_deref() {
+                        Some("raw") => PathEncoding::Raw,
+                        _ => PathEncoding::for_tool(&args.trash_info_compat).unwrap_or_default(),
+                    },
+                    max_collisions: args.max_collisions,
+                    interactive: args.interactive,
+                    confirm_larger_than: ask_about_large_dirs.then_some(args.confirm_larger_than),
+                    recursive: args.recursive,
+                    record_mode: args.record_mode,
+                    copy_fallback: args.copy_fallback,
+                    limit_rate: args.limit_rate,
+                    copy: args.copy,
+                    allow_protected: args.allow_protected,
+                    dry_run: args.dry_run,
+                    verbose: args.verbose,
+                    protect_tracked: args.protect_tracked,
+                    max_size: args.max_size,
+                },
+            )?;
+        }
+        _ if args.stats => {
+            set_no_create(true);
+            handle_stats(
+                &mut open_output(args.output.as_deref())?,
+                StatsOptions {
+                    all_trash: args.all,
+                    porcelain: args.porcelain,
+                    watch: args.watch.map(|secs| Duration::from_secs(secs.max(1))),
+                },
+            )?;
+        }
+        _ if args.du => {
+            set_no_create(true);
+            handle_disk_usage(
+                &mut open_output(args.output.as_deref())?,
+                DiskUsageOptions {
+                    all_trash: args.all,
+                    bytes: args.bytes,
+                },
+            )?;
+        }
+        _ if args.count => {
+            set_no_create(true);
+            handle_count(&mut open_output(args.output.as_deref())?, args.all)?;
         }
         _ if args.empty || args.no_confirm => {
             handle_empty_trash(EmptyTrashOptions {
                 all_trash: args.all,
+                trash_dir: args.trash_dir,
                 no_confirm: args.no_confirm,
                 display: args.display,
                 long_format: args.long,
+                porcelain: args.porcelain,
+                tty,
+                filter: EntryFilter {
+                    larger_than: args.larger_than,
+                    older_than: args.older_than,
+                    ..Default::default()
+                },
+                grace: args.grace,
+                dry_run: args.dry_run,
             })?;
         }
         _ => {
-            handle_display_trash(args.all, args.long)?;
+            set_no_create(true);
+            // The pager shows our output on the same stdout, so colors and layout are still decided by it.
+            let use_pager = match args.pager.as_deref() {
+                Some("always") => true,
+                Some("auto") => tty,
+                _ => false,
+            };
+            let pager = if use_pager { Pager::from_env()? } else { None };
+            let mut writer: Box<dyn Write> = match pager {
+                Some(pager) => Box::new(pager),
+                None => open_output(args.output.as_deref())?,
+            };
+            handle_display_trash(
+                &mut writer,
+                DisplayTrashOptions {
+                    all_trash: args.all,
+                    trash_dir: args.trash_dir,
+                    long_format: args.long,
+                    porcelain: args.porcelain,
+                    tty,
+                    order: ListOrder {
+                        sort: args.sort.as_deref().and_then(SortKey::from_name),
+                        reverse: args.reverse,
+                    },
+                    show_trash_type: args.show_trash_type,
+                    print_original: args.print_original,
+                    bytes: args.bytes,
+                },
+            )?;
         }
     }
 
     Ok(())
 }
+
+/// Opens the destination for listing, stats, count or disk usage output: the `--output` file (truncated) if given, otherwise stdout.
+#[cfg(unix)]
+fn open_output(path: Option<&Path>) -> Result<Box<dyn Write>, AppError> {
+    match path {
+        Some(path) => {
+            let file = File::create(path).map_err(|source| AppError::OutputFile {
+                path: path.to_path_buf(),
+                source,
+            })?;
+            Ok(Box::new(BufWriter::new(file)))
+        }
+        None => Ok(Box::new(io::stdout())),
+    }
+}
+
+#[cfg(windows)]
+fn main() {
+    if let Err(e) = recycle_bin::run() {
+        if !matches!(e, recycle_bin::RecycleBinError::Ignorable) {
+            eprintln!("Error: {}", e);
+        }
+        std::process::exit(e.exit_code())
+    }
+}
@@ -0,0 +1,238 @@
+//! The Windows backend: moves files to the Recycle Bin and lists what it holds, through the shell API.
+//! The Recycle Bin keeps its own records instead of `.trashinfo` files, so restoring and emptying
+//! are left to Explorer.
+
+mod shell;
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use clap::{ArgAction, Parser};
+use humansize::{format_size, BINARY};
+use thiserror::Error;
+
+use shell::RecycledItem;
+
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+/// The error type of the Windows backend.
+#[derive(Error, Debug)]
+pub enum RecycleBinError {
+    /// Represents an I/O error with context (the path that caused it).
+    #[error("I/O error for '{path}': {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    /// A generic I/O error without a specific path.
+    #[error("I/O error: {0}")]
+    GenericIo(#[from] io::Error),
+
+    /// `SHFileOperationW` refused to move an item to the Recycle Bin.
+    #[error("Cannot move '{path}' to the Recycle Bin (shell error {code:#x})")]
+    Recycle { path: PathBuf, code: i32 },
+
+    /// The shell API failed, e.g. while enumerating the Recycle Bin.
+    #[error("Shell error: {0}")]
+    Shell(#[from] windows::core::Error),
+
+    /// The command-line arguments could not be parsed.
+    #[error("{0}")]
+    InvalidArgument(String),
+
+    /// Some items failed; they have already been reported.
+    #[error("")]
+    Ignorable,
+}
+
+impl RecycleBinError {
+    /// Returns the process exit code for this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RecycleBinError::InvalidArgument(_) => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+/// Moves files to the Windows Recycle Bin, or lists what it holds.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Files or directories to move to the Recycle Bin
+    files: Vec<PathBuf>,
+
+    /// Display the contents of the Recycle Bin.
+    #[arg(short = 'd', long, action = ArgAction::SetTrue, conflicts_with = "files")]
+    display: bool,
+
+    /// List the Recycle Bin contents in a long format (deletion date, size and original path).
+    #[arg(short = 'l', long, action = ArgAction::SetTrue, conflicts_with = "files")]
+    long: bool,
+
+    /// Allow trashing directories; without it they are skipped like `rm` does.
+    #[arg(short = 'R', long, action = ArgAction::SetTrue)]
+    recursive: bool,
+}
+
+/// Runs `tt` on Windows.
+pub fn run() -> Result<(), RecycleBinError> {
+    let args = Args::try_parse().map_err(|e| match e.kind() {
+        clap::error::ErrorKind::DisplayHelp | clap::error::ErrorKind::DisplayVersion => {
+            let _ = e.print();
+            std::process::exit(EXIT_SUCCESS)
+        }
+        _ => RecycleBinError::InvalidArgument(e.to_string()),
+    })?;
+
+    if args.display || args.long {
+        let mut items = shell::recycled_items()?;
+        items.sort_by_key(|item| item.deleted_at);
+        return write_listing(&mut io::stdout().lock(), &items, args.long);
+    }
+    if args.files.is_empty() {
+        return Err(RecycleBinError::InvalidArgument(
+            "No files given. Use --help for usage.".to_string(),
+        ));
+    }
+    handle_recycle(&args.files, args.recursive)
+}
+
+/// Moves each of `files` to the Recycle Bin, reporting failures as it goes.
+fn handle_recycle(files: &[PathBuf], recursive: bool) -> Result<(), RecycleBinError> {
+    let mut recycled = Vec::new();
+    let mut failed = 0;
+    for file in files {
+        match recycle_one(file, recursive) {
+            Ok(()) => recycled.push(file.display().to_string()),
+            Err(e) => {
+                eprintln!("Failed to trash '{}': {}", file.display(), e);
+                failed += 1;
+            }
+        }
+    }
+    if !recycled.is_empty() {
+        println!("Trashed: {}", recycled.join(", "));
+    }
+    if failed > 0 {
+        return Err(RecycleBinError::Ignorable);
+    }
+    Ok(())
+}
+
+fn recycle_one(file: &Path, recursive: bool) -> Result<(), RecycleBinError> {
+    let metadata = fs::symlink_metadata(file).map_err(|source| RecycleBinError::Io {
+        path: file.to_path_buf(),
+        source,
+    })?;
+    if metadata.is_dir() && !recursive {
+        return Err(RecycleBinError::InvalidArgument(
+            "it is a directory (use --recursive)".to_string(),
+        ));
+    }
+    shell::recycle(&resolve_path(file)?)
+}
+
+/// Makes `file` absolute without resolving links, since the shell rejects relative paths
+/// and the `\\?\` form that `canonicalize` returns.
+fn resolve_path(file: &Path) -> Result<PathBuf, RecycleBinError> {
+    std::path::absolute(file).map_err(|source| RecycleBinError::Io {
+        path: file.to_path_buf(),
+        source,
+    })
+}
+
+fn write_listing<W: Write>(writer: &mut W, items: &[RecycledItem], long: bool) -> Result<(), RecycleBinError> {
+    for item in items {
+        if long {
+            let date = item
+                .deleted_at
+                .map(|date| format_date(&date))
+                .unwrap_or_else(|| "-".to_string());
+            writeln!(
+                writer,
+                "{}  {:>10}  {}",
+                date,
+                format_size(item.size, BINARY),
+                item.original_path.display()
+            )?;
+        } else {
+            writeln!(writer, "{}", item.original_path.display())?;
+        }
+    }
+    Ok(())
+}
+
+fn format_date(date: &DateTime<Local>) -> String {
+    date.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resolve_path_is_absolute_and_keeps_links() -> Result<(), RecycleBinError> {
+        let resolved = resolve_path(Path::new("notes.txt"))?;
+        assert!(resolved.is_absolute());
+        assert_eq!(resolved, std::env::current_dir()?.join("notes.txt"));
+        assert!(!resolved.to_string_lossy().starts_with(r"\\?\"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_listing() -> Result<(), RecycleBinError> {
+        let items = vec![RecycledItem {
+            original_path: PathBuf::from(r"C:\Users\me\notes.txt"),
+            deleted_at: Local.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).single(),
+            size: 2048,
+        }];
+
+        let mut plain = Vec::new();
+        write_listing(&mut plain, &items, false)?;
+        assert_eq!(String::from_utf8_lossy(&plain), "C:\\Users\\me\\notes.txt\n");
+
+        let mut long = Vec::new();
+        write_listing(&mut long, &items, true)?;
+        assert_eq!(
+            String::from_utf8_lossy(&long),
+            "2024-01-02 03:04:05     2 KiB  C:\\Users\\me\\notes.txt\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_recycle_one_refuses_directories_without_recursive() -> Result<(), RecycleBinError> {
+        let temp_root = tempdir()?;
+        let result = recycle_one(temp_root.path(), false);
+        assert!(matches!(result, Err(RecycleBinError::InvalidArgument(_))));
+        assert!(temp_root.path().exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_recycle_and_list_round_trip() -> Result<(), RecycleBinError> {
+        let temp_root = tempdir()?;
+        let path = temp_root.path().join("tt-round-trip.txt");
+        fs::write(&path, "round trip")?;
+
+        recycle_one(&path, false)?;
+        assert!(!path.exists());
+        let original_path = resolve_path(&path)?;
+        let items = shell::recycled_items()?;
+        assert!(
+            items.iter().any(|item| item.original_path == original_path),
+            "the Recycle Bin should list {}",
+            original_path.display()
+        );
+        Ok(())
+    }
+}
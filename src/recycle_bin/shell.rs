@@ -0,0 +1,156 @@
+use std::ffi::OsString;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, TimeZone};
+use windows::core::{Interface, GUID, PCWSTR, PWSTR};
+use windows::Win32::Foundation::FILETIME;
+use windows::Win32::System::Com::{CoInitializeEx, CoTaskMemFree, CoUninitialize, COINIT_APARTMENTTHREADED};
+use windows::Win32::UI::Shell::PropertiesSystem::PROPERTYKEY;
+use windows::Win32::UI::Shell::{
+    BHID_EnumItems, FOLDERID_RecycleBinFolder, IEnumShellItems, IShellItem, IShellItem2, SHFileOperationW,
+    SHGetKnownFolderItem, FOF_ALLOWUNDO, FOF_NOCONFIRMATION, FOF_NOERRORUI, FOF_SILENT, FO_DELETE, KF_FLAG_DEFAULT,
+    SHFILEOPSTRUCTW, SIGDN_NORMALDISPLAY,
+};
+
+use super::RecycleBinError;
+
+/// `System.Recycle.DeletedFrom`: the directory an item was deleted from.
+const PKEY_DELETED_FROM: PROPERTYKEY = PROPERTYKEY {
+    fmtid: GUID::from_u128(0x9b174b33_40ff_11d2_a27e_00c04fc30871),
+    pid: 2,
+};
+/// `System.Recycle.DateDeleted`: when an item was deleted.
+const PKEY_DATE_DELETED: PROPERTYKEY = PROPERTYKEY {
+    fmtid: GUID::from_u128(0x9b174b33_40ff_11d2_a27e_00c04fc30871),
+    pid: 3,
+};
+/// `System.Size`: the size of an item in bytes.
+const PKEY_SIZE: PROPERTYKEY = PROPERTYKEY {
+    fmtid: GUID::from_u128(0xb725f130_47ef_101a_a5f1_02608c9eebac),
+    pid: 12,
+};
+
+/// Seconds between the `FILETIME` epoch (1601-01-01) and the Unix epoch.
+const FILETIME_UNIX_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// One item in the Recycle Bin, as the shell describes it.
+#[derive(Debug)]
+pub(crate) struct RecycledItem {
+    pub(crate) original_path: PathBuf,
+    pub(crate) deleted_at: Option<DateTime<Local>>,
+    pub(crate) size: u64,
+}
+
+/// Moves `path`, which must be absolute, to the Recycle Bin of its drive.
+pub(crate) fn recycle(path: &Path) -> Result<(), RecycleBinError> {
+    let from = double_nul_terminated(path);
+    let mut operation = SHFILEOPSTRUCTW {
+        wFunc: FO_DELETE,
+        pFrom: PCWSTR(from.as_ptr()),
+        fFlags: (FOF_ALLOWUNDO | FOF_NOCONFIRMATION | FOF_NOERRORUI | FOF_SILENT).0 as u16,
+        ..Default::default()
+    };
+    // SAFETY: `from` outlives the call and is terminated by two NULs, as `pFrom` requires.
+    let code = unsafe { SHFileOperationW(&mut operation) };
+    if code != 0 || operation.fAnyOperationsAborted.as_bool() {
+        return Err(RecycleBinError::Recycle {
+            path: path.to_path_buf(),
+            code,
+        });
+    }
+    Ok(())
+}
+
+/// Encodes `path` for `SHFILEOPSTRUCTW::pFrom`, a list of paths ended by an empty one.
+fn double_nul_terminated(path: &Path) -> Vec<u16> {
+    path.as_os_str().encode_wide().chain([0, 0]).collect()
+}
+
+/// Initializes COM for the current thread for as long as it lives.
+struct ComGuard;
+
+impl ComGuard {
+    fn new() -> Result<Self, RecycleBinError> {
+        // SAFETY: balanced by `CoUninitialize` in `drop`.
+        unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED).ok()? };
+        Ok(ComGuard)
+    }
+}
+
+impl Drop for ComGuard {
+    fn drop(&mut self) {
+        // SAFETY: `new` initialized COM on this thread.
+        unsafe { CoUninitialize() };
+    }
+}
+
+/// Lists the items in the Recycle Bins of all drives by enumerating the shell's Recycle Bin folder.
+pub(crate) fn recycled_items() -> Result<Vec<RecycledItem>, RecycleBinError> {
+    let _com = ComGuard::new()?;
+    let mut items = Vec::new();
+    // SAFETY: plain COM calls on interfaces owned by this function.
+    unsafe {
+        let bin: IShellItem = SHGetKnownFolderItem(&FOLDERID_RecycleBinFolder, KF_FLAG_DEFAULT, None)?;
+        let enumerator: IEnumShellItems = bin.BindToHandler(None, &BHID_EnumItems)?;
+        loop {
+            let mut fetched = [None];
+            // The end of the list is S_FALSE, which leaves `fetched` empty.
+            enumerator.Next(&mut fetched, None)?;
+            let Some(item) = fetched[0].take() else {
+                break;
+            };
+            items.push(describe(&item.cast::<IShellItem2>()?)?);
+        }
+    }
+    Ok(items)
+}
+
+/// Reads the original location, deletion date and size of one Recycle Bin item.
+unsafe fn describe(item: &IShellItem2) -> Result<RecycledItem, RecycleBinError> {
+    let name = take_string(item.GetDisplayName(SIGDN_NORMALDISPLAY)?);
+    let deleted_from = take_string(item.GetString(&PKEY_DELETED_FROM)?);
+    Ok(RecycledItem {
+        original_path: PathBuf::from(deleted_from).join(name),
+        deleted_at: item.GetFileTime(&PKEY_DATE_DELETED).ok().and_then(filetime_to_local),
+        size: item.GetUInt64(&PKEY_SIZE).unwrap_or(0),
+    })
+}
+
+/// Copies a string the shell allocated with `CoTaskMemAlloc`, then frees it.
+unsafe fn take_string(string: PWSTR) -> OsString {
+    let value = OsString::from_wide(string.as_wide());
+    CoTaskMemFree(Some(string.0 as *const _));
+    value
+}
+
+fn filetime_to_local(filetime: FILETIME) -> Option<DateTime<Local>> {
+    let ticks = (i64::from(filetime.dwHighDateTime) << 32) | i64::from(filetime.dwLowDateTime);
+    let seconds = ticks / 10_000_000 - FILETIME_UNIX_EPOCH_OFFSET;
+    let nanos = (ticks % 10_000_000) as u32 * 100;
+    Local.timestamp_opt(seconds, nanos).single()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_double_nul_terminated() {
+        let encoded = double_nul_terminated(Path::new(r"C:\a b.txt"));
+        let expected: Vec<u16> = r"C:\a b.txt".encode_utf16().chain([0, 0]).collect();
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn test_filetime_to_local() {
+        // 2024-01-01T00:00:00Z
+        let ticks: u64 = (1_704_067_200 + FILETIME_UNIX_EPOCH_OFFSET as u64) * 10_000_000;
+        let filetime = FILETIME {
+            dwLowDateTime: ticks as u32,
+            dwHighDateTime: (ticks >> 32) as u32,
+        };
+        let date = filetime_to_local(filetime).expect("a valid date");
+        assert_eq!(date.timestamp(), 1_704_067_200);
+    }
+}
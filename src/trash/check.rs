@@ -0,0 +1,481 @@
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+
+use crate::trash::error::AppError;
+use crate::trash::home_path::display_path;
+use crate::trash::info_file::data_name_for;
+use crate::trash::locations::get_target_trash_dirs;
+use crate::trash::restoring::scan_trash_dirs_strict;
+use crate::trash::spec::{TRASH_FILES_DIR_NAME, TRASH_INFO_DATE_FORMAT, TRASH_INFO_DIR_NAME, TRASH_INFO_SUFFIX};
+use crate::trash::trashing::{build_trash_info_content, determine_info_file_path};
+use crate::trash::url_escape::PathEncoding;
+
+pub struct CheckOptions {
+    pub all_trash: bool,
+    /// Delete orphaned info files and write info files for orphaned items instead of only reporting them.
+    pub fix: bool,
+}
+
+/// The halves of trash entries whose other half is missing, and info files that are corrupt.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct CheckReport {
+    /// `.trashinfo` files whose item is gone from `files/`.
+    pub(crate) info_files: Vec<PathBuf>,
+    /// Items in `files/` without a `.trashinfo` file.
+    pub(crate) items: Vec<PathBuf>,
+    /// Why each unreadable or malformed info file was rejected. These are only reported, never fixed.
+    pub(crate) corrupt: Vec<String>,
+}
+
+/// The names in the `files/` and `info/` directories of a trash, paired up by name only.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct TrashNames {
+    /// Items in `files/` with a matching `.trashinfo` file, i.e. what can be restored.
+    pub(crate) paired: Vec<OsString>,
+    /// Items in `files/` without a `.trashinfo` file.
+    pub(crate) orphaned_items: Vec<OsString>,
+    /// The item names of `.trashinfo` files whose item is gone from `files/`.
+    pub(crate) orphaned_info_files: Vec<OsString>,
+    /// Entries of `info/` that are not `.trashinfo` files at all.
+    pub(crate) other_info_entries: usize,
+}
+
+impl TrashNames {
+    /// Whether neither directory has any entry at all.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.paired.is_empty()
+            && self.orphaned_items.is_empty()
+            && self.orphaned_info_files.is_empty()
+            && self.other_info_entries == 0
+    }
+}
+
+/// Pairs the names in `files/` and `info/` of `trash_dir`. Each list is sorted; a missing directory counts as empty.
+pub(crate) fn pair_trash_names(trash_dir: &Path) -> Result<TrashNames, AppError> {
+    let info_entries = read_dir_names(&trash_dir.join(TRASH_INFO_DIR_NAME))?;
+    let info_entry_count = info_entries.len();
+    let mut described: Vec<OsString> = info_entries
+        .iter()
+        .filter(|name| name.to_string_lossy().ends_with(TRASH_INFO_SUFFIX))
+        .filter_map(|name| data_name_for(name).map(|data_name| data_name.to_os_string()))
+        .collect();
+    described.sort();
+    let mut data_names = read_dir_names(&trash_dir.join(TRASH_FILES_DIR_NAME))?;
+    data_names.sort();
+
+    let data_set: HashSet<&OsString> = data_names.iter().collect();
+    let described_set: HashSet<&OsString> = described.iter().collect();
+    let (paired, orphaned_items): (Vec<OsString>, Vec<OsString>) = data_names
+        .iter()
+        .cloned()
+        .partition(|name| described_set.contains(name));
+    let orphaned_info_files = described
+        .iter()
+        .filter(|name| !data_set.contains(name))
+        .cloned()
+        .collect();
+    Ok(TrashNames {
+        paired,
+        orphaned_items,
+        orphaned_info_files,
+        other_info_entries: info_entry_count - described.len(),
+    })
+}
+
+impl CheckReport {
+    fn is_empty(&self) -> bool {
+        self.info_files.is_empty() && self.items.is_empty() && self.corrupt.is_empty()
+    }
+}
+
+/// Reports the orphaned info files and items of the target trash directories, and repairs them with `fix`.
+/// Without `fix`, fails with `AppError::Ignorable` when any orphan was found.
+pub fn handle_check(opts: CheckOptions) -> Result<(), AppError> {
+    let trash_dirs = get_target_trash_dirs(opts.all_trash)?;
+    let mut writer = io::stdout();
+    let mut found_problems = false;
+    for trash_dir in trash_dirs {
+        let report = find_orphans(&trash_dir)?;
+        found_problems |= !report.is_empty();
+        write_check_report(&mut writer, &trash_dir, &report, opts.fix)?;
+        if opts.fix {
+            fix_orphans(&trash_dir, &report)?;
+        }
+    }
+    if found_problems && !opts.fix {
+        return Err(AppError::Ignorable);
+    }
+    Ok(())
+}
+
+fn write_check_report<W: Write>(
+    writer: &mut W,
+    trash_dir: &Path,
+    report: &CheckReport,
+    fix: bool,
+) -> Result<(), AppError> {
+    for info_file in &report.info_files {
+        let action = if fix { " (deleted)" } else { "" };
+        writeln!(writer, "info without item: {}{}", display_path(info_file), action)?;
+    }
+    for item in &report.items {
+        let action = if fix { " (info written)" } else { "" };
+        writeln!(writer, "item without info: {}{}", display_path(item), action)?;
+    }
+    for message in &report.corrupt {
+        writeln!(writer, "corrupt info: {}", message)?;
+    }
+    writeln!(
+        writer,
+        "{}: {}, {}, {}",
+        display_path(trash_dir),
+        count_of(report.info_files.len(), "orphaned info file"),
+        count_of(report.items.len(), "orphaned item"),
+        count_of(report.corrupt.len(), "corrupt info file")
+    )?;
+    Ok(())
+}
+
+/// Formats `count` followed by `noun`, adding an `s` unless there is exactly one.
+pub(crate) fn count_of(count: usize, noun: &str) -> String {
+    let suffix = if count == 1 { "" } else { "s" };
+    format!("{} {}{}", count, noun, suffix)
+}
+
+/// Pairs the `info/` and `files/` directories of `trash_dir` and collects what is left unpaired.
+/// An info file that cannot be parsed still counts as the info of its item, so it is never
+/// deleted; one whose `Path` has a malformed percent escape is reported as corrupt.
+pub(crate) fn find_orphans(trash_dir: &Path) -> Result<CheckReport, AppError> {
+    let scan = scan_trash_dirs_strict(&[trash_dir.to_path_buf()]);
+    let corrupt = scan.errors.iter().map(ToString::to_string).collect();
+    let mut info_files: Vec<PathBuf> = scan
+        .entries
+        .into_iter()
+        .filter(|entry| entry.trashed_path.symlink_metadata().is_err())
+        .map(|entry| entry.info_path)
+        .collect();
+    info_files.sort();
+
+    let files_dir = trash_dir.join(TRASH_FILES_DIR_NAME);
+    let items = pair_trash_names(trash_dir)?
+        .orphaned_items
+        .into_iter()
+        .map(|name| files_dir.join(name))
+        .collect();
+
+    Ok(CheckReport {
+        info_files,
+        items,
+        corrupt,
+    })
+}
+
+fn read_dir_names(dir: &Path) -> Result<Vec<OsString>, AppError> {
+    match fs::read_dir(dir) {
+        Ok(entries) => Ok(entries.filter_map(Result::ok).map(|entry| entry.file_name()).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(source) => Err(AppError::Io {
+            path: dir.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+/// Deletes the orphaned info files and gives each orphaned item a minimal info file, so it can be restored again.
+/// The original location of such an item is unknown; it is recorded as being directly in the
+/// directory the trash belongs to (the mount point for `$topdir` trashes, `$HOME` otherwise).
+fn fix_orphans(trash_dir: &Path, report: &CheckReport) -> Result<(), AppError> {
+    for info_file in &report.info_files {
+        fs::remove_file(info_file).map_err(|source| AppError::Io {
+            path: info_file.clone(),
+            source,
+        })?;
+    }
+    if report.items.is_empty() {
+        return Ok(());
+    }
+
+    let original_dir = owning_dir(trash_dir)
+        .ok_or_else(|| AppError::Message(format!("Could not determine where '{}' belongs", trash_dir.display())))?;
+    let deletion_date = Local::now().format(TRASH_INFO_DATE_FORMAT).to_string();
+    let info_dir = trash_dir.join(TRASH_INFO_DIR_NAME);
+    for item in &report.items {
+        let Some(name) = item.file_name() else {
+            continue;
+        };
+        let content = build_trash_info_content(
+            &original_dir.join(name),
+            &deletion_date,
+            PathEncoding::TrashTool,
+            None,
+            None,
+        );
+        let info_path = determine_info_file_path(item, &info_dir);
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|source| AppError::Io {
+                path: info_path,
+                source,
+            })?;
+    }
+    Ok(())
+}
+
+/// The directory a trash belongs to: `$topdir` for `$topdir/.Trash-$uid` and `$topdir/.Trash/$uid`, `$HOME` otherwise.
+fn owning_dir(trash_dir: &Path) -> Option<PathBuf> {
+    let is_named = |path: &Path, matches: &dyn Fn(&str) -> bool| {
+        path.file_name().and_then(|name| name.to_str()).is_some_and(matches)
+    };
+    if is_named(trash_dir, &|name| name.starts_with(".Trash-")) {
+        return trash_dir.parent().map(Path::to_path_buf);
+    }
+    if let Some(shared) = trash_dir
+        .parent()
+        .filter(|parent| is_named(parent, &|name| name == ".Trash"))
+    {
+        return shared.parent().map(Path::to_path_buf);
+    }
+    dirs::home_dir()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trash::restoring::find_trash_entries_in_dirs;
+    use tempfile::tempdir;
+
+    fn write_info(trash_dir: &Path, name: &str, original: &str) -> Result<(), AppError> {
+        fs::write(
+            trash_dir
+                .join(TRASH_INFO_DIR_NAME)
+                .join(format!("{}{}", name, TRASH_INFO_SUFFIX)),
+            format!("[Trash Info]\nPath={}\nDeletionDate=2024-01-01T12:00:00\n", original),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_pair_trash_names() -> Result<(), AppError> {
+        struct TestCase {
+            description: &'static str,
+            files: &'static [&'static str],
+            info_files: &'static [&'static str],
+            paired: &'static [&'static str],
+            orphaned_items: &'static [&'static str],
+            orphaned_info_files: &'static [&'static str],
+            is_empty: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                description: "empty",
+                files: &[],
+                info_files: &[],
+                paired: &[],
+                orphaned_items: &[],
+                orphaned_info_files: &[],
+                is_empty: true,
+            },
+            TestCase {
+                description: "paired",
+                files: &["a.txt", "b.txt"],
+                info_files: &["a.txt.trashinfo", "b.txt.trashinfo"],
+                paired: &["a.txt", "b.txt"],
+                orphaned_items: &[],
+                orphaned_info_files: &[],
+                is_empty: false,
+            },
+            TestCase {
+                description: "items without info files",
+                files: &["a.txt", "b.txt", "c.txt"],
+                info_files: &["a.txt.trashinfo"],
+                paired: &["a.txt"],
+                orphaned_items: &["b.txt", "c.txt"],
+                orphaned_info_files: &[],
+                is_empty: false,
+            },
+            TestCase {
+                description: "only stale info files",
+                files: &[],
+                info_files: &["gone.txt.trashinfo", "notes"],
+                paired: &[],
+                orphaned_items: &[],
+                orphaned_info_files: &["gone.txt"],
+                is_empty: false,
+            },
+            TestCase {
+                description: "only a stray entry in info",
+                files: &[],
+                info_files: &["notes"],
+                paired: &[],
+                orphaned_items: &[],
+                orphaned_info_files: &[],
+                is_empty: false,
+            },
+            TestCase {
+                description: "both kinds of orphans",
+                files: &["a.txt", "stray.bin"],
+                info_files: &["a.txt.trashinfo", "gone.txt.trashinfo"],
+                paired: &["a.txt"],
+                orphaned_items: &["stray.bin"],
+                orphaned_info_files: &["gone.txt"],
+                is_empty: false,
+            },
+        ];
+
+        let names = |names: &[&str]| names.iter().map(OsString::from).collect::<Vec<_>>();
+        for case in test_cases {
+            let trash_root = tempdir()?;
+            let files_dir = trash_root.path().join(TRASH_FILES_DIR_NAME);
+            let info_dir = trash_root.path().join(TRASH_INFO_DIR_NAME);
+            fs::create_dir_all(&files_dir)?;
+            fs::create_dir_all(&info_dir)?;
+            for name in case.files {
+                fs::write(files_dir.join(name), "")?;
+            }
+            for name in case.info_files {
+                fs::write(info_dir.join(name), "")?;
+            }
+            let result = pair_trash_names(trash_root.path())?;
+            assert_eq!(result.paired, names(case.paired), "{}", case.description);
+            assert_eq!(
+                result.orphaned_items,
+                names(case.orphaned_items),
+                "{}",
+                case.description
+            );
+            assert_eq!(
+                result.orphaned_info_files,
+                names(case.orphaned_info_files),
+                "{}",
+                case.description
+            );
+            assert_eq!(result.is_empty(), case.is_empty, "{}", case.description);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_of() {
+        assert_eq!(count_of(0, "orphaned item"), "0 orphaned items");
+        assert_eq!(count_of(1, "orphaned info file"), "1 orphaned info file");
+        assert_eq!(count_of(2, "corrupt info file"), "2 corrupt info files");
+    }
+
+    #[test]
+    fn test_find_and_fix_orphans() -> Result<(), AppError> {
+        let topdir = tempdir()?;
+        let trash_dir = topdir.path().join(".Trash-1000");
+        let files_dir = trash_dir.join(TRASH_FILES_DIR_NAME);
+        let info_dir = trash_dir.join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(&files_dir)?;
+        fs::create_dir_all(&info_dir)?;
+
+        // A complete entry, an info file without its item and an item without its info.
+        fs::write(files_dir.join("kept.txt"), "kept")?;
+        write_info(&trash_dir, "kept.txt", "/data/kept.txt")?;
+        write_info(&trash_dir, "gone.txt", "/data/gone.txt")?;
+        fs::create_dir(files_dir.join("stray dir"))?;
+        // An unparseable info file still pairs with its item, and is only reported as corrupt.
+        fs::write(files_dir.join("odd.txt"), "odd")?;
+        fs::write(info_dir.join("odd.txt.trashinfo"), "garbage")?;
+        let odd_corrupt = vec![format!(
+            "Failed to parse trash info file '{}': missing the '[Trash Info]' header",
+            info_dir.join("odd.txt.trashinfo").display()
+        )];
+
+        let report = find_orphans(&trash_dir)?;
+        assert_eq!(
+            report,
+            CheckReport {
+                info_files: vec![info_dir.join("gone.txt.trashinfo")],
+                items: vec![files_dir.join("stray dir")],
+                corrupt: odd_corrupt.clone(),
+            }
+        );
+
+        fix_orphans(&trash_dir, &report)?;
+        assert!(!info_dir.join("gone.txt.trashinfo").exists());
+        assert!(info_dir.join("odd.txt.trashinfo").exists());
+        assert_eq!(
+            find_orphans(&trash_dir)?,
+            CheckReport {
+                corrupt: odd_corrupt,
+                ..Default::default()
+            }
+        );
+
+        let entries = find_trash_entries_in_dirs(std::slice::from_ref(&trash_dir));
+        let stray = entries
+            .iter()
+            .find(|entry| entry.trashed_path == files_dir.join("stray dir"))
+            .expect("the orphaned item has an info file");
+        assert_eq!(stray.original_path, topdir.path().join("stray dir"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_orphans_reports_corrupt_path() -> Result<(), AppError> {
+        let topdir = tempdir()?;
+        let trash_dir = topdir.path().join(".Trash-1000");
+        fs::create_dir_all(trash_dir.join(TRASH_FILES_DIR_NAME))?;
+        fs::create_dir_all(trash_dir.join(TRASH_INFO_DIR_NAME))?;
+        fs::write(trash_dir.join(TRASH_FILES_DIR_NAME).join("bad.txt"), "bad")?;
+        write_info(&trash_dir, "bad.txt", "/data/bad%GG.txt")?;
+
+        let report = find_orphans(&trash_dir)?;
+        assert!(report.info_files.is_empty());
+        assert!(report.items.is_empty());
+        assert_eq!(report.corrupt.len(), 1);
+        assert!(
+            report.corrupt[0].contains("malformed percent escape '%GG'"),
+            "{}",
+            report.corrupt[0]
+        );
+
+        // A corrupt info file still pairs with its item, so fixing leaves both alone.
+        fix_orphans(&trash_dir, &report)?;
+        assert!(trash_dir.join(TRASH_INFO_DIR_NAME).join("bad.txt.trashinfo").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_check_report() -> Result<(), AppError> {
+        let report = CheckReport {
+            info_files: vec![PathBuf::from("/t/info/a.trashinfo")],
+            items: vec![PathBuf::from("/t/files/b")],
+            corrupt: vec!["bad escape".to_string()],
+        };
+        let mut output = Vec::new();
+        write_check_report(&mut output, Path::new("/t"), &report, true)?;
+        assert_eq!(
+            String::from_utf8(output)?,
+            "info without item: /t/info/a.trashinfo (deleted)\n\
+             item without info: /t/files/b (info written)\n\
+             corrupt info: bad escape\n\
+             /t: 1 orphaned info file, 1 orphaned item, 1 corrupt info file\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_owning_dir() {
+        assert_eq!(
+            owning_dir(Path::new("/mnt/usb/.Trash-1000")),
+            Some(PathBuf::from("/mnt/usb"))
+        );
+        assert_eq!(
+            owning_dir(Path::new("/mnt/usb/.Trash/1000")),
+            Some(PathBuf::from("/mnt/usb"))
+        );
+        assert_eq!(owning_dir(Path::new("/home/u/.local/share/Trash")), dirs::home_dir());
+    }
+}
@@ -1,53 +1,496 @@
+use std::env;
 use std::path::Path;
 
-use colored::{control, ColoredString, Colorize};
+use colored::{control, Color, ColoredString, Colorize};
+use once_cell::sync::OnceCell;
 
 use super::file_type::{get_file_type, FileType};
 
+/// The environment variable naming the color theme to use.
+const TRASH_TOOL_THEME: &str = "TRASH_TOOL_THEME";
+
+/// The `ls` color configuration, e.g. `di=01;34:ex=01;32:*.tar=01;31`.
+const LS_COLORS_VAR: &str = "LS_COLORS";
+
+/// A foreground color plus emphasis, applied to one kind of text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Style {
+    color: Option<Color>,
+    bold: bool,
+    dimmed: bool,
+}
+
+impl Style {
+    const PLAIN: Style = Style::new(None);
+
+    const fn new(color: Option<Color>) -> Self {
+        Style {
+            color,
+            bold: false,
+            dimmed: false,
+        }
+    }
+
+    const fn bold(self) -> Self {
+        Style { bold: true, ..self }
+    }
+
+    const fn dimmed(self) -> Self {
+        Style { dimmed: true, ..self }
+    }
+
+    fn paint(self, text: &str) -> ColoredString {
+        let mut painted = match self.color {
+            Some(color) => text.color(color),
+            None => text.normal(),
+        };
+        if self.bold {
+            painted = painted.bold();
+        }
+        if self.dimmed {
+            painted = painted.dimmed();
+        }
+        painted
+    }
+}
+
+/// A coordinated palette used by all `colorize_*` functions.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Theme {
+    name: &'static str,
+    trash_directory: Style,
+    directory: Style,
+    symlink: Style,
+    fifo: Style,
+    socket: Style,
+    device: Style,
+    executable: Style,
+    archive: Style,
+    config: Style,
+    document: Style,
+    image: Style,
+    video: Style,
+    music: Style,
+    database: Style,
+    font: Style,
+    source: Style,
+    other: Style,
+    user_group: Style,
+    file_size: Style,
+    modified: Style,
+    mode_dir: Style,
+    mode_read: Style,
+    mode_write: Style,
+    mode_exec: Style,
+    mode_unset: Style,
+}
+
+const fn color(color: Color) -> Style {
+    Style::new(Some(color))
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Style {
+    Style::new(Some(Color::TrueColor { r, g, b }))
+}
+
+/// The original colors of this tool.
+static DEFAULT_THEME: Theme = Theme {
+    name: "default",
+    trash_directory: color(Color::White),
+    directory: color(Color::Blue).bold(),
+    symlink: color(Color::Cyan).bold(),
+    fifo: color(Color::Yellow),
+    socket: color(Color::Magenta).bold(),
+    device: color(Color::Yellow).bold(),
+    executable: color(Color::Green).bold(),
+    archive: color(Color::Red).bold(),
+    config: color(Color::Yellow).bold(),
+    document: Style::PLAIN,
+    image: color(Color::Magenta).bold(),
+    video: color(Color::Magenta).bold(),
+    music: color(Color::Cyan).bold(),
+    database: color(Color::Yellow).dimmed(),
+    font: color(Color::BrightMagenta),
+    source: color(Color::BrightCyan),
+    other: Style::PLAIN,
+    user_group: color(Color::Yellow).bold(),
+    file_size: color(Color::Green).bold(),
+    modified: color(Color::Blue),
+    mode_dir: color(Color::Blue),
+    mode_read: color(Color::Yellow),
+    mode_write: color(Color::Red),
+    mode_exec: color(Color::Green),
+    mode_unset: Style::PLAIN.dimmed(),
+};
+
+/// The Solarized accent colors, in 24-bit color.
+static SOLARIZED_THEME: Theme = Theme {
+    name: "solarized",
+    trash_directory: rgb(0x93, 0xa1, 0xa1),
+    directory: rgb(0x26, 0x8b, 0xd2).bold(),
+    symlink: rgb(0x2a, 0xa1, 0x98).bold(),
+    fifo: rgb(0xb5, 0x89, 0x00),
+    socket: rgb(0xd3, 0x36, 0x82).bold(),
+    device: rgb(0xcb, 0x4b, 0x16).bold(),
+    executable: rgb(0x85, 0x99, 0x00).bold(),
+    archive: rgb(0xdc, 0x32, 0x2f),
+    config: rgb(0xb5, 0x89, 0x00),
+    document: rgb(0x83, 0x94, 0x96),
+    image: rgb(0xd3, 0x36, 0x82),
+    video: rgb(0x6c, 0x71, 0xc4),
+    music: rgb(0x2a, 0xa1, 0x98),
+    database: rgb(0xcb, 0x4b, 0x16),
+    font: rgb(0x6c, 0x71, 0xc4).bold(),
+    source: rgb(0x85, 0x99, 0x00),
+    other: rgb(0x83, 0x94, 0x96),
+    user_group: rgb(0xb5, 0x89, 0x00),
+    file_size: rgb(0x85, 0x99, 0x00),
+    modified: rgb(0x26, 0x8b, 0xd2),
+    mode_dir: rgb(0x26, 0x8b, 0xd2),
+    mode_read: rgb(0xb5, 0x89, 0x00),
+    mode_write: rgb(0xcb, 0x4b, 0x16),
+    mode_exec: rgb(0x85, 0x99, 0x00),
+    mode_unset: rgb(0x58, 0x6e, 0x75),
+};
+
+/// No colors; important kinds of entries stand out in bold instead.
+static MONOCHROME_BOLD_THEME: Theme = Theme {
+    name: "monochrome-bold",
+    trash_directory: Style::PLAIN.bold(),
+    directory: Style::PLAIN.bold(),
+    symlink: Style::PLAIN,
+    fifo: Style::PLAIN,
+    socket: Style::PLAIN,
+    device: Style::PLAIN.bold(),
+    executable: Style::PLAIN.bold(),
+    archive: Style::PLAIN.bold(),
+    config: Style::PLAIN,
+    document: Style::PLAIN,
+    image: Style::PLAIN,
+    video: Style::PLAIN,
+    music: Style::PLAIN,
+    database: Style::PLAIN.dimmed(),
+    font: Style::PLAIN,
+    source: Style::PLAIN,
+    other: Style::PLAIN,
+    user_group: Style::PLAIN,
+    file_size: Style::PLAIN.bold(),
+    modified: Style::PLAIN,
+    mode_dir: Style::PLAIN.bold(),
+    mode_read: Style::PLAIN,
+    mode_write: Style::PLAIN.bold(),
+    mode_exec: Style::PLAIN,
+    mode_unset: Style::PLAIN.dimmed(),
+};
+
+/// Only directories and executables are colored; everything else is plain.
+static MINIMAL_THEME: Theme = Theme {
+    name: "minimal",
+    trash_directory: Style::PLAIN,
+    directory: color(Color::Blue),
+    symlink: Style::PLAIN,
+    fifo: Style::PLAIN,
+    socket: Style::PLAIN,
+    device: Style::PLAIN,
+    executable: color(Color::Green),
+    archive: Style::PLAIN,
+    config: Style::PLAIN,
+    document: Style::PLAIN,
+    image: Style::PLAIN,
+    video: Style::PLAIN,
+    music: Style::PLAIN,
+    database: Style::PLAIN,
+    font: Style::PLAIN,
+    source: Style::PLAIN,
+    other: Style::PLAIN,
+    user_group: Style::PLAIN,
+    file_size: Style::PLAIN,
+    modified: Style::PLAIN,
+    mode_dir: Style::PLAIN,
+    mode_read: Style::PLAIN,
+    mode_write: Style::PLAIN,
+    mode_exec: Style::PLAIN,
+    mode_unset: Style::PLAIN,
+};
+
+static THEMES: [&Theme; 4] = [&DEFAULT_THEME, &SOLARIZED_THEME, &MONOCHROME_BOLD_THEME, &MINIMAL_THEME];
+
+/// File name colors taken from `LS_COLORS`, set once at startup.
+static LS_COLORS: OnceCell<LsColors> = OnceCell::new();
+
+/// The file name colors of an `LS_COLORS` value. Only the foreground color, bold and dim are
+/// kept; other attributes (backgrounds, underline, blinking) are ignored.
+#[derive(Debug, Default, PartialEq)]
+struct LsColors {
+    directory: Option<Style>,
+    symlink: Option<Style>,
+    fifo: Option<Style>,
+    socket: Option<Style>,
+    block_device: Option<Style>,
+    char_device: Option<Style>,
+    executable: Option<Style>,
+    file: Option<Style>,
+    /// `*suffix` patterns (usually `*.ext`) with lower-cased suffixes, in the order given.
+    suffixes: Vec<(String, Style)>,
+}
+
+impl LsColors {
+    /// Parses a colon-separated list of `key=codes` pairs, skipping entries it does not understand.
+    fn parse(value: &str) -> Self {
+        let mut ls_colors = LsColors::default();
+        for (key, codes) in value.split(':').filter_map(|entry| entry.split_once('=')) {
+            let style = parse_sgr(codes);
+            match key {
+                "di" => ls_colors.directory = Some(style),
+                "ln" => ls_colors.symlink = Some(style),
+                "pi" => ls_colors.fifo = Some(style),
+                "so" => ls_colors.socket = Some(style),
+                "bd" => ls_colors.block_device = Some(style),
+                "cd" => ls_colors.char_device = Some(style),
+                "ex" => ls_colors.executable = Some(style),
+                "fi" => ls_colors.file = Some(style),
+                _ => {
+                    if let Some(suffix) = key.strip_prefix('*').filter(|suffix| !suffix.is_empty()) {
+                        ls_colors.suffixes.push((suffix.to_lowercase(), style));
+                    }
+                }
+            }
+        }
+        ls_colors
+    }
+
+    /// Picks the style for `path` the way `ls` does: by file type first, and by name only for
+    /// regular files that are not executable. Names match case-insensitively, like extensions
+    /// elsewhere in this tool; a later pattern for the same suffix wins.
+    fn style_for(&self, path: &Path) -> Style {
+        let by_type = match get_file_type(path) {
+            FileType::Symlink => self.symlink,
+            FileType::Fifo => self.fifo,
+            FileType::Socket => self.socket,
+            FileType::BlockDevice => self.block_device,
+            FileType::CharDevice => self.char_device,
+            FileType::Directory => self.directory,
+            FileType::Executable => self.executable,
+            _ => None,
+        };
+        if let Some(style) = by_type {
+            return style;
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        self.suffixes
+            .iter()
+            .rev()
+            .find(|(suffix, _)| name.ends_with(suffix.as_str()))
+            .map(|(_, style)| *style)
+            .or(self.file)
+            .unwrap_or(Style::PLAIN)
+    }
+}
+
+/// Converts SGR codes such as `01;34` or `38;5;208` into a `Style`.
+fn parse_sgr(codes: &str) -> Style {
+    let mut style = Style::PLAIN;
+    let mut codes = codes.split(';').map(|code| code.parse::<u8>().ok());
+    while let Some(code) = codes.next() {
+        match code {
+            Some(0) => style = Style::PLAIN,
+            Some(1) => style.bold = true,
+            Some(2) => style.dimmed = true,
+            Some(code @ 30..=37) => style.color = Some(ansi_color(code - 30)),
+            Some(code @ 90..=97) => style.color = Some(ansi_color(code - 90 + 8)),
+            Some(38) => match codes.next().flatten() {
+                Some(5) => style.color = codes.next().flatten().map(color_256),
+                Some(2) => {
+                    if let (Some(Some(r)), Some(Some(g)), Some(Some(b))) = (codes.next(), codes.next(), codes.next()) {
+                        style.color = Some(Color::TrueColor { r, g, b });
+                    }
+                }
+                _ => {}
+            },
+            Some(39) => style.color = None,
+            _ => {}
+        }
+    }
+    style
+}
+
+/// One of the 16 standard terminal colors; 8 to 15 are the bright variants.
+fn ansi_color(index: u8) -> Color {
+    const COLORS: [Color; 16] = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::White,
+        Color::BrightBlack,
+        Color::BrightRed,
+        Color::BrightGreen,
+        Color::BrightYellow,
+        Color::BrightBlue,
+        Color::BrightMagenta,
+        Color::BrightCyan,
+        Color::BrightWhite,
+    ];
+    COLORS[usize::from(index % 16)]
+}
+
+/// A color of the xterm 256-color palette, as a standard color or 24-bit RGB.
+fn color_256(index: u8) -> Color {
+    match index {
+        0..=15 => ansi_color(index),
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let index = index - 16;
+            Color::TrueColor {
+                r: level(index / 36),
+                g: level(index / 6 % 6),
+                b: level(index % 6),
+            }
+        }
+        232..=255 => {
+            let gray = 8 + (index - 232) * 10;
+            Color::TrueColor {
+                r: gray,
+                g: gray,
+                b: gray,
+            }
+        }
+    }
+}
+
+/// Colors file names from `LS_COLORS` for the rest of the run instead of by the theme's
+/// file types, if the variable is set and not empty.
+pub fn apply_ls_colors_from_env() {
+    if let Some(ls_colors) = ls_colors_from_env() {
+        let _ = LS_COLORS.set(ls_colors);
+    }
+}
+
+fn ls_colors_from_env() -> Option<LsColors> {
+    env::var(LS_COLORS_VAR)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| LsColors::parse(&value))
+}
+
+/// The theme chosen with `TRASH_TOOL_THEME`, set once at startup and shared by every thread,
+/// including the ones skim renders the restore UI's previews on.
+static ACTIVE_THEME: OnceCell<&'static Theme> = OnceCell::new();
+
+/// Looks up a built-in theme by name.
+fn find_theme(name: &str) -> Option<&'static Theme> {
+    THEMES.iter().copied().find(|theme| theme.name == name)
+}
+
+fn active_theme() -> &'static Theme {
+    ACTIVE_THEME.get().copied().unwrap_or(&DEFAULT_THEME)
+}
+
+/// Selects the color theme named by `TRASH_TOOL_THEME` for the rest of the run.
+/// An unknown name is reported and the default theme is kept.
+pub fn apply_theme_from_env() {
+    let Ok(name) = env::var(TRASH_TOOL_THEME) else {
+        return;
+    };
+    match find_theme(name.trim()) {
+        Some(theme) => {
+            let _ = ACTIVE_THEME.set(theme);
+        }
+        None => {
+            let known: Vec<&str> = THEMES.iter().map(|theme| theme.name).collect();
+            eprintln!(
+                "warning: Unknown {} '{}'; expected one of: {}.",
+                TRASH_TOOL_THEME,
+                name,
+                known.join(", ")
+            );
+        }
+    }
+}
+
+/// The environment variable that, when set to anything but an empty string, disables colors
+/// unless they are asked for explicitly (see <https://no-color.org>).
+const NO_COLOR: &str = "NO_COLOR";
+
 /// Applies the global color setting based on the user's choice from CLI arguments.
 /// This function centralizes control over the `colored` crate's behavior.
-pub fn apply_color_setting(color_choice: &str) {
+/// `tty_override` (from `--assume-tty`/`--no-tty`) replaces TTY detection for "auto".
+pub fn apply_color_setting(color_choice: &str, tty_override: Option<bool>) {
+    if let Some(enabled) = color_override(color_choice, tty_override, no_color_from_env()) {
+        control::set_override(enabled);
+    }
+}
+
+fn no_color_from_env() -> bool {
+    env::var_os(NO_COLOR).is_some_and(|value| !value.is_empty())
+}
+
+/// Decides whether colors must be forced on or off, or `None` to leave it to the `colored`
+/// crate's own TTY detection. `--color=always` and `--color=never` win over everything;
+/// with "auto", a non-empty `NO_COLOR` turns colors off even when output is forced to be a TTY.
+fn color_override(color_choice: &str, tty_override: Option<bool>, no_color: bool) -> Option<bool> {
     match color_choice {
-        "always" => control::set_override(true),
-        "never" => control::set_override(false),
-        "auto" | _ => {
-            // "auto" is the default behavior of the `colored` crate, which checks if the output is a TTY.
-            // No override is needed in this case.
-        }
+        "always" => Some(true),
+        "never" => Some(false),
+        _ if no_color => Some(false),
+        // "auto" is the default behavior of the `colored` crate, which checks if the output is a TTY.
+        // No override is needed in this case unless TTY detection itself is overridden.
+        _ => tty_override,
     }
 }
 
 /// Colorizes a string representing a trash directory
 pub fn colorize_trash_directory(name: &str) -> ColoredString {
-    name.white()
+    active_theme().trash_directory.paint(name)
 }
 
-/// Colorizes the path based on its file type.
+/// Colorizes the path based on its file type, or on `LS_COLORS` when that is set.
 pub fn colorize_path(filename: &str, path: &Path) -> ColoredString {
-    let file_type = get_file_type(path);
-
-    match file_type {
-        FileType::Directory => filename.blue().bold(),
-        FileType::Executable => filename.green().bold(),
-        FileType::Archive => filename.red().bold(),
-        FileType::Config => filename.yellow().bold(),
-        FileType::Document => filename.normal(),
-        FileType::Image => filename.magenta().bold(),
-        FileType::Video => filename.purple().bold(),
-        FileType::Music => filename.cyan().bold(),
-        FileType::Other => filename.normal(),
+    if let Some(ls_colors) = LS_COLORS.get() {
+        return ls_colors.style_for(path).paint(filename);
     }
+    let theme = active_theme();
+    let style = match get_file_type(path) {
+        FileType::Directory => theme.directory,
+        FileType::Symlink => theme.symlink,
+        FileType::Fifo => theme.fifo,
+        FileType::Socket => theme.socket,
+        FileType::BlockDevice | FileType::CharDevice => theme.device,
+        FileType::Executable => theme.executable,
+        FileType::Archive => theme.archive,
+        FileType::Config => theme.config,
+        FileType::Document => theme.document,
+        FileType::Image => theme.image,
+        FileType::Video => theme.video,
+        FileType::Music => theme.music,
+        FileType::Database => theme.database,
+        FileType::Font => theme.font,
+        FileType::Source => theme.source,
+        FileType::Other => theme.other,
+    };
+    style.paint(filename)
 }
 
 /// Formats and colorizes the file mode (permissions) string.
 #[cfg(unix)]
 pub fn format_mode(mode: u32, is_dir: bool) -> String {
-    let dir = if is_dir { "d".blue() } else { "-".dimmed() };
+    let theme = active_theme();
+    let dir = if is_dir {
+        theme.mode_dir.paint("d")
+    } else {
+        theme.mode_unset.paint("-")
+    };
 
-    let r = "r".yellow();
-    let w = "w".red();
-    let x = "x".green();
-    let dash = "-".dimmed();
+    let r = theme.mode_read.paint("r");
+    let w = theme.mode_write.paint("w");
+    let x = theme.mode_exec.paint("x");
+    let dash = theme.mode_unset.paint("-");
 
     let user_r = if mode & 0o400 != 0 { &r } else { &dash };
     let user_w = if mode & 0o200 != 0 { &w } else { &dash };
@@ -69,17 +512,17 @@ pub fn format_mode(mode: u32, is_dir: bool) -> String {
 
 /// Colorizes a string representing a user or group.
 pub fn colorize_user_group(name: &str) -> ColoredString {
-    name.yellow().bold()
+    active_theme().user_group.paint(name)
 }
 
 /// Colorizes a string representing a file size
 pub fn colorize_file_size(size: &str) -> ColoredString {
-    size.green().bold()
+    active_theme().file_size.paint(size)
 }
 
 /// Colorizes a string representing a modified
 pub fn colorize_modified(modified: &str) -> ColoredString {
-    modified.blue()
+    active_theme().modified.paint(modified)
 }
 
 #[cfg(test)]
@@ -91,6 +534,164 @@ mod tests {
         re.replace_all(s, "").to_string()
     }
 
+    #[test]
+    fn test_default_theme_matches_original_colors() {
+        let theme = &DEFAULT_THEME;
+        assert_eq!(theme.directory.paint("x"), "x".blue().bold());
+        assert_eq!(theme.video.paint("x"), "x".purple().bold());
+        assert_eq!(theme.document.paint("x"), "x".normal());
+        assert_eq!(theme.trash_directory.paint("x"), "x".white());
+        assert_eq!(theme.modified.paint("x"), "x".blue());
+        assert_eq!(theme.mode_unset.paint("-"), "-".dimmed());
+        assert_eq!(theme.database.paint("x"), "x".yellow().dimmed());
+        assert_eq!(theme.symlink.paint("x"), "x".cyan().bold());
+        assert_eq!(theme.source.paint("x"), "x".bright_cyan());
+    }
+
+    #[test]
+    fn test_parse_sgr() {
+        assert_eq!(parse_sgr("01;34"), color(Color::Blue).bold());
+        assert_eq!(parse_sgr("2;91"), color(Color::BrightRed).dimmed());
+        assert_eq!(parse_sgr("38;5;208"), rgb(255, 135, 0));
+        assert_eq!(parse_sgr("38;5;244"), rgb(128, 128, 128));
+        assert_eq!(parse_sgr("38;2;1;2;3"), rgb(1, 2, 3));
+        assert_eq!(parse_sgr("01;32;0"), Style::PLAIN);
+        // Backgrounds and underlines are not kept.
+        assert_eq!(parse_sgr("04;33;41"), color(Color::Yellow));
+        assert_eq!(parse_sgr("garbage"), Style::PLAIN);
+    }
+
+    #[test]
+    fn test_ls_colors_style_for() -> Result<(), std::io::Error> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir()?;
+        let archive = dir.path().join("backup.TAR");
+        let script = dir.path().join("run.sh");
+        let link = dir.path().join("latest");
+        let plain = dir.path().join("notes");
+        std::fs::write(&archive, "")?;
+        std::fs::write(&script, "")?;
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))?;
+        std::os::unix::fs::symlink(&archive, &link)?;
+        std::fs::write(&plain, "")?;
+
+        let ls_colors = LsColors::parse("rs=0:di=01;34:ln=01;36:ex=01;32:*.tar=01;31:*.sh=00;33:*.tar=35:bogus");
+        assert_eq!(ls_colors.style_for(dir.path()), color(Color::Blue).bold());
+        assert_eq!(ls_colors.style_for(&link), color(Color::Cyan).bold());
+        // Executables are colored by type before their name is looked at.
+        assert_eq!(ls_colors.style_for(&script), color(Color::Green).bold());
+        // The later `*.tar` wins, and suffixes match case-insensitively.
+        assert_eq!(ls_colors.style_for(&archive), color(Color::Magenta));
+        assert_eq!(ls_colors.style_for(&plain), Style::PLAIN);
+        assert_eq!(
+            ls_colors.style_for(&archive).paint("backup.TAR"),
+            "backup.TAR".magenta()
+        );
+
+        let with_file = LsColors::parse("fi=02:cd=33");
+        assert_eq!(with_file.style_for(&plain), Style::PLAIN.dimmed());
+        assert_eq!(with_file.style_for(Path::new("/dev/null")), color(Color::Yellow));
+        Ok(())
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_ls_colors_from_env() {
+        env::set_var(LS_COLORS_VAR, "di=01;34:*.zip=01;31");
+        let ls_colors = ls_colors_from_env().expect("LS_COLORS is set");
+        assert_eq!(ls_colors.directory, Some(color(Color::Blue).bold()));
+        assert_eq!(ls_colors.suffixes, vec![(".zip".to_string(), color(Color::Red).bold())]);
+
+        env::set_var(LS_COLORS_VAR, "");
+        assert!(ls_colors_from_env().is_none(), "an empty LS_COLORS keeps the theme");
+        env::remove_var(LS_COLORS_VAR);
+        assert!(ls_colors_from_env().is_none());
+    }
+
+    #[test]
+    fn test_color_override() {
+        struct TestCase {
+            color_choice: &'static str,
+            tty_override: Option<bool>,
+            no_color: bool,
+            expected: Option<bool>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                color_choice: "auto",
+                tty_override: None,
+                no_color: false,
+                expected: None,
+            },
+            TestCase {
+                color_choice: "auto",
+                tty_override: Some(true),
+                no_color: false,
+                expected: Some(true),
+            },
+            TestCase {
+                color_choice: "auto",
+                tty_override: None,
+                no_color: true,
+                expected: Some(false),
+            },
+            TestCase {
+                color_choice: "auto",
+                tty_override: Some(true),
+                no_color: true,
+                expected: Some(false),
+            },
+            TestCase {
+                color_choice: "always",
+                tty_override: None,
+                no_color: true,
+                expected: Some(true),
+            },
+            TestCase {
+                color_choice: "never",
+                tty_override: Some(true),
+                no_color: false,
+                expected: Some(false),
+            },
+        ];
+
+        for case in test_cases {
+            assert_eq!(
+                color_override(case.color_choice, case.tty_override, case.no_color),
+                case.expected,
+                "--color={} tty_override={:?} NO_COLOR={}",
+                case.color_choice,
+                case.tty_override,
+                case.no_color
+            );
+        }
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_apply_color_setting_honors_no_color() {
+        env::set_var(NO_COLOR, "1");
+        assert!(no_color_from_env());
+        apply_color_setting("auto", Some(true));
+        assert!(!control::SHOULD_COLORIZE.should_colorize());
+
+        env::set_var(NO_COLOR, "");
+        assert!(!no_color_from_env(), "an empty NO_COLOR does not count");
+        env::remove_var(NO_COLOR);
+        assert!(!no_color_from_env());
+        control::unset_override();
+    }
+
+    #[test]
+    fn test_find_theme() {
+        for name in ["default", "solarized", "monochrome-bold", "minimal"] {
+            assert_eq!(find_theme(name).map(|theme| theme.name), Some(name));
+        }
+        assert!(find_theme("neon").is_none());
+    }
+
     #[test]
     fn test_format_mode() {
         struct TestCase {
@@ -0,0 +1,125 @@
+use std::io::{BufRead, Write};
+
+use crate::trash::error::AppError;
+
+/// Shows `message` and reads a yes/no answer from `reader`, asking again until it gets one.
+/// An empty answer means yes, as the `[Y/n]` in the prompts says, but end of input means no:
+/// a closed or exhausted stdin is not a user pressing Enter.
+pub(crate) fn confirm_input<W: Write, R: BufRead>(
+    writer: &mut W,
+    reader: &mut R,
+    message: String,
+) -> Result<bool, AppError> {
+    let mut input = String::new();
+    loop {
+        write!(writer, "{}", message)?;
+        writer.flush()?;
+        if reader.read_line(&mut input)? == 0 {
+            writeln!(writer)?;
+            return Ok(false);
+        }
+        let trimmed_input = input.trim().to_lowercase();
+
+        if trimmed_input.is_empty() || trimmed_input == "y" || trimmed_input == "yes" {
+            return Ok(true);
+        } else if trimmed_input == "n" || trimmed_input == "no" {
+            return Ok(false);
+        }
+        // If input is invalid, loop will continue and re-prompt.
+        input.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_confirm_input() {
+        struct TestCase {
+            input: &'static str,
+            expected_result: bool,
+            description: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                input: "\n",
+                expected_result: true,
+                description: "Input empty (default to yes)",
+            },
+            TestCase {
+                input: "y\n",
+                expected_result: true,
+                description: "Input 'y'",
+            },
+            TestCase {
+                input: "yes\n",
+                expected_result: true,
+                description: "Input 'yes'",
+            },
+            TestCase {
+                input: "Y\n",
+                expected_result: true,
+                description: "Input 'Y' (case-insensitive)",
+            },
+            TestCase {
+                input: "n\n",
+                expected_result: false,
+                description: "Input 'n'",
+            },
+            TestCase {
+                input: "no\n",
+                expected_result: false,
+                description: "Input 'no'",
+            },
+        ];
+
+        for case in test_cases {
+            let mut reader = Cursor::new(case.input);
+            let mut writer = Vec::new();
+            let message = "Do you want to empty? [Y/n]: ".to_string();
+
+            let result = confirm_input(&mut writer, &mut reader, message).unwrap();
+
+            assert_eq!(result, case.expected_result, "Failed on: {}", case.description);
+
+            let output = String::from_utf8(writer).unwrap();
+            assert_eq!(output, "Do you want to empty? [Y/n]: ");
+        }
+    }
+
+    #[test]
+    fn test_confirm_input_invalid_then_valid() {
+        let input = "maybe\nyes\n";
+        let mut reader = Cursor::new(input);
+        let mut writer = Vec::new();
+        let message = "Do you want to empty? [Y/n]: ".to_string();
+
+        let result = confirm_input(&mut writer, &mut reader, message).unwrap();
+
+        assert!(result, "Should return true after an invalid input");
+
+        let output = String::from_utf8(writer).unwrap();
+        let expected_prompt = "Do you want to empty? [Y/n]: ";
+        assert_eq!(
+            output,
+            format!("{}{}", expected_prompt, expected_prompt),
+            "Should re-prompt after invalid input"
+        );
+    }
+
+    #[test]
+    fn test_confirm_input_eof_means_no() {
+        for input in ["", "maybe\n"] {
+            let mut reader = Cursor::new(input);
+            let mut writer = Vec::new();
+            let message = "Do you want to empty? [Y/n]: ".to_string();
+
+            let result = confirm_input(&mut writer, &mut reader, message).unwrap();
+
+            assert!(!result, "End of input after {:?} should mean no", input);
+        }
+    }
+}
@@ -0,0 +1,627 @@
+use std::ffi::{CString, OsStr};
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{lchown, symlink, FileTypeExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::os::unix::net::UnixListener;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::trash::error::AppError;
+use crate::trash::size::tree_summary;
+use crate::trash::walk::{WalkEvent, Walker};
+
+/// Size of the buffer used to copy file contents, and thus the granularity of throttling.
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Source of time for `Throttle`, so the throttling logic can be tested without sleeping.
+pub(crate) trait Clock {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+/// The real clock, backed by `Instant::now` and `thread::sleep`.
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Caps the throughput of a copy by sleeping whenever it gets ahead of the allowed rate.
+/// Without a rate (`None`), it never sleeps.
+pub(crate) struct Throttle<C: Clock = SystemClock> {
+    bytes_per_sec: Option<u64>,
+    clock: C,
+    started: Instant,
+    transferred: u64,
+}
+
+impl Throttle<SystemClock> {
+    pub(crate) fn new(bytes_per_sec: Option<u64>) -> Self {
+        Self::with_clock(bytes_per_sec, SystemClock)
+    }
+}
+
+impl<C: Clock> Throttle<C> {
+    pub(crate) fn with_clock(bytes_per_sec: Option<u64>, clock: C) -> Self {
+        let started = clock.now();
+        Throttle {
+            bytes_per_sec,
+            clock,
+            started,
+            transferred: 0,
+        }
+    }
+
+    /// Records that `bytes` more were copied, sleeping until the average rate
+    /// since the start no longer exceeds the limit.
+    pub(crate) fn consume(&mut self, bytes: u64) {
+        let Some(bytes_per_sec) = self.bytes_per_sec.filter(|&rate| rate > 0) else {
+            return;
+        };
+        self.transferred += bytes;
+        let expected = Duration::from_secs_f64(self.transferred as f64 / bytes_per_sec as f64);
+        let elapsed = self.clock.now().saturating_duration_since(self.started);
+        if expected > elapsed {
+            self.clock.sleep(expected - elapsed);
+        }
+    }
+}
+
+/// Recursively copies `src` to `dst`, preserving file modes, timestamps and symbolic links.
+/// FIFOs, sockets and device nodes are recreated rather than read.
+/// Extended attributes (which include SELinux labels and POSIX ACLs) and ownership are kept
+/// where permitted; what cannot be kept is reported in a single warning, never as an error.
+/// File contents are copied through `throttle`, so `--limit-rate` applies to the whole tree.
+pub(crate) fn copy_recursive<C: Clock>(src: &Path, dst: &Path, throttle: &mut Throttle<C>) -> Result<(), AppError> {
+    let mut lost_attributes = LostAttributes::default();
+    let result = copy_tree(src, dst, throttle, &mut lost_attributes);
+    lost_attributes.warn(src);
+    result
+}
+
+fn copy_tree<C: Clock>(
+    src: &Path,
+    dst: &Path,
+    throttle: &mut Throttle<C>,
+    lost_attributes: &mut LostAttributes,
+) -> Result<(), AppError> {
+    let with_path = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source: io::Error| AppError::Io { path, source }
+    };
+    let target_for = |path: &Path| match path.strip_prefix(src) {
+        Ok(relative) if !relative.as_os_str().is_empty() => dst.join(relative),
+        _ => dst.to_path_buf(),
+    };
+    for event in Walker::new(src) {
+        match event? {
+            WalkEvent::Visit(entry) => {
+                let target = target_for(&entry.path);
+                let file_type = entry.metadata.file_type();
+                if file_type.is_symlink() {
+                    let link_target = fs::read_link(&entry.path).map_err(with_path(&entry.path))?;
+                    symlink(link_target, &target).map_err(with_path(&target))?;
+                    copy_times(&entry.metadata, &target).map_err(with_path(&target))?;
+                } else if file_type.is_dir() {
+                    fs::create_dir(&target).map_err(with_path(&target))?;
+                } else {
+                    if file_type.is_file() {
+                        copy_file_contents(&entry.path, &target, throttle)?;
+                    } else {
+                        create_special_file(&entry.metadata, &target).map_err(with_path(&target))?;
+                    }
+                    copy_ownership_and_xattrs(&entry.path, &entry.metadata, &target, lost_attributes);
+                    fs::set_permissions(&target, entry.metadata.permissions()).map_err(with_path(&target))?;
+                    copy_times(&entry.metadata, &target).map_err(with_path(&target))?;
+                }
+            }
+            // Directory modes and times are applied last, so a read-only directory can still be
+            // filled and its mtime is not bumped by creating its contents.
+            WalkEvent::Leave(entry) => {
+                let target = target_for(&entry.path);
+                copy_ownership_and_xattrs(&entry.path, &entry.metadata, &target, lost_attributes);
+                fs::set_permissions(&target, entry.metadata.permissions()).map_err(with_path(&target))?;
+                copy_times(&entry.metadata, &target).map_err(with_path(&target))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Extended attributes that could not be copied, collected for one warning per copy
+/// rather than one per file (a target filesystem without xattr support fails them all).
+#[derive(Default)]
+struct LostAttributes {
+    count: usize,
+    first: Option<String>,
+}
+
+impl LostAttributes {
+    fn record(&mut self, path: &Path, name: &OsStr, error: &io::Error) {
+        self.count += 1;
+        self.first
+            .get_or_insert_with(|| format!("{} on '{}': {}", name.to_string_lossy(), path.display(), error));
+    }
+
+    fn warn(&self, src: &Path) {
+        if let Some(first) = &self.first {
+            eprintln!(
+                "warning: {} extended attribute(s) of '{}' were not preserved by the copy (first: {})",
+                self.count,
+                src.display(),
+                first
+            );
+        }
+    }
+}
+
+/// Gives `target` the owner and group of `src` when permitted (only root may give files away, so
+/// a refusal is expected and silent), and copies every extended attribute of `src` to it.
+/// POSIX ACLs and SELinux labels are extended attributes too (`system.posix_acl_*`, `security.selinux`).
+fn copy_ownership_and_xattrs(src: &Path, metadata: &Metadata, target: &Path, lost: &mut LostAttributes) {
+    let _ = lchown(target, Some(metadata.uid()), Some(metadata.gid()));
+    let names = match xattr::list(src) {
+        Ok(names) => names,
+        // The source filesystem has no extended attributes at all.
+        Err(e) if e.raw_os_error() == Some(libc::ENOTSUP) => return,
+        Err(e) => {
+            lost.record(src, OsStr::new("(list)"), &e);
+            return;
+        }
+    };
+    for name in names {
+        let result = xattr::get(src, &name).and_then(|value| match value {
+            Some(value) => xattr::set(target, &name, &value),
+            None => Ok(()),
+        });
+        if let Err(e) = result {
+            lost.record(target, &name, &e);
+        }
+    }
+}
+
+/// Copies `src` to `dst` with `copy` and checks that the copy holds as many items and bytes
+/// as the source. If the copy fails midway or comes up short, whatever was already written
+/// to `dst` is removed, so a failed copy never leaves a half-copied tree behind. If the copy
+/// could not even create `dst` because something is already there, that is left alone.
+pub(crate) fn copy_verified<F>(src: &Path, dst: &Path, copy: F) -> Result<(), AppError>
+where
+    F: FnOnce(&Path, &Path) -> Result<(), AppError>,
+{
+    if let Err(e) = copy(src, dst).and_then(|()| verify_copy(src, dst)) {
+        let dst_was_taken = matches!(
+            &e,
+            AppError::Io { path, source } if path == dst && source.kind() == io::ErrorKind::AlreadyExists
+        );
+        if dst_was_taken {
+            return Err(e);
+        }
+        if let Err(cleanup) = remove_any(dst) {
+            if cleanup.kind() != io::ErrorKind::NotFound {
+                eprintln!(
+                    "warning: Failed to clean up partial copy '{}': {}",
+                    dst.display(),
+                    cleanup
+                );
+            }
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Moves `src` to `dst` across filesystems: makes a verified copy with `copy_verified`,
+/// then removes `src`. A failed move leaves `src` intact and nothing at `dst`.
+pub(crate) fn move_by_copy<F>(src: &Path, dst: &Path, copy: F) -> Result<(), AppError>
+where
+    F: FnOnce(&Path, &Path) -> Result<(), AppError>,
+{
+    copy_verified(src, dst, copy)?;
+    remove_any(src).map_err(|source| AppError::Io {
+        path: src.to_path_buf(),
+        source,
+    })
+}
+
+/// Removes a file, symbolic link or directory tree.
+pub(crate) fn remove_any(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        make_tree_writable(path);
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Adds owner write permission to every directory under `path`, so a copied
+/// read-only directory does not prevent its own removal.
+fn make_tree_writable(path: &Path) {
+    for entry in Walker::new(path).flatten() {
+        if let WalkEvent::Visit(entry) = entry {
+            let mode = entry.metadata.permissions().mode();
+            if entry.metadata.is_dir() && mode & 0o200 == 0 {
+                let _ = fs::set_permissions(&entry.path, fs::Permissions::from_mode(mode | 0o700));
+            }
+        }
+    }
+}
+
+/// Checks that `dst` has the same number of items and total size as `src`.
+fn verify_copy(src: &Path, dst: &Path) -> Result<(), AppError> {
+    let expected = tree_summary(src, u64::MAX);
+    let copied = tree_summary(dst, u64::MAX);
+    if fs::symlink_metadata(dst).is_err()
+        || (copied.item_count, copied.total_size) != (expected.item_count, expected.total_size)
+    {
+        return Err(AppError::Message(format!(
+            "Copy of '{}' to '{}' is incomplete ({} of {} items, {} of {} bytes)",
+            src.display(),
+            dst.display(),
+            copied.item_count,
+            expected.item_count,
+            copied.total_size,
+            expected.total_size
+        )));
+    }
+    Ok(())
+}
+
+/// Creates a FIFO, socket or device node like the one described by `metadata` at `target`.
+/// The source is never opened: reading a FIFO blocks until some process writes to it.
+/// Device nodes can usually only be created by root, so copying one fails otherwise.
+fn create_special_file(metadata: &Metadata, target: &Path) -> io::Result<()> {
+    let file_type = metadata.file_type();
+    if file_type.is_socket() {
+        // Binding creates the socket node; nobody listens on it once the listener is dropped.
+        return UnixListener::bind(target).map(drop);
+    }
+    let c_path = CString::new(target.as_os_str().as_bytes())?;
+    // SAFETY: `c_path` is a valid NUL-terminated string.
+    let result = unsafe {
+        if file_type.is_fifo() {
+            libc::mkfifo(c_path.as_ptr(), (metadata.mode() & 0o7777) as libc::mode_t)
+        } else {
+            libc::mknod(
+                c_path.as_ptr(),
+                metadata.mode() as libc::mode_t,
+                metadata.rdev() as libc::dev_t,
+            )
+        }
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Sets the access and modification times of `path` (not following symbolic links) to those in `metadata`.
+fn copy_times(metadata: &Metadata, path: &Path) -> io::Result<()> {
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let times = [
+        libc::timespec {
+            tv_sec: metadata.atime() as libc::time_t,
+            tv_nsec: metadata.atime_nsec() as _,
+        },
+        libc::timespec {
+            tv_sec: metadata.mtime() as libc::time_t,
+            tv_nsec: metadata.mtime_nsec() as _,
+        },
+    ];
+    // SAFETY: `c_path` is a valid NUL-terminated string and `times` holds exactly two timespecs.
+    let result = unsafe {
+        libc::utimensat(
+            libc::AT_FDCWD,
+            c_path.as_ptr(),
+            times.as_ptr(),
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Copies the contents of a regular file in chunks, reporting each chunk to `throttle`.
+/// Anything else is refused before `dst` is created: the source is opened without blocking,
+/// so a FIFO put in its place since it was listed cannot stall the copy.
+fn copy_file_contents<C: Clock>(src: &Path, dst: &Path, throttle: &mut Throttle<C>) -> Result<(), AppError> {
+    let with_src = |source: io::Error| AppError::Io {
+        path: src.to_path_buf(),
+        source,
+    };
+    let mut reader = fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(src)
+        .map_err(with_src)?;
+    if !reader.metadata().map_err(with_src)?.is_file() {
+        return Err(with_src(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a regular file",
+        )));
+    }
+    let mut writer = File::create_new(dst).map_err(|source| AppError::Io {
+        path: dst.to_path_buf(),
+        source,
+    })?;
+    let mut buffer = vec![0; COPY_BUFFER_SIZE];
+    loop {
+        let read = reader.read(&mut buffer).map_err(with_src)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read]).map_err(|source| AppError::Io {
+            path: dst.to_path_buf(),
+            source,
+        })?;
+        throttle.consume(read as u64);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::SystemTime;
+    use tempfile::tempdir;
+
+    /// A clock whose time only advances when the throttle sleeps.
+    struct MockClock {
+        now: Cell<Instant>,
+        slept: Cell<Duration>,
+    }
+
+    impl MockClock {
+        fn new() -> Self {
+            MockClock {
+                now: Cell::new(Instant::now()),
+                slept: Cell::new(Duration::ZERO),
+            }
+        }
+
+        fn advance(&self, duration: Duration) {
+            self.now.set(self.now.get() + duration);
+        }
+    }
+
+    impl Clock for &MockClock {
+        fn now(&self) -> Instant {
+            self.now.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.slept.set(self.slept.get() + duration);
+            self.advance(duration);
+        }
+    }
+
+    #[test]
+    fn test_throttle_sleeps_to_cap_rate() {
+        let clock = MockClock::new();
+        let mut throttle = Throttle::with_clock(Some(1000), &clock);
+
+        // 500 bytes at 1000 B/s should take half a second.
+        throttle.consume(500);
+        assert_eq!(clock.slept.get(), Duration::from_millis(500));
+
+        // Time that already passed on its own counts towards the budget.
+        clock.advance(Duration::from_millis(200));
+        throttle.consume(500);
+        assert_eq!(clock.slept.get(), Duration::from_millis(800));
+
+        // Falling behind the rate never causes a sleep.
+        clock.advance(Duration::from_secs(5));
+        throttle.consume(1000);
+        assert_eq!(clock.slept.get(), Duration::from_millis(800));
+    }
+
+    #[test]
+    fn test_throttle_without_rate_never_sleeps() {
+        let clock = MockClock::new();
+        let mut throttle = Throttle::with_clock(None, &clock);
+        throttle.consume(u32::MAX as u64);
+        assert_eq!(clock.slept.get(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_copy_file_contents_refuses_special_files() -> Result<(), AppError> {
+        use std::sync::mpsc;
+
+        let dir = tempdir()?;
+        let regular = dir.path().join("data.bin");
+        fs::write(&regular, vec![7u8; 3000])?;
+        let clock = MockClock::new();
+        let mut throttle = Throttle::with_clock(Some(1000), &clock);
+        copy_file_contents(&regular, &dir.path().join("data.copy"), &mut throttle)?;
+        assert_eq!(fs::read(dir.path().join("data.copy"))?, vec![7u8; 3000]);
+        assert_eq!(clock.slept.get(), Duration::from_secs(3));
+
+        let fifo = dir.path().join("pipe");
+        let fifo_c = CString::new(fifo.as_os_str().as_bytes()).expect("no NUL in a temp path");
+        // SAFETY: `fifo_c` is a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(fifo_c.as_ptr(), 0o600) }, 0, "mkfifo failed");
+        let fifo_copy = dir.path().join("pipe.copy");
+
+        // Opening or reading the FIFO would block forever, so the copy runs under a timeout.
+        let (sender, receiver) = mpsc::channel();
+        let (thread_src, thread_dst) = (fifo.clone(), fifo_copy.clone());
+        thread::spawn(move || {
+            let _ = sender.send(copy_file_contents(
+                &thread_src,
+                &thread_dst,
+                &mut Throttle::new(Some(1000)),
+            ));
+        });
+        let result = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("a FIFO must be refused without blocking");
+        assert!(matches!(result, Err(AppError::Io { source, .. }) if source.kind() == io::ErrorKind::InvalidInput));
+        assert!(!fifo_copy.exists(), "nothing should be created for a refused source");
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_recursive_preserves_xattrs() -> Result<(), AppError> {
+        let dir = tempdir()?;
+        let src = dir.path().join("src");
+        fs::create_dir(&src)?;
+        fs::write(src.join("labeled.txt"), "data")?;
+        if let Err(e) = xattr::set(src.join("labeled.txt"), "user.test", b"file value") {
+            eprintln!("skipping: the temporary directory does not support user xattrs: {}", e);
+            return Ok(());
+        }
+        xattr::set(&src, "user.test", b"dir value")?;
+        // Read-only after the copy, so the attribute must be set before the mode is.
+        fs::set_permissions(src.join("labeled.txt"), fs::Permissions::from_mode(0o444))?;
+
+        let dst = dir.path().join("dst");
+        copy_recursive(&src, &dst, &mut Throttle::new(None))?;
+
+        assert_eq!(
+            xattr::get(dst.join("labeled.txt"), "user.test")?.as_deref(),
+            Some(&b"file value"[..])
+        );
+        assert_eq!(xattr::get(&dst, "user.test")?.as_deref(), Some(&b"dir value"[..]));
+        assert_eq!(
+            fs::metadata(dst.join("labeled.txt"))?.permissions().mode() & 0o777,
+            0o444
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_recursive_recreates_special_files() -> Result<(), AppError> {
+        use std::sync::mpsc;
+
+        let dir = tempdir()?;
+        let src = dir.path().join("src");
+        fs::create_dir(&src)?;
+        fs::write(src.join("data.txt"), "data")?;
+        let fifo = CString::new(src.join("pipe").as_os_str().as_bytes()).expect("no NUL in a temp path");
+        // SAFETY: `fifo` is a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o640) }, 0, "mkfifo failed");
+        drop(UnixListener::bind(src.join("app.sock"))?);
+        let dst = dir.path().join("dst");
+
+        // Reading the FIFO would block forever, so the copy runs under a timeout.
+        let (sender, receiver) = mpsc::channel();
+        let (thread_src, thread_dst) = (src.clone(), dst.clone());
+        thread::spawn(move || {
+            let result = copy_verified(&thread_src, &thread_dst, |src, dst| {
+                copy_recursive(src, dst, &mut Throttle::new(None))
+            });
+            let _ = sender.send(result);
+        });
+        receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("copying a FIFO must not block")?;
+
+        assert_eq!(fs::read_to_string(dst.join("data.txt"))?, "data");
+        let pipe = fs::symlink_metadata(dst.join("pipe"))?;
+        assert!(pipe.file_type().is_fifo());
+        assert_eq!(pipe.permissions().mode() & 0o777, 0o640);
+        assert!(fs::symlink_metadata(dst.join("app.sock"))?.file_type().is_socket());
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_verified_keeps_existing_destination() -> Result<(), AppError> {
+        struct TestCase {
+            name: &'static str,
+            src_is_dir: bool,
+            dst_is_dir: bool,
+        }
+        let test_cases = [
+            TestCase {
+                name: "file onto existing file",
+                src_is_dir: false,
+                dst_is_dir: false,
+            },
+            TestCase {
+                name: "directory onto existing directory",
+                src_is_dir: true,
+                dst_is_dir: true,
+            },
+            TestCase {
+                name: "file onto existing directory",
+                src_is_dir: false,
+                dst_is_dir: true,
+            },
+        ];
+        for case in test_cases {
+            let dir = tempdir()?;
+            let src = dir.path().join("src");
+            if case.src_is_dir {
+                fs::create_dir(&src)?;
+                fs::write(src.join("new.txt"), "new")?;
+            } else {
+                fs::write(&src, "new")?;
+            }
+            let dst = dir.path().join("dst");
+            if case.dst_is_dir {
+                fs::create_dir(&dst)?;
+                fs::write(dst.join("keep.txt"), "user data")?;
+            } else {
+                fs::write(&dst, "user data")?;
+            }
+
+            let result = copy_verified(&src, &dst, |src, dst| {
+                copy_recursive(src, dst, &mut Throttle::new(None))
+            });
+
+            assert!(result.is_err(), "case '{}': the copy should fail", case.name);
+            let kept = if case.dst_is_dir { dst.join("keep.txt") } else { dst };
+            assert_eq!(
+                fs::read_to_string(&kept)?,
+                "user data",
+                "case '{}': the existing destination was touched",
+                case.name
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_recursive() -> Result<(), AppError> {
+        let dir = tempdir()?;
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("nested"))?;
+        fs::write(src.join("nested/data.bin"), vec![7u8; COPY_BUFFER_SIZE * 2 + 10])?;
+        fs::write(src.join("script.sh"), "#!/bin/sh\n")?;
+        fs::set_permissions(src.join("script.sh"), fs::Permissions::from_mode(0o755))?;
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        File::options()
+            .write(true)
+            .open(src.join("script.sh"))?
+            .set_modified(old)?;
+        symlink("script.sh", src.join("link"))?;
+
+        let dst = dir.path().join("dst");
+        let clock = MockClock::new();
+        copy_recursive(&src, &dst, &mut Throttle::with_clock(Some(1024 * 1024), &clock))?;
+        let single = dir.path().join("single.sh");
+        copy_recursive(&src.join("script.sh"), &single, &mut Throttle::new(None))?;
+
+        assert_eq!(fs::read(dst.join("nested/data.bin"))?.len(), COPY_BUFFER_SIZE * 2 + 10);
+        assert_eq!(fs::metadata(dst.join("script.sh"))?.permissions().mode() & 0o777, 0o755);
+        assert_eq!(fs::read_link(dst.join("link"))?, Path::new("script.sh"));
+        assert_eq!(fs::metadata(dst.join("script.sh"))?.modified()?, old);
+        assert_eq!(fs::metadata(&single)?.modified()?, old);
+        assert_eq!(fs::read_to_string(&single)?, "#!/bin/sh\n");
+        assert!(clock.slept.get() > Duration::ZERO);
+        Ok(())
+    }
+}
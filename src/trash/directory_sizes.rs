@@ -0,0 +1,115 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::trash::error::AppError;
+use crate::trash::spec::DIRECTORY_SIZES_FILE_NAME;
+use crate::trash::url_escape::trash_spec_url_encode;
+
+/// Records the size of the trashed directory `name` in the trash's `directorysizes` cache,
+/// replacing any earlier line for the same name. `info_mtime` is the modification time of
+/// its `.trashinfo` file in seconds since the epoch, which readers use to detect stale lines.
+pub(crate) fn record_directory_size(
+    trash_root: &Path,
+    name: &OsStr,
+    size: u64,
+    info_mtime: i64,
+) -> Result<(), AppError> {
+    let encoded_name = trash_spec_url_encode(name);
+    let mut lines = read_lines(trash_root)?;
+    lines.retain(|line| line_name(line) != Some(encoded_name.as_str()));
+    lines.push(format!("{} {} {}", size, info_mtime, encoded_name));
+    write_lines(trash_root, &lines)
+}
+
+/// Removes the line for `name` from the trash's `directorysizes` cache, if there is one.
+pub(crate) fn forget_directory_size(trash_root: &Path, name: &OsStr) -> Result<(), AppError> {
+    let encoded_name = trash_spec_url_encode(name);
+    let mut lines = read_lines(trash_root)?;
+    let count = lines.len();
+    lines.retain(|line| line_name(line) != Some(encoded_name.as_str()));
+    if lines.len() == count {
+        return Ok(());
+    }
+    write_lines(trash_root, &lines)
+}
+
+/// Forgets the cached size of a trashed item, given its path in a trash `files` directory.
+/// The cache is only an optimization for file managers, so a failure is merely reported.
+pub(crate) fn forget_trashed_item(trashed_path: &Path) {
+    let (Some(trash_root), Some(name)) = (trashed_path.parent().and_then(Path::parent), trashed_path.file_name())
+    else {
+        return;
+    };
+    if let Err(e) = forget_directory_size(trash_root, name) {
+        eprintln!("warning: Failed to update the directory size cache: {}", e);
+    }
+}
+
+/// The encoded name of a `size mtime name` line.
+fn line_name(line: &str) -> Option<&str> {
+    line.splitn(3, ' ').nth(2)
+}
+
+fn read_lines(trash_root: &Path) -> Result<Vec<String>, AppError> {
+    let path = trash_root.join(DIRECTORY_SIZES_FILE_NAME);
+    match fs::read_to_string(&path) {
+        Ok(content) => Ok(content
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(source) => Err(AppError::Io { path, source }),
+    }
+}
+
+/// Replaces the cache atomically, as the spec asks, so readers never see a half-written file.
+fn write_lines(trash_root: &Path, lines: &[String]) -> Result<(), AppError> {
+    let path = trash_root.join(DIRECTORY_SIZES_FILE_NAME);
+    let temp_path = trash_root.join(format!("{}.{}.tmp", DIRECTORY_SIZES_FILE_NAME, std::process::id()));
+    let content: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+    fs::write(&temp_path, content)
+        .and_then(|()| fs::rename(&temp_path, &path))
+        .map_err(|source| {
+            let _ = fs::remove_file(&temp_path);
+            AppError::Io { path, source }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_record_and_forget_directory_size() -> Result<(), AppError> {
+        let trash_root = tempdir()?;
+        let cache = trash_root.path().join(DIRECTORY_SIZES_FILE_NAME);
+
+        record_directory_size(trash_root.path(), OsStr::new("photos"), 4096, 1_700_000_000)?;
+        record_directory_size(trash_root.path(), OsStr::new("my docs"), 100, 1_700_000_100)?;
+        // A second record for the same name replaces the first.
+        record_directory_size(trash_root.path(), OsStr::new("photos"), 8192, 1_700_000_200)?;
+        assert_eq!(
+            fs::read_to_string(&cache)?,
+            "100 1700000100 my%20docs\n8192 1700000200 photos\n"
+        );
+
+        forget_directory_size(trash_root.path(), OsStr::new("my docs"))?;
+        assert_eq!(fs::read_to_string(&cache)?, "8192 1700000200 photos\n");
+        // Forgetting an unknown name leaves the cache alone.
+        forget_directory_size(trash_root.path(), OsStr::new("unknown"))?;
+        assert_eq!(fs::read_to_string(&cache)?, "8192 1700000200 photos\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_forget_directory_size_without_cache() -> Result<(), AppError> {
+        let trash_root = tempdir()?;
+        forget_directory_size(trash_root.path(), OsStr::new("photos"))?;
+        assert!(!trash_root.path().join(DIRECTORY_SIZES_FILE_NAME).exists());
+        Ok(())
+    }
+}
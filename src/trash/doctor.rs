@@ -0,0 +1,206 @@
+use std::env;
+use std::ffi::CString;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use colored::control::SHOULD_COLORIZE;
+
+use crate::trash::error::AppError;
+#[cfg(target_os = "linux")]
+use crate::trash::locations::MOUNTS_FILE_PATH;
+use crate::trash::locations::{find_mounted_trashes, get_local_trash_path, resolve_target_trash, Mounts};
+use crate::trash::spec::{TRASH_FILES_DIR_NAME, TRASH_INFO_DIR_NAME};
+
+/// Environment variables that influence where trash directories are looked for.
+const REPORTED_ENV_VARS: &[&str] = &[
+    "HOME",
+    "XDG_DATA_HOME",
+    "XDG_DATA_DIRS",
+    "XDG_STATE_HOME",
+    "TRASH_TOOL_OPTIONS",
+];
+
+/// Prints a read-only report of everything that decides which trash is used, for support triage.
+pub fn handle_doctor() -> Result<(), AppError> {
+    write_report(&mut io::stdout())
+}
+
+fn write_report<W: Write>(writer: &mut W) -> Result<(), AppError> {
+    let uid = users::get_current_uid();
+
+    writeln!(writer, "User:")?;
+    writeln!(writer, "  uid: {}, gid: {}", uid, users::get_current_gid())?;
+
+    writeln!(writer, "Environment:")?;
+    for name in REPORTED_ENV_VARS {
+        match env::var_os(name) {
+            Some(value) => writeln!(writer, "  {}={}", name, value.to_string_lossy())?,
+            None => writeln!(writer, "  {} is not set", name)?,
+        }
+    }
+
+    writeln!(writer, "Home trash:")?;
+    match get_local_trash_path() {
+        Some(home_trash) => {
+            writeln!(writer, "  path: {}", home_trash.display())?;
+            for problem in trash_dir_problems(&home_trash) {
+                writeln!(writer, "  problem: {}", problem)?;
+            }
+        }
+        None => writeln!(writer, "  problem: could not be determined (no home directory)")?,
+    }
+
+    writeln!(writer, "Mounts:")?;
+    #[cfg(target_os = "linux")]
+    match fs::File::open(MOUNTS_FILE_PATH) {
+        Ok(_) => writeln!(writer, "  {} is readable", MOUNTS_FILE_PATH)?,
+        Err(e) => writeln!(writer, "  problem: {} is not readable: {}", MOUNTS_FILE_PATH, e)?,
+    }
+    let trash_dirs: Vec<PathBuf> = find_mounted_trashes(uid)
+        .iter()
+        .map(|trash| trash.root_path().to_path_buf())
+        .collect();
+    match mountpoints::mountinfos() {
+        Ok(mount_infos) => {
+            // Pseudo filesystems such as proc and sysfs never hold a trash.
+            for info in mount_infos.into_iter().filter(|info| !info.dummy) {
+                let trashes: Vec<String> = trash_dirs
+                    .iter()
+                    .filter(|dir| {
+                        dir.parent() == Some(&info.path) || dir.parent().and_then(Path::parent) == Some(&info.path)
+                    })
+                    .map(|dir| dir.display().to_string())
+                    .collect();
+                writeln!(
+                    writer,
+                    "  {} ({}){}",
+                    info.path.display(),
+                    info.format.as_deref().unwrap_or("unknown"),
+                    if trashes.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" trash: {}", trashes.join(", "))
+                    }
+                )?;
+            }
+        }
+        Err(e) => writeln!(writer, "  problem: failed to read mount points: {}", e)?,
+    }
+
+    writeln!(writer, "Current directory:")?;
+    match env::current_dir() {
+        Ok(current_dir) => {
+            writeln!(writer, "  path: {}", current_dir.display())?;
+            let target = Mounts::read().and_then(|mounts| resolve_target_trash(&current_dir, &mounts));
+            match target {
+                Ok(target) => writeln!(writer, "  files trashed here go to: {}", target.root_path().display())?,
+                Err(e) => writeln!(writer, "  problem: no trash for this directory: {}", e)?,
+            }
+        }
+        Err(e) => writeln!(writer, "  problem: cannot read the current directory: {}", e)?,
+    }
+
+    writeln!(writer, "Terminal:")?;
+    writeln!(writer, "  stdout is a terminal: {}", io::stdout().is_terminal())?;
+    writeln!(writer, "  colors enabled: {}", SHOULD_COLORIZE.should_colorize())?;
+    Ok(())
+}
+
+/// Lists the problems that would stop a trash directory from being used.
+/// A missing trash is not a problem, as it is created on first use.
+fn trash_dir_problems(trash_dir: &Path) -> Vec<String> {
+    let metadata = match fs::symlink_metadata(trash_dir) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Vec::new(),
+        Err(e) => return vec![format!("cannot be inspected: {}", e)],
+    };
+    if metadata.file_type().is_symlink() {
+        return vec!["is a symbolic link, which is refused for security reasons".to_string()];
+    }
+    if !metadata.is_dir() {
+        return vec!["exists but is not a directory".to_string()];
+    }
+
+    let mut problems = Vec::new();
+    let mode = metadata.permissions().mode() & 0o7777;
+    if mode & 0o077 != 0 {
+        problems.push(format!(
+            "permissions {:o} allow access by other users (expected 700)",
+            mode
+        ));
+    }
+    for dir in [
+        trash_dir.to_path_buf(),
+        trash_dir.join(TRASH_FILES_DIR_NAME),
+        trash_dir.join(TRASH_INFO_DIR_NAME),
+    ] {
+        if dir.exists() && !is_writable(&dir) {
+            problems.push(format!("'{}' is not writable", dir.display()));
+        }
+    }
+    problems
+}
+
+/// Checks write access the way the kernel would for this process.
+fn is_writable(path: &Path) -> bool {
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: `c_path` is a valid NUL-terminated string that outlives the call.
+    unsafe { libc::access(c_path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_trash_dir_problems() -> Result<(), AppError> {
+        let root = tempdir()?;
+
+        let missing = root.path().join("missing");
+        assert!(trash_dir_problems(&missing).is_empty());
+
+        let healthy = root.path().join("healthy");
+        fs::create_dir_all(healthy.join(TRASH_FILES_DIR_NAME))?;
+        fs::set_permissions(&healthy, fs::Permissions::from_mode(0o700))?;
+        assert!(trash_dir_problems(&healthy).is_empty());
+
+        let open = root.path().join("open");
+        fs::create_dir(&open)?;
+        fs::set_permissions(&open, fs::Permissions::from_mode(0o755))?;
+        assert_eq!(trash_dir_problems(&open).len(), 1);
+
+        let link = root.path().join("link");
+        std::os::unix::fs::symlink(&healthy, &link)?;
+        assert!(trash_dir_problems(&link)[0].contains("symbolic link"));
+
+        let file = root.path().join("file");
+        File::create(&file)?;
+        assert!(trash_dir_problems(&file)[0].contains("not a directory"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_report_has_all_sections() -> Result<(), AppError> {
+        let mut output = Vec::new();
+        write_report(&mut output)?;
+        let report = String::from_utf8(output)?;
+        for section in [
+            "User:",
+            "Environment:",
+            "Home trash:",
+            "Mounts:",
+            "Current directory:",
+            "Terminal:",
+        ] {
+            assert!(report.contains(section), "Missing section {}", section);
+        }
+        Ok(())
+    }
+}
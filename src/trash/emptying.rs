@@ -1,35 +1,164 @@
 use std::fs;
-use std::io::{self, BufRead, BufReader, Write};
-use std::path::Path;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
 
+use chrono::{Local, NaiveDateTime, TimeDelta};
+use humansize::{format_size, BINARY};
+
+use crate::trash::check::{count_of, pair_trash_names};
+use crate::trash::confirm::confirm_input;
+use crate::trash::directory_sizes::forget_trashed_item;
 use crate::trash::error::AppError;
-use crate::trash::listing::list_directory_contents_single_trash;
-use crate::trash::locations::get_target_trash_dirs;
-use crate::trash::spec::{TRASH_FILES_DIR_NAME, TRASH_INFO_DIR_NAME};
+use crate::trash::filter::EntryFilter;
+use crate::trash::grace::{commit_pending, default_manifest_path, stage_empty};
+use crate::trash::home_path::display_path;
+use crate::trash::listing::{list_directory_contents_single_trash, DisplayTrashOptions};
+use crate::trash::locations::{get_target_trash_dirs, select_target_trashes};
+use crate::trash::porcelain::{write_record, DRY_RUN_FIELD};
+use crate::trash::restoring::{absolute_original_path, find_entry_for_path, find_trash_entries_in_dirs, TrashEntry};
+use crate::trash::size::dir_size;
+use crate::trash::spec::{DIRECTORY_SIZES_FILE_NAME, TRASH_FILES_DIR_NAME, TRASH_INFO_DIR_NAME};
 
 pub struct EmptyTrashOptions {
     pub all_trash: bool,
+    /// Empty only this trash directory instead (`--trash-dir`).
+    pub trash_dir: Option<PathBuf>,
     pub no_confirm: bool,
     pub display: bool,
     pub long_format: bool,
+    pub porcelain: bool,
+    pub tty: bool,
+    /// When active, only the matching entries are deleted instead of the whole trash.
+    pub filter: EntryFilter,
+    /// When set, contents are held back and only deleted once this period has passed
+    /// (or on `--commit-empty`), so the empty can be undone with `--cancel-empty`.
+    pub grace: Option<TimeDelta>,
+    /// Only list what would be deleted; nothing is asked, removed or held back.
+    pub dry_run: bool,
+}
+
+pub struct PurgeOptions {
+    pub no_confirm: bool,
+    pub porcelain: bool,
+}
+
+/// Permanently deletes the items that were trashed from `paths`, without emptying anything else.
+/// Like restoring by path, every trash is searched and the most recent item wins.
+pub fn handle_purge_paths(paths: &[String], opts: PurgeOptions) -> Result<(), AppError> {
+    let entries = find_trash_entries_in_dirs(&get_target_trash_dirs(true)?);
+    let mut writer = io::stdout();
+    let mut had_errors = false;
+
+    for path in paths {
+        let original_path = absolute_original_path(Path::new(path));
+        let Some(entry) = find_entry_for_path(&entries, &original_path) else {
+            eprintln!("No trashed item found for '{}'.", original_path.display());
+            had_errors = true;
+            continue;
+        };
+        if !opts.no_confirm {
+            let mut stdin = BufReader::new(io::stdin());
+            let message = format!(
+                "Permanently delete '{}' ({}) from the trash? [Y/n]: ",
+                display_path(&entry.original_path),
+                format_size(dir_size(&entry.trashed_path), BINARY)
+            );
+            if !confirm_input(&mut writer, &mut stdin, message)? {
+                continue;
+            }
+        }
+        match purge_entry(entry) {
+            Ok(size) if opts.porcelain => {
+                write_record(
+                    &mut writer,
+                    &[&entry.trashed_path.display().to_string(), &size.to_string()],
+                )?;
+            }
+            Ok(size) => println!(
+                "Purged: {} ({})",
+                display_path(&entry.original_path),
+                format_size(size, BINARY)
+            ),
+            Err(e) => {
+                eprintln!("Failed to purge '{}': {}", entry.original_path.display(), e);
+                had_errors = true;
+            }
+        }
+    }
+
+    if had_errors {
+        return Err(AppError::Ignorable);
+    }
+    Ok(())
+}
+
+/// Permanently deletes a single trash entry (its data, recursively for directories, and its
+/// `.trashinfo` file) and returns the number of bytes freed.
+pub fn purge_entry(entry: &TrashEntry) -> Result<u64, AppError> {
+    let size = dir_size(&entry.trashed_path);
+    remove_entry(entry)?;
+    Ok(size)
 }
 
 pub fn handle_empty_trash(opts: EmptyTrashOptions) -> Result<(), AppError> {
-    let trash_dirs = get_target_trash_dirs(opts.all_trash)?;
-    if trash_dirs.is_empty() {
+    let trashes = select_target_trashes(opts.all_trash, opts.trash_dir.as_deref())?;
+    if trashes.is_empty() {
         return Ok(());
     }
+    if !opts.dry_run {
+        commit_expired_pending_empties()?;
+    }
+    if opts.filter.is_active() {
+        let trash_dirs: Vec<PathBuf> = trashes.iter().map(|trash| trash.root_path().to_path_buf()).collect();
+        return empty_matching_entries(&trash_dirs, &opts);
+    }
     let mut writer = io::stdout();
-
-    for path in trash_dirs {
-        let (item_count, is_empty) = get_trash_status(&path)?;
-        if is_empty {
-            println!("({}): {}", item_count, path.display());
+    let mut total_freed: u64 = 0;
+    let mut emptied_count = 0;
+
+    for trash in &trashes {
+        let path = trash.root_path();
+        let names = pair_trash_names(path)?;
+        let item_count = names.paired.len();
+        if names.is_empty() {
+            if !opts.porcelain {
+                println!("({}): {}", item_count, path.display());
+            }
             continue;
         }
 
+        if !names.orphaned_items.is_empty() || !names.orphaned_info_files.is_empty() {
+            eprintln!(
+                "warning: {} also holds {} and {}, which are deleted along with it (see `tt check`).",
+                path.display(),
+                count_of(names.orphaned_items.len(), "orphaned item"),
+                count_of(names.orphaned_info_files.len(), "orphaned info file")
+            );
+        }
+
         if opts.display || opts.long_format {
-            list_directory_contents_single_trash(&mut writer, &path, opts.long_format)?;
+            let display_opts = DisplayTrashOptions {
+                long_format: opts.long_format,
+                porcelain: opts.porcelain,
+                tty: opts.tty,
+                ..Default::default()
+            };
+            list_directory_contents_single_trash(&mut writer, trash, &display_opts)?;
+        }
+
+        if opts.dry_run {
+            let doomed = empty_single_trash_dir(path, true)?;
+            if !opts.porcelain {
+                println!("Would permanently delete from {}:", path.display());
+            }
+            for doomed_path in doomed {
+                if opts.porcelain {
+                    write_record(&mut writer, &[&doomed_path.display().to_string(), DRY_RUN_FIELD])?;
+                } else {
+                    println!("  {}", doomed_path.display());
+                }
+            }
+            continue;
         }
 
         let should_empty = if opts.no_confirm {
@@ -40,56 +169,203 @@ pub fn handle_empty_trash(opts: EmptyTrashOptions) -> Result<(), AppError> {
             confirm_input(&mut writer, &mut stdin, message)?
         };
 
-        if should_empty {
-            empty_single_trash_dir(&path)?;
-            println!("Emptied trash at: {}", path.display());
+        if let (true, Some(grace)) = (should_empty, opts.grace) {
+            let pending = stage_empty(&default_manifest_path()?, path, grace, Local::now())?;
+            if opts.porcelain {
+                write_record(&mut writer, &[&path.display().to_string(), &item_count.to_string()])?;
+            } else {
+                println!(
+                    "Emptied trash at: {} (undo with --cancel-empty before {})",
+                    path.display(),
+                    chrono::DateTime::from_timestamp(pending.deadline, 0)
+                        .map(|d| d.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+                        .unwrap_or_default()
+                );
+            }
+        } else if should_empty {
+            let freed = empty_and_measure(path)?;
+            total_freed += freed;
+            emptied_count += 1;
+            if opts.porcelain {
+                write_record(&mut writer, &[&path.display().to_string(), &item_count.to_string()])?;
+            } else {
+                println!("Emptied trash at: {}", path.display());
+                println!("Freed {} from {}", format_size(freed, BINARY), path.display());
+            }
         }
     }
+    if emptied_count > 1 && !opts.porcelain {
+        println!("Freed {} in total", format_size(total_freed, BINARY));
+    }
     Ok(())
 }
 
-fn get_trash_status(trash_dir: &Path) -> Result<(usize, bool), AppError> {
-    let files_dir = trash_dir.join(TRASH_FILES_DIR_NAME);
-    let info_dir = trash_dir.join(TRASH_INFO_DIR_NAME);
-    let files_dir_count = fs::read_dir(&files_dir)
-        .map_err(|source| AppError::Io {
-            path: files_dir.clone(),
-            source,
-        })?
-        .count();
-    let info_dir_count = fs::read_dir(&info_dir)
-        .map_err(|source| AppError::Io {
-            path: info_dir.clone(),
-            source,
-        })?
-        .count();
-    Ok((files_dir_count, files_dir_count == 0 && info_dir_count == 0))
+/// Empties the trash at `trash_root` like `empty_single_trash_dir` and returns the number of bytes
+/// its `files` directory held, measured just before. Unreadable subdirectories are left out of the
+/// count with a warning.
+fn empty_and_measure(trash_root: &Path) -> Result<u64, AppError> {
+    let freed = dir_size(&trash_root.join(TRASH_FILES_DIR_NAME));
+    empty_single_trash_dir(trash_root, false)?;
+    Ok(freed)
+}
+
+/// Permanently deletes the held contents of earlier `--grace` empties whose grace period has ended.
+fn commit_expired_pending_empties() -> Result<(), AppError> {
+    match default_manifest_path() {
+        Ok(manifest_path) => commit_pending(&manifest_path, true, Local::now()).map(|_| ()),
+        // Without a state directory no grace empty can have been recorded.
+        Err(_) => Ok(()),
+    }
 }
 
-fn confirm_input<W: Write, R: BufRead>(writer: &mut W, reader: &mut R, message: String) -> Result<bool, AppError> {
-    let mut input = String::new();
-    loop {
-        write!(writer, "{}", message)?;
-        writer.flush()?;
-        reader.read_line(&mut input)?;
-        let trimmed_input = input.trim().to_lowercase();
+/// Permanently deletes only the entries matching `opts.filter`, leaving the rest of each trash untouched.
+fn empty_matching_entries(trash_dirs: &[PathBuf], opts: &EmptyTrashOptions) -> Result<(), AppError> {
+    let mut writer = io::stdout();
+    let now = Local::now().naive_local();
+    let mut total_freed: u64 = 0;
+    let mut removed_count = 0;
+
+    for path in trash_dirs {
+        let candidates = select_matching_entries(path, &opts.filter, now);
+        if candidates.is_empty() {
+            if !opts.porcelain {
+                println!("(0): {}", path.display());
+            }
+            continue;
+        }
+
+        let should_empty = if opts.no_confirm || opts.dry_run {
+            true
+        } else {
+            let candidates_size: u64 = candidates.iter().map(|(_, size)| size).sum();
+            let mut stdin = BufReader::new(io::stdin());
+            let message = format!(
+                "({}): {} - permanently delete {} matching items ({})? [Y/n]: ",
+                candidates.len(),
+                path.display(),
+                candidates.len(),
+                format_size(candidates_size, BINARY)
+            );
+            confirm_input(&mut writer, &mut stdin, message)?
+        };
+        if !should_empty {
+            continue;
+        }
 
-        if trimmed_input.is_empty() || trimmed_input == "y" || trimmed_input == "yes" {
-            return Ok(true);
-        } else if trimmed_input == "n" || trimmed_input == "no" {
-            return Ok(false);
+        for (entry, size) in candidates {
+            if opts.dry_run {
+                total_freed += size;
+                removed_count += 1;
+                if opts.porcelain {
+                    write_record(
+                        &mut writer,
+                        &[
+                            &entry.trashed_path.display().to_string(),
+                            &size.to_string(),
+                            DRY_RUN_FIELD,
+                        ],
+                    )?;
+                } else {
+                    println!(
+                        "Would remove: {} ({})",
+                        display_path(&entry.original_path),
+                        format_size(size, BINARY)
+                    );
+                }
+                continue;
+            }
+            match remove_entry(&entry) {
+                Ok(()) => {
+                    total_freed += size;
+                    removed_count += 1;
+                    if opts.porcelain {
+                        write_record(
+                            &mut writer,
+                            &[&entry.trashed_path.display().to_string(), &size.to_string()],
+                        )?;
+                    } else {
+                        println!(
+                            "Removed: {} ({})",
+                            display_path(&entry.original_path),
+                            format_size(size, BINARY)
+                        );
+                    }
+                }
+                Err(e) => eprintln!("Failed to remove '{}': {}", entry.trashed_path.display(), e),
+            }
         }
-        // If input is invalid, loop will continue and re-prompt.
-        input.clear();
+    }
+
+    if !opts.porcelain {
+        let verb = if opts.dry_run { "Would free" } else { "Freed" };
+        println!(
+            "{} {} ({} items)",
+            verb,
+            format_size(total_freed, BINARY),
+            removed_count
+        );
+    }
+    Ok(())
+}
+
+/// Returns the entries of `trash_dir` that match `filter`, together with their recursive sizes.
+fn select_matching_entries(trash_dir: &Path, filter: &EntryFilter, now: NaiveDateTime) -> Vec<(TrashEntry, u64)> {
+    find_trash_entries_in_dirs(&[trash_dir.to_path_buf()])
+        .into_iter()
+        .map(|entry| {
+            let size = dir_size(&entry.trashed_path);
+            (entry, size)
+        })
+        .filter(|(entry, size)| filter.matches(*size, entry.deletion_time(), now))
+        .collect()
+}
+
+/// Permanently deletes a single trash entry.
+/// The `.trashinfo` file is removed first so that an interruption never leaves an info file
+/// pointing at partially deleted data.
+fn remove_entry(entry: &TrashEntry) -> Result<(), AppError> {
+    fs::remove_file(&entry.info_path).map_err(|source| AppError::Io {
+        path: entry.info_path.clone(),
+        source,
+    })?;
+
+    let is_dir = fs::symlink_metadata(&entry.trashed_path)
+        .map(|m| m.is_dir())
+        .unwrap_or(false);
+    let result = if is_dir {
+        fs::remove_dir_all(&entry.trashed_path)
+    } else {
+        fs::remove_file(&entry.trashed_path)
+    };
+    forget_trashed_item(&entry.trashed_path);
+    match result {
+        Ok(()) => Ok(()),
+        // The data is already gone; removing the info file was all that was left to do.
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(source) => Err(AppError::Io {
+            path: entry.trashed_path.clone(),
+            source,
+        }),
     }
 }
 
 /// Empties a single trash directory according to the FreeDesktop.org specification.
 /// This involves recursively removing the `files` and `info` directories and then recreating them.
-fn empty_single_trash_dir(trash_root: &Path) -> Result<(), AppError> {
+/// Returns the top-level items of both directories that are deleted; with `dry_run` they are
+/// only collected and nothing is removed.
+fn empty_single_trash_dir(trash_root: &Path, dry_run: bool) -> Result<Vec<PathBuf>, AppError> {
     let targets = [TRASH_FILES_DIR_NAME, TRASH_INFO_DIR_NAME];
+    let mut deleted = Vec::new();
     for target in targets {
         let dir = trash_root.join(target);
+        if let Ok(entries) = fs::read_dir(&dir) {
+            let mut paths: Vec<PathBuf> = entries.filter_map(Result::ok).map(|entry| entry.path()).collect();
+            paths.sort();
+            deleted.extend(paths);
+        }
+        if dry_run {
+            continue;
+        }
         if dir.is_dir() {
             if let Err(source) = fs::remove_dir_all(&dir) {
                 return Err(AppError::Io { path: dir, source });
@@ -101,92 +377,27 @@ fn empty_single_trash_dir(trash_root: &Path) -> Result<(), AppError> {
         }
     }
 
-    Ok(())
+    if !dry_run {
+        // Every cached directory size is stale now.
+        let cache = trash_root.join(DIRECTORY_SIZES_FILE_NAME);
+        if let Err(source) = fs::remove_file(&cache) {
+            if source.kind() != io::ErrorKind::NotFound {
+                return Err(AppError::Io { path: cache, source });
+            }
+        }
+    }
+
+    Ok(deleted)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::trash::filter::{parse_since, parse_until};
     use std::fs::{self, File};
-    use std::io::Cursor;
     use std::os::unix::fs::PermissionsExt;
     use tempfile::tempdir;
 
-    #[test]
-    fn test_confirm_input() {
-        struct TestCase {
-            input: &'static str,
-            expected_result: bool,
-            description: &'static str,
-        }
-
-        let test_cases = vec![
-            TestCase {
-                input: "\n",
-                expected_result: true,
-                description: "Input empty (default to yes)",
-            },
-            TestCase {
-                input: "y\n",
-                expected_result: true,
-                description: "Input 'y'",
-            },
-            TestCase {
-                input: "yes\n",
-                expected_result: true,
-                description: "Input 'yes'",
-            },
-            TestCase {
-                input: "Y\n",
-                expected_result: true,
-                description: "Input 'Y' (case-insensitive)",
-            },
-            TestCase {
-                input: "n\n",
-                expected_result: false,
-                description: "Input 'n'",
-            },
-            TestCase {
-                input: "no\n",
-                expected_result: false,
-                description: "Input 'no'",
-            },
-        ];
-
-        for case in test_cases {
-            let mut reader = Cursor::new(case.input);
-            let mut writer = Vec::new();
-            let message = "Do you want to empty? [Y/n]: ".to_string();
-
-            let result = confirm_input(&mut writer, &mut reader, message).unwrap();
-
-            assert_eq!(result, case.expected_result, "Failed on: {}", case.description);
-
-            let output = String::from_utf8(writer).unwrap();
-            assert_eq!(output, "Do you want to empty? [Y/n]: ");
-        }
-    }
-
-    #[test]
-    fn test_confirm_input_invalid_then_valid() {
-        let input = "maybe\nyes\n";
-        let mut reader = Cursor::new(input);
-        let mut writer = Vec::new();
-        let message = "Do you want to empty? [Y/n]: ".to_string();
-
-        let result = confirm_input(&mut writer, &mut reader, message).unwrap();
-
-        assert!(result, "Should return true after an invalid input");
-
-        let output = String::from_utf8(writer).unwrap();
-        let expected_prompt = "Do you want to empty? [Y/n]: ";
-        assert_eq!(
-            output,
-            format!("{}{}", expected_prompt, expected_prompt),
-            "Should re-prompt after invalid input"
-        );
-    }
-
     #[test]
     fn test_empty_single_trash_dir() -> Result<(), AppError> {
         let trash_root = tempdir()?;
@@ -199,7 +410,7 @@ mod tests {
         File::create(files_dir.join("some_file.txt"))?;
         File::create(info_dir.join("some_file.txt.trashinfo"))?;
 
-        empty_single_trash_dir(trash_root.path())?;
+        empty_single_trash_dir(trash_root.path(), false)?;
 
         // Check that the 'files' and 'info' directories still exist.
         assert!(files_dir.exists(), "'files' directory should be recreated.");
@@ -215,6 +426,165 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_empty_and_measure_reports_freed_size() -> Result<(), AppError> {
+        let trash_root = tempdir()?;
+        let files_dir = trash_root.path().join(TRASH_FILES_DIR_NAME);
+        let info_dir = trash_root.path().join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(files_dir.join("project/src"))?;
+        fs::create_dir_all(&info_dir)?;
+        fs::write(files_dir.join("video.mp4"), vec![0u8; 3 * 1024 * 1024])?;
+        fs::write(files_dir.join("project/README"), vec![b'r'; 512])?;
+        fs::write(files_dir.join("project/src/main.rs"), vec![b'm'; 512])?;
+        // Info files are not counted: they are metadata, not the trashed data.
+        fs::write(info_dir.join("video.mp4.trashinfo"), "[Trash Info]\n")?;
+
+        let freed = empty_and_measure(trash_root.path())?;
+
+        assert_eq!(freed, 3 * 1024 * 1024 + 1024);
+        assert_eq!(format_size(freed, BINARY), "3.00 MiB");
+        assert_eq!(fs::read_dir(&files_dir)?.count(), 0);
+        assert_eq!(empty_and_measure(trash_root.path())?, 0, "nothing is left to free");
+        Ok(())
+    }
+
+    fn write_entry(trash_root: &Path, name: &str, deletion_date: &str, size: usize) -> Result<(), AppError> {
+        let files_dir = trash_root.join(TRASH_FILES_DIR_NAME);
+        let info_dir = trash_root.join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(&files_dir)?;
+        fs::create_dir_all(&info_dir)?;
+        fs::write(files_dir.join(name), vec![0u8; size])?;
+        fs::write(
+            info_dir.join(format!("{}.trashinfo", name)),
+            format!(
+                "[Trash Info]\nPath=/home/user/{}\nDeletionDate={}\n",
+                name, deletion_date
+            ),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_matching_entries_by_size_and_age() -> Result<(), AppError> {
+        let trash_root = tempdir()?;
+        write_entry(trash_root.path(), "big-old.mkv", "2024-01-01T00:00:00", 2048)?;
+        write_entry(trash_root.path(), "big-new.mkv", "2024-03-01T00:00:00", 2048)?;
+        write_entry(trash_root.path(), "small-old.txt", "2024-01-01T00:00:00", 10)?;
+
+        let now = NaiveDateTime::parse_from_str("2024-03-02T00:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        let size_only = EntryFilter {
+            larger_than: Some(1024),
+            ..Default::default()
+        };
+        let mut names: Vec<String> = select_matching_entries(trash_root.path(), &size_only, now)
+            .iter()
+            .map(|(entry, _)| entry.trashed_path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["big-new.mkv", "big-old.mkv"]);
+
+        let size_and_age = EntryFilter {
+            larger_than: Some(1024),
+            older_than: Some(chrono::TimeDelta::days(30)),
+            ..Default::default()
+        };
+        let matched = select_matching_entries(trash_root.path(), &size_and_age, now);
+        assert_eq!(matched.len(), 1);
+        assert!(matched[0].0.trashed_path.ends_with("big-old.mkv"));
+        assert_eq!(matched[0].1, 2048);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_matching_entries_by_date_range() -> Result<(), AppError> {
+        let trash_root = tempdir()?;
+        write_entry(trash_root.path(), "monday.txt", "2024-01-01T23:59:59", 1)?;
+        write_entry(trash_root.path(), "tuesday-start.txt", "2024-01-02T00:00:00", 1)?;
+        write_entry(trash_root.path(), "tuesday-end.txt", "2024-01-02T23:59:59", 1)?;
+        write_entry(trash_root.path(), "wednesday.txt", "2024-01-03T00:00:00", 1)?;
+        let now = NaiveDateTime::parse_from_str("2024-03-02T00:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        let select = |since: Option<&str>, until: Option<&str>| -> Vec<String> {
+            let filter = EntryFilter {
+                deleted_since: since.map(|since| parse_since(since).unwrap()),
+                deleted_until: until.map(|until| parse_until(until).unwrap()),
+                ..Default::default()
+            };
+            let mut names: Vec<String> = select_matching_entries(trash_root.path(), &filter, now)
+                .iter()
+                .map(|(entry, _)| entry.trashed_path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(
+            select(Some("2024-01-02"), Some("2024-01-02")),
+            vec!["tuesday-end.txt", "tuesday-start.txt"]
+        );
+        assert_eq!(
+            select(Some("2024-01-02T00:00:00"), Some("2024-01-03T00:00:00")),
+            vec!["tuesday-end.txt", "tuesday-start.txt"]
+        );
+        assert_eq!(select(Some("2024-01-03"), None), vec!["wednesday.txt"]);
+        assert_eq!(select(None, Some("2024-01-02T00:00:00")), vec!["monday.txt"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_purge_entry_removes_directory_and_info() -> Result<(), AppError> {
+        let trash_root = tempdir()?;
+        write_entry(trash_root.path(), "project", "2024-01-01T00:00:00", 0)?;
+        let data = trash_root.path().join(TRASH_FILES_DIR_NAME).join("project");
+        fs::remove_file(&data)?;
+        fs::create_dir_all(data.join("src"))?;
+        fs::write(data.join("src/main.rs"), vec![0u8; 300])?;
+        fs::write(data.join("README"), vec![0u8; 12])?;
+        let entries = find_trash_entries_in_dirs(&[trash_root.path().to_path_buf()]);
+
+        assert_eq!(purge_entry(&entries[0])?, 312);
+        assert!(!entries[0].trashed_path.exists());
+        assert!(!entries[0].info_path.exists());
+        assert_eq!(fs::read_dir(trash_root.path().join(TRASH_FILES_DIR_NAME))?.count(), 0);
+        assert_eq!(fs::read_dir(trash_root.path().join(TRASH_INFO_DIR_NAME))?.count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_entry_deletes_data_and_info() -> Result<(), AppError> {
+        let trash_root = tempdir()?;
+        write_entry(trash_root.path(), "doomed.bin", "2024-01-01T00:00:00", 16)?;
+        let entries = find_trash_entries_in_dirs(&[trash_root.path().to_path_buf()]);
+        assert_eq!(entries.len(), 1);
+
+        remove_entry(&entries[0])?;
+
+        assert!(!entries[0].trashed_path.exists());
+        assert!(!entries[0].info_path.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_single_trash_dir_dry_run_changes_nothing() -> Result<(), AppError> {
+        let trash_root = tempdir()?;
+        let files_dir = trash_root.path().join(TRASH_FILES_DIR_NAME);
+        let info_dir = trash_root.path().join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(files_dir.join("dir"))?;
+        fs::create_dir_all(&info_dir)?;
+        File::create(files_dir.join("dir").join("inner.txt"))?;
+        File::create(info_dir.join("dir.trashinfo"))?;
+
+        let doomed = empty_single_trash_dir(trash_root.path(), true)?;
+
+        assert_eq!(doomed, vec![files_dir.join("dir"), info_dir.join("dir.trashinfo")]);
+        assert!(files_dir.join("dir").join("inner.txt").exists());
+        assert!(info_dir.join("dir.trashinfo").exists());
+        Ok(())
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_empty_single_trash_dir_permission_error() -> Result<(), AppError> {
@@ -227,7 +597,7 @@ mod tests {
         perms.set_mode(0o555); // r-xr-xr-x
         fs::set_permissions(trash_root.path(), perms)?;
 
-        let result = empty_single_trash_dir(trash_root.path());
+        let result = empty_single_trash_dir(trash_root.path(), false);
 
         assert!(result.is_err(), "Expected an error due to permission issues");
         if let Err(AppError::Io { path, .. }) = result {
@@ -16,6 +16,14 @@ pub enum AppError {
         source: io::Error,
     },
 
+    /// The file given with `--output` could not be created.
+    #[error("Cannot write output to '{path}': {source}")]
+    OutputFile {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
     /// A generic I/O error without a specific path.
     #[error("I/O error: {0}")]
     GenericIo(#[from] io::Error),
@@ -32,6 +40,10 @@ pub enum AppError {
     #[error("Trashed item '{path}' not found. The trash directory might be in an inconsistent state.")]
     TrashedItemNotFound { path: PathBuf },
 
+    /// The directory given with `--trash-dir` lacks the `files` and `info` subdirectories of a trash.
+    #[error("'{path}' is not a trash directory (it needs 'files' and 'info' subdirectories).")]
+    NotATrashDirectory { path: PathBuf },
+
     /// No trash directories (e.g., ~/.local/share/Trash) could be found.
     #[error("No trash directories found.")]
     NoTrashDirectories,
@@ -40,6 +52,18 @@ pub enum AppError {
     #[error("Item '{path}' is already in the trash.")]
     AlreadyInTrash { path: PathBuf },
 
+    /// The source is a system directory, the home directory or the working directory.
+    #[error("Refusing to trash protected path '{path}' (use --allow-protected to trash it anyway).")]
+    ProtectedPath { path: PathBuf },
+
+    /// With `--protect-tracked`, the source is tracked by a git repository.
+    #[error("Refusing to trash '{path}': it is tracked by the git repository at '{repository}' (use --allow-protected to trash it anyway).")]
+    TrackedByGit { path: PathBuf, repository: PathBuf },
+
+    /// The source already sits in the `files` directory it would be moved to.
+    #[error("Item '{path}' is already in the trash files directory it would be moved to.")]
+    SourceInTrashFilesDir { path: PathBuf },
+
     /// Occurs when trying to trash an item that is already in a trash directory.
     #[error("Trash '{path}' is symbolic link.")]
     SymbolicLink { path: PathBuf },
@@ -48,6 +72,20 @@ pub enum AppError {
     #[error("Cross-device move not supported for '{path}'. The destination is on a different filesystem.")]
     CrossDeviceMove { path: PathBuf },
 
+    /// The filesystem that would hold the trash is mounted read-only.
+    #[error("Cannot trash into '{path}': its filesystem is mounted read-only.")]
+    ReadOnlyFilesystem { path: PathBuf },
+
+    /// The filesystem holding the trash has no space left for the item or its info file.
+    #[error("The trash filesystem is full; could not write '{path}'. Free some space or empty the trash.")]
+    TrashFull { path: PathBuf },
+
+    /// No free name was found in the trash within the configured number of attempts.
+    #[error(
+        "Gave up finding a free name in the trash for '{path}' after {attempts} attempts. Consider emptying the trash."
+    )]
+    TooManyCollisions { path: PathBuf, attempts: u32 },
+
     /// Error originating from the `mountpoints` crate.
     #[error("Failed to read mount points: {0}")]
     Mountpoints(#[from] mountpoints::Error),
@@ -60,6 +98,10 @@ pub enum AppError {
     #[error("UTF-8 conversion error: {0}")]
     FromUtf8(#[from] FromUtf8Error),
 
+    /// An invalid combination of command-line options.
+    #[error("Invalid arguments: {detail}")]
+    InvalidArgument { detail: String },
+
     /// A generic, message-based error.
     #[error("{0}")]
     Message(String),
@@ -67,6 +109,32 @@ pub enum AppError {
     /// No output error
     #[error("Ignorable Error")]
     Ignorable,
+
+    /// There was nothing to act on (e.g. every item was declined, or the trash is empty).
+    /// Already reported to the user, and not a failure.
+    #[error("Nothing to do")]
+    NothingToDo,
+}
+
+/// The exit status of a successful run, and of one that had nothing to do.
+pub const EXIT_SUCCESS: i32 = 0;
+
+/// The exit status of a run that failed, including one whose errors were already reported.
+pub const EXIT_FAILURE: i32 = 1;
+
+/// The exit status of a run with invalid command-line arguments, as for clap's own usage errors.
+pub const EXIT_USAGE: i32 = 2;
+
+impl AppError {
+    /// The status the process exits with for this error, so scripts can tell a no-op
+    /// and a usage mistake apart from a real failure.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::NothingToDo => EXIT_SUCCESS,
+            AppError::InvalidArgument { .. } => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
 }
 
 /// Allows converting from a string slice to our custom error type.
@@ -75,3 +143,48 @@ impl From<&str> for AppError {
         AppError::Message(s.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        struct TestCase {
+            error: AppError,
+            expected: i32,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                error: AppError::NothingToDo,
+                expected: EXIT_SUCCESS,
+            },
+            TestCase {
+                error: AppError::InvalidArgument {
+                    detail: "--bytes can only be used with the long listing (-l)".to_string(),
+                },
+                expected: EXIT_USAGE,
+            },
+            TestCase {
+                error: AppError::Ignorable,
+                expected: EXIT_FAILURE,
+            },
+            TestCase {
+                error: AppError::Io {
+                    path: PathBuf::from("/tmp/x"),
+                    source: io::Error::from(io::ErrorKind::PermissionDenied),
+                },
+                expected: EXIT_FAILURE,
+            },
+            TestCase {
+                error: AppError::NoTrashDirectories,
+                expected: EXIT_FAILURE,
+            },
+        ];
+
+        for case in test_cases {
+            assert_eq!(case.error.exit_code(), case.expected, "{:?}", case.error);
+        }
+    }
+}
@@ -1,13 +1,28 @@
 use std::fs;
 use std::path::Path;
 
+use once_cell::sync::OnceCell;
+
+use crate::trash::file_type_config::{file_type_overrides, FileTypeOverrides};
+
 const CONFIG_EXTENSIONS: &[&str] = &[
-    "toml", "yaml", "yml", "json", "conf", "ini", "env", "gradle", "xml", "cfg",
+    "toml", "yaml", "yml", "json", "conf", "ini", "env", "gradle", "xml", "cfg", "nix",
 ];
 
+/// Well-known config file names, in their canonical case (used as-is by case-sensitive matching).
 const CONFIG_FILENAMES: &[&str] = &[
-    "makefile",
-    "cargo.toml",
+    "Makefile",
+    "CMakeLists.txt",
+    "justfile",
+    "Taskfile.yml",
+    "flake.nix",
+    "flake.lock",
+    ".envrc",
+    ".nvmrc",
+    "renovate.json",
+    "deno.json",
+    "biome.json",
+    "Cargo.toml",
     "package.json",
     "package-lock.json",
     "yarn.lock",
@@ -15,17 +30,17 @@ const CONFIG_FILENAMES: &[&str] = &[
     "composer.json",
     "pom.xml",
     "build.gradle",
-    "gemfile",
-    "pipfile",
-    "pipfile.lock",
+    "Gemfile",
+    "Pipfile",
+    "Pipfile.lock",
     "requirements.txt",
     "pyproject.toml",
     "setup.py",
     "setup.cfg",
-    "dockerfile",
+    "Dockerfile",
     "docker-compose.yml",
-    "license",
-    "license.txt",
+    "LICENSE",
+    "LICENSE.txt",
     ".editorconfig",
     ".gitignore",
     ".gitattributes",
@@ -36,7 +51,7 @@ const CONFIG_FILENAMES: &[&str] = &[
     "webpack.config.js",
     "vite.config.js",
     "rollup.config.js",
-    "vagrantfile",
+    "Vagrantfile",
 ];
 
 const ARCHIVE_EXTENSIONS: &[&str] = &[
@@ -50,11 +65,24 @@ const IMAGE_EXTENSIONS: &[&str] = &[
 ];
 const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mkv", "mov", "avi", "webm", "mpeg", "mpg", "flv", "wmv", "3gp"];
 const MUSIC_EXTENSIONS: &[&str] = &["mp3", "flac", "m4a", "wav", "ogg", "aac", "alac", "aiff", "opus"];
+const DATABASE_EXTENSIONS: &[&str] = &["db", "sqlite", "sqlite3", "mdb", "parquet", "duckdb"];
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "woff", "woff2", "eot"];
+/// Checked after the config rules, so `vite.config.js` or `setup.py` stay `Config`.
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "rs", "c", "h", "cc", "cpp", "cxx", "hpp", "py", "js", "mjs", "cjs", "jsx", "ts", "tsx", "go", "java", "kt", "kts",
+    "scala", "swift", "rb", "php", "cs", "lua", "pl", "hs", "ex", "exs", "erl", "dart", "zig", "sh", "bash", "zsh",
+    "vue", "svelte", "html", "css", "scss",
+];
 
 /// Represents the classified type of a file or directory.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum FileType {
     Directory,
+    Symlink,
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
     Executable,
     Archive,
     Config,
@@ -62,48 +90,150 @@ pub enum FileType {
     Image,
     Video,
     Music,
+    Database,
+    Font,
+    Source,
     Other,
 }
 
-/// Determines the `FileType` of a given path.
+impl FileType {
+    /// Returns the type a user may assign by name in the file type config, e.g. `"document"`.
+    /// Types that depend on the file itself (directories, executables, symlinks, ...) cannot be assigned.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "archive" => Some(FileType::Archive),
+            "config" => Some(FileType::Config),
+            "document" => Some(FileType::Document),
+            "image" => Some(FileType::Image),
+            "video" => Some(FileType::Video),
+            "music" => Some(FileType::Music),
+            "database" => Some(FileType::Database),
+            "font" => Some(FileType::Font),
+            "source" => Some(FileType::Source),
+            "other" => Some(FileType::Other),
+            _ => None,
+        }
+    }
+}
+
+/// Decides the `FileType` of paths. Extensions always match case-insensitively;
+/// whether well-known file names (e.g. `Makefile`) do is configurable.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileTypeClassifier {
+    /// Match names in `CONFIG_FILENAMES` exactly, so `makefile` is not taken for `Makefile`.
+    pub case_sensitive_names: bool,
+}
+
+/// The classifier chosen at startup; the default one until `set_file_type_classifier` is called.
+static CLASSIFIER: OnceCell<FileTypeClassifier> = OnceCell::new();
+
+/// Sets the classifier used by `get_file_type` for the rest of the run. Only the first call counts.
+pub fn set_file_type_classifier(classifier: FileTypeClassifier) {
+    let _ = CLASSIFIER.set(classifier);
+}
+
+/// Determines the `FileType` of a given path with the active classifier.
 pub fn get_file_type(path: &Path) -> FileType {
-    if path.is_dir() {
-        return FileType::Directory;
+    CLASSIFIER.get().copied().unwrap_or_default().classify(path)
+}
+
+impl FileTypeClassifier {
+    fn is_config_filename(&self, filename: &str) -> bool {
+        if self.case_sensitive_names {
+            CONFIG_FILENAMES.contains(&filename)
+        } else {
+            CONFIG_FILENAMES.iter().any(|name| name.eq_ignore_ascii_case(filename))
+        }
     }
 
-    if is_executable(path) {
-        return FileType::Executable;
+    /// Determines the `FileType` of a given path. A symlink is classified as such, not by its target.
+    /// Names and extensions mapped in the user's file type config take precedence over the built-in lists.
+    pub fn classify(&self, path: &Path) -> FileType {
+        self.classify_with(path, file_type_overrides())
     }
 
-    let filename_lower = path.file_name().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
-    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
-
-    // Match by exact filename, prefix, suffix, or extension
-    if CONFIG_EXTENSIONS.contains(&extension.as_str())
-        || CONFIG_FILENAMES.contains(&filename_lower.as_str())
-        || filename_lower.starts_with(".env")
-        || filename_lower.ends_with(".config.js")
-        || filename_lower.ends_with(".config.mjs")
-        || filename_lower.ends_with(".config.ts")
-        || filename_lower.ends_with("rc")
-    {
-        return FileType::Config;
+    fn classify_with(&self, path: &Path, overrides: Option<&FileTypeOverrides>) -> FileType {
+        if let Some(file_type) = special_file_type(path) {
+            return file_type;
+        }
+
+        if path.is_dir() {
+            return FileType::Directory;
+        }
+
+        if is_executable(path) {
+            return FileType::Executable;
+        }
+
+        let filename = path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+        let filename_lower = filename.to_lowercase();
+        let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+
+        if let Some(file_type) =
+            overrides.and_then(|overrides| overrides.lookup(filename, &extension, self.case_sensitive_names))
+        {
+            return file_type;
+        }
+
+        // Match by exact filename, prefix, suffix, or extension
+        if CONFIG_EXTENSIONS.contains(&extension.as_str())
+            || self.is_config_filename(filename)
+            || filename_lower.starts_with(".env")
+            || filename_lower.ends_with(".config.js")
+            || filename_lower.ends_with(".config.mjs")
+            || filename_lower.ends_with(".config.ts")
+            || filename_lower.ends_with("rc")
+        {
+            return FileType::Config;
+        }
+
+        if ARCHIVE_EXTENSIONS.contains(&extension.as_str()) {
+            return FileType::Archive;
+        } else if DOCUMENT_EXTENSIONS.contains(&extension.as_str()) {
+            return FileType::Document;
+        } else if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+            return FileType::Image;
+        } else if VIDEO_EXTENSIONS.contains(&extension.as_str()) {
+            return FileType::Video;
+        } else if MUSIC_EXTENSIONS.contains(&extension.as_str()) {
+            return FileType::Music;
+        } else if DATABASE_EXTENSIONS.contains(&extension.as_str()) {
+            return FileType::Database;
+        } else if FONT_EXTENSIONS.contains(&extension.as_str()) {
+            return FileType::Font;
+        } else if SOURCE_EXTENSIONS.contains(&extension.as_str()) {
+            return FileType::Source;
+        }
+
+        // If no specific type was found
+        FileType::Other
     }
+}
 
-    if ARCHIVE_EXTENSIONS.contains(&extension.as_str()) {
-        return FileType::Archive;
-    } else if DOCUMENT_EXTENSIONS.contains(&extension.as_str()) {
-        return FileType::Document;
-    } else if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
-        return FileType::Image;
-    } else if VIDEO_EXTENSIONS.contains(&extension.as_str()) {
-        return FileType::Video;
-    } else if MUSIC_EXTENSIONS.contains(&extension.as_str()) {
-        return FileType::Music;
+/// Detects symlinks, FIFOs, sockets and devices, without following a symlink.
+#[cfg(unix)]
+fn special_file_type(path: &Path) -> Option<FileType> {
+    use std::os::unix::fs::FileTypeExt;
+    let file_type = fs::symlink_metadata(path).ok()?.file_type();
+    if file_type.is_symlink() {
+        Some(FileType::Symlink)
+    } else if file_type.is_fifo() {
+        Some(FileType::Fifo)
+    } else if file_type.is_socket() {
+        Some(FileType::Socket)
+    } else if file_type.is_block_device() {
+        Some(FileType::BlockDevice)
+    } else if file_type.is_char_device() {
+        Some(FileType::CharDevice)
+    } else {
+        None
     }
+}
 
-    // If no specific type was found
-    FileType::Other
+/// Fallback for non-Unix systems, which only have symlinks among the special file types.
+#[cfg(not(unix))]
+fn special_file_type(path: &Path) -> Option<FileType> {
+    path.is_symlink().then_some(FileType::Symlink)
 }
 
 /// Checks if a file is executable (Unix-like OS only).
@@ -156,6 +286,61 @@ mod tests {
                 expected: FileType::Config,
                 description: "rc suffix",
             },
+            TestCase {
+                path: "CMakeLists.txt",
+                expected: FileType::Config,
+                description: "CMake build file despite its .txt extension",
+            },
+            TestCase {
+                path: "justfile",
+                expected: FileType::Config,
+                description: "just recipes",
+            },
+            TestCase {
+                path: "Justfile",
+                expected: FileType::Config,
+                description: "just recipes, capitalized",
+            },
+            TestCase {
+                path: "Taskfile.yml",
+                expected: FileType::Config,
+                description: "Task runner file",
+            },
+            TestCase {
+                path: "flake.nix",
+                expected: FileType::Config,
+                description: "Nix flake",
+            },
+            TestCase {
+                path: "flake.lock",
+                expected: FileType::Config,
+                description: "Nix flake lock file",
+            },
+            TestCase {
+                path: "shell.nix",
+                expected: FileType::Config,
+                description: ".nix extension",
+            },
+            TestCase {
+                path: ".envrc",
+                expected: FileType::Config,
+                description: "direnv file",
+            },
+            TestCase {
+                path: ".nvmrc",
+                expected: FileType::Config,
+                description: "nvm version file",
+            },
+            TestCase {
+                path: "renovate.json",
+                expected: FileType::Config,
+                description: "Renovate config",
+            },
+            TestCase {
+                path: "biome.json",
+                expected: FileType::Config,
+                description: "Biome config",
+            },
             // Archives
             TestCase {
                 path: "archive.zip",
@@ -194,6 +379,74 @@ mod tests {
                 expected: FileType::Music,
                 description: "FLAC music",
             },
+            // Databases
+            TestCase {
+                path: "app.sqlite3",
+                expected: FileType::Database,
+                description: "SQLite database",
+            },
+            TestCase {
+                path: "events.parquet",
+                expected: FileType::Database,
+                description: "Parquet file",
+            },
+            TestCase {
+                path: "Legacy.MDB",
+                expected: FileType::Database,
+                description: "Access database, upper-case extension",
+            },
+            // Fonts
+            TestCase {
+                path: "Inter-Regular.otf",
+                expected: FileType::Font,
+                description: "OpenType font",
+            },
+            TestCase {
+                path: "icons.woff2",
+                expected: FileType::Font,
+                description: "WOFF2 web font",
+            },
+            // Source code
+            TestCase {
+                path: "main.rs",
+                expected: FileType::Source,
+                description: "Rust source",
+            },
+            TestCase {
+                path: "parser.c",
+                expected: FileType::Source,
+                description: "C source",
+            },
+            TestCase {
+                path: "app.py",
+                expected: FileType::Source,
+                description: "Python source",
+            },
+            TestCase {
+                path: "index.js",
+                expected: FileType::Source,
+                description: "JavaScript source",
+            },
+            TestCase {
+                path: "server.go",
+                expected: FileType::Source,
+                description: "Go source",
+            },
+            TestCase {
+                path: "Main.JAVA",
+                expected: FileType::Source,
+                description: "Java source, upper-case extension",
+            },
+            TestCase {
+                path: "vite.config.js",
+                expected: FileType::Config,
+                description: "JavaScript config stays config",
+            },
+            TestCase {
+                path: "setup.py",
+                expected: FileType::Config,
+                description: "Python setup script stays config",
+            },
             // Edge cases
             TestCase {
                 path: ".bashrc",
@@ -227,4 +480,83 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_case_sensitive_names() {
+        let classifier = FileTypeClassifier {
+            case_sensitive_names: true,
+        };
+        assert_eq!(classifier.classify(Path::new("Makefile")), FileType::Config);
+        assert_eq!(classifier.classify(Path::new("CMakeLists.txt")), FileType::Config);
+        assert_eq!(classifier.classify(Path::new("makefile")), FileType::Other);
+        assert_eq!(classifier.classify(Path::new("cmakelists.txt")), FileType::Document);
+        // Extensions still match regardless of case.
+        assert_eq!(classifier.classify(Path::new("settings.TOML")), FileType::Config);
+        assert_eq!(
+            FileTypeClassifier::default().classify(Path::new("makefile")),
+            FileType::Config
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_classify_special_files() -> Result<(), std::io::Error> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempfile::tempdir()?;
+        let target_dir = dir.path().join("target");
+        fs::create_dir(&target_dir)?;
+        let dir_link = dir.path().join("dir-link");
+        std::os::unix::fs::symlink(&target_dir, &dir_link)?;
+        let dangling_link = dir.path().join("dangling.txt");
+        std::os::unix::fs::symlink(dir.path().join("missing"), &dangling_link)?;
+        let fifo = dir.path().join("pipe");
+        let fifo_c = CString::new(fifo.as_os_str().as_bytes())?;
+        // SAFETY: `fifo_c` is a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(fifo_c.as_ptr(), 0o644) }, 0, "mkfifo failed");
+        let socket = dir.path().join("app.sock");
+        let _listener = UnixListener::bind(&socket)?;
+
+        let classifier = FileTypeClassifier::default();
+        assert_eq!(classifier.classify(&target_dir), FileType::Directory);
+        assert_eq!(
+            classifier.classify(&dir_link),
+            FileType::Symlink,
+            "a symlink to a directory"
+        );
+        assert_eq!(
+            classifier.classify(&dangling_link),
+            FileType::Symlink,
+            "a dangling symlink"
+        );
+        assert_eq!(classifier.classify(&fifo), FileType::Fifo);
+        assert_eq!(classifier.classify(&socket), FileType::Socket);
+        assert_eq!(classifier.classify(Path::new("/dev/null")), FileType::CharDevice);
+        Ok(())
+    }
+
+    #[test]
+    fn test_classify_with_overrides() {
+        let overrides = FileTypeOverrides::parse(
+            "[extensions]\nrs = \"document\"\nzip = \"other\"\n[filenames]\n\"NOTES\" = \"image\"\n",
+        )
+        .unwrap();
+        let classifier = FileTypeClassifier::default();
+        let classify = |path: &str| classifier.classify_with(Path::new(path), Some(&overrides));
+
+        assert_eq!(classify("main.rs"), FileType::Document, "an added extension");
+        assert_eq!(
+            classify("backup.ZIP"),
+            FileType::Other,
+            "an extension recolored over the built-in"
+        );
+        assert_eq!(classify("notes"), FileType::Image, "a file name");
+        // Anything not in the config falls back to the built-in lists.
+        assert_eq!(classify("photo.png"), FileType::Image);
+        assert_eq!(classify("Cargo.toml"), FileType::Config);
+        assert_eq!(classify("unknown.file"), FileType::Other);
+        assert_eq!(classifier.classify_with(Path::new("main.rs"), None), FileType::Source);
+    }
 }
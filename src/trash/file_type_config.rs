@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::OnceCell;
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+
+use crate::trash::error::AppError;
+use crate::trash::file_type::FileType;
+
+/// The name of the user's file type mappings under the `trash-tool` config directory.
+const FILE_TYPE_CONFIG_NAME: &str = "filetypes.toml";
+
+/// The mappings loaded at startup, if the config file exists and is valid.
+static FILE_TYPE_OVERRIDES: OnceCell<FileTypeOverrides> = OnceCell::new();
+
+/// User-defined file types that take precedence over the built-in extension and name lists.
+///
+/// The config file is TOML with two optional tables, each mapping a key to the name of a
+/// file type (`archive`, `config`, `document`, `image`, `video`, `music`, `database`, `font`,
+/// `source` or `other`):
+///
+/// ```toml
+/// [extensions]
+/// rs = "document"
+/// log = "other"
+///
+/// [filenames]
+/// "Brewfile" = "config"
+/// ```
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct FileTypeOverrides {
+    /// Extensions, with or without the leading dot; lower-cased by `parse`.
+    #[serde(default)]
+    extensions: HashMap<String, FileType>,
+    /// Whole file names, as written in the config file.
+    #[serde(default)]
+    filenames: HashMap<String, FileType>,
+}
+
+impl<'de> Deserialize<'de> for FileType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        FileType::from_name(&name).ok_or_else(|| de::Error::custom(format!("unknown file type '{}'", name)))
+    }
+}
+
+impl FileTypeOverrides {
+    /// Parses the config file content. An error names the line it was found on.
+    pub(crate) fn parse(content: &str) -> Result<Self, AppError> {
+        let mut overrides: FileTypeOverrides = toml::from_str(content).map_err(|e| {
+            let line = e
+                .span()
+                .map(|span| content[..span.start].matches('\n').count() + 1)
+                .unwrap_or(1);
+            let message: Vec<&str> = e.message().lines().collect();
+            AppError::Message(format!("line {}: {}", line, message.join("; ")))
+        })?;
+        overrides.extensions = overrides
+            .extensions
+            .into_iter()
+            .map(|(extension, file_type)| {
+                (
+                    extension.strip_prefix('.').unwrap_or(&extension).to_lowercase(),
+                    file_type,
+                )
+            })
+            .collect();
+        Ok(overrides)
+    }
+
+    /// Returns the configured type of `filename`: a file name entry first, then its extension.
+    /// File names compare case-insensitively unless `case_sensitive` is set, with an entry of
+    /// exactly the same case preferred; extensions always compare case-insensitively.
+    pub(crate) fn lookup(&self, filename: &str, extension: &str, case_sensitive: bool) -> Option<FileType> {
+        self.filenames
+            .get(filename)
+            .or_else(|| {
+                self.filenames
+                    .iter()
+                    .filter(|_| !case_sensitive)
+                    .find(|(name, _)| name.eq_ignore_ascii_case(filename))
+                    .map(|(_, file_type)| file_type)
+            })
+            .copied()
+            .or_else(|| self.extensions.get(&extension.to_lowercase()).copied())
+    }
+}
+
+/// The mappings loaded by `load_file_type_config`, if any.
+pub(crate) fn file_type_overrides() -> Option<&'static FileTypeOverrides> {
+    FILE_TYPE_OVERRIDES.get()
+}
+
+/// Returns where the user's file type mappings are read from, e.g. `~/.config/trash-tool/filetypes.toml`.
+fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("trash-tool").join(FILE_TYPE_CONFIG_NAME))
+}
+
+/// Loads the user's file type mappings for the rest of the run. A missing file is not an
+/// error; an unreadable or malformed one is reported and the built-in types are kept.
+pub fn load_file_type_config() {
+    let Some(path) = default_config_path() else {
+        return;
+    };
+    match read_config(&path) {
+        Ok(Some(overrides)) => {
+            let _ = FILE_TYPE_OVERRIDES.set(overrides);
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("warning: Ignoring {}: {}", path.display(), e),
+    }
+}
+
+fn read_config(path: &Path) -> Result<Option<FileTypeOverrides>, AppError> {
+    match fs::read_to_string(path) {
+        Ok(content) => FileTypeOverrides::parse(&content).map(Some),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(AppError::Io {
+            path: path.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_file_type_overrides() {
+        let overrides = FileTypeOverrides::parse(
+            "# My types\n\
+             [extensions]\n\
+             rs = \"document\"   # source code\n\
+             \".LOG\" = \"other\"\n\
+             \n\
+             [filenames]\n\
+             \"Brewfile\" = \"config\"\n\
+             \"notes #1.txt\" = \"image\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(overrides.lookup("main.rs", "rs", false), Some(FileType::Document));
+        assert_eq!(overrides.lookup("app.log", "log", false), Some(FileType::Other));
+        assert_eq!(overrides.lookup("brewfile", "", false), Some(FileType::Config));
+        assert_eq!(overrides.lookup("brewfile", "", true), None);
+        assert_eq!(overrides.lookup("notes #1.txt", "txt", false), Some(FileType::Image));
+        assert_eq!(overrides.lookup("photo.png", "png", false), None);
+    }
+
+    #[test]
+    fn test_lookup_prefers_file_name_of_same_case() {
+        let overrides = FileTypeOverrides::parse(
+            "[filenames]\n\
+             \"README\" = \"document\"\n\
+             \"readme\" = \"other\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(overrides.lookup("README", "", false), Some(FileType::Document));
+        assert_eq!(overrides.lookup("readme", "", false), Some(FileType::Other));
+        assert_eq!(overrides.lookup("ReadMe", "", true), None);
+        assert!(overrides.lookup("ReadMe", "", false).is_some());
+    }
+
+    #[test]
+    fn test_parse_file_type_overrides_rejects_malformed_content() {
+        struct TestCase {
+            content: &'static str,
+            expected: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                content: "rs = \"document\"\n",
+                expected: "line 1: unknown field `rs`, expected `extensions` or `filenames`",
+            },
+            TestCase {
+                content: "[colors]\n",
+                expected: "line 1: unknown field `colors`, expected `extensions` or `filenames`",
+            },
+            TestCase {
+                content: "[extensions]\nrs = \"code\"\n",
+                expected: "line 2: unknown file type 'code'",
+            },
+            TestCase {
+                content: "[extensions]\nrs = document\n",
+                expected: "line 2: invalid string; expected `\"`, `'`",
+            },
+            TestCase {
+                content: "[extensions]\nrs\n",
+                expected: "line 2: expected `.`, `=`",
+            },
+        ];
+
+        for case in test_cases {
+            let result = FileTypeOverrides::parse(case.content);
+            assert!(
+                matches!(&result, Err(AppError::Message(message)) if message == case.expected),
+                "{:?}: {:?}",
+                case.content,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_config() -> Result<(), AppError> {
+        let dir = tempdir()?;
+        let path = dir.path().join(FILE_TYPE_CONFIG_NAME);
+        assert_eq!(read_config(&path)?, None, "a missing file is not an error");
+
+        fs::write(&path, "[extensions]\ngo = \"document\"\n")?;
+        let overrides = read_config(&path)?.expect("the config is loaded");
+        assert_eq!(overrides.lookup("main.go", "go", false), Some(FileType::Document));
+
+        fs::write(&path, "[extensions\n")?;
+        assert!(read_config(&path).is_err());
+        Ok(())
+    }
+}
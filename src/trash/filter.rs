@@ -0,0 +1,240 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+
+use crate::trash::error::AppError;
+use crate::trash::restoring::parse_deletion_date;
+
+/// Criteria for selecting trash entries, e.g. `--empty --larger-than 100M --older-than 30d`.
+/// An entry matches only when every configured condition holds.
+#[derive(Default, Clone)]
+pub struct EntryFilter {
+    pub larger_than: Option<u64>,
+    pub older_than: Option<TimeDelta>,
+    /// Only entries deleted at or after this time (`--since`, inclusive).
+    pub deleted_since: Option<NaiveDateTime>,
+    /// Only entries deleted before this time (`--until`, exclusive).
+    pub deleted_until: Option<NaiveDateTime>,
+}
+
+impl EntryFilter {
+    /// Returns true if at least one condition is configured.
+    pub fn is_active(&self) -> bool {
+        self.larger_than.is_some()
+            || self.older_than.is_some()
+            || self.deleted_since.is_some()
+            || self.deleted_until.is_some()
+    }
+
+    /// Checks an entry of `size` bytes deleted at `deleted_at` against the filter.
+    /// An entry whose deletion date is unknown never satisfies `older_than` or the date window.
+    pub fn matches(&self, size: u64, deleted_at: Option<NaiveDateTime>, now: NaiveDateTime) -> bool {
+        if let Some(threshold) = self.larger_than {
+            if size <= threshold {
+                return false;
+            }
+        }
+        if let Some(min_age) = self.older_than {
+            match deleted_at {
+                Some(deleted_at) if now - deleted_at > min_age => {}
+                _ => return false,
+            }
+        }
+        if let Some(since) = self.deleted_since {
+            match deleted_at {
+                Some(deleted_at) if deleted_at >= since => {}
+                _ => return false,
+            }
+        }
+        if let Some(until) = self.deleted_until {
+            match deleted_at {
+                Some(deleted_at) if deleted_at < until => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Parses the start of a deletion date window: a date (`2024-01-02`, from midnight on) or a
+/// local date and time in any form a `DeletionDate` may take (`2024-01-02T15:00:00`).
+pub fn parse_since(input: &str) -> Result<NaiveDateTime, AppError> {
+    parse_date_bound(input, false)
+}
+
+/// Parses the end of a deletion date window, which is exclusive. A plain date stands for the
+/// end of that day, so `--since 2024-01-02 --until 2024-01-02` covers the whole day.
+pub fn parse_until(input: &str) -> Result<NaiveDateTime, AppError> {
+    parse_date_bound(input, true)
+}
+
+fn parse_date_bound(input: &str, end_of_day: bool) -> Result<NaiveDateTime, AppError> {
+    let trimmed = input.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(trimmed, "%Y-%m-%d") {
+        let date = if end_of_day { date.succ_opt() } else { Some(date) };
+        return date
+            .map(|date| date.and_time(NaiveTime::MIN))
+            .ok_or_else(|| AppError::Message(format!("Date '{}' is out of range", input)));
+    }
+    // Also accept a space between the date and the time, as people tend to type it.
+    parse_deletion_date(&trimmed.replacen(' ', "T", 1))
+        .ok_or_else(|| AppError::Message(format!("Invalid date '{}' (expected YYYY-MM-DD[Thh:mm:ss])", input)))
+}
+
+/// Parses an age such as `30d`, `12h`, `2w`, `90m` or `45s`. A bare number is a number of days.
+pub fn parse_age(input: &str) -> Result<TimeDelta, AppError> {
+    let trimmed = input.trim();
+    let split_at = trimmed.find(|c: char| !c.is_ascii_digit()).unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split_at);
+
+    let value: i64 = number
+        .parse()
+        .map_err(|_| AppError::Message(format!("Invalid age '{}'", input)))?;
+    let age = match unit {
+        "s" => TimeDelta::try_seconds(value),
+        "m" => TimeDelta::try_minutes(value),
+        "h" => TimeDelta::try_hours(value),
+        "" | "d" => TimeDelta::try_days(value),
+        "w" => TimeDelta::try_weeks(value),
+        _ => return Err(AppError::Message(format!("Invalid age unit in '{}'", input))),
+    };
+    age.ok_or_else(|| AppError::Message(format!("Age '{}' is out of range", input)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("30d").unwrap(), TimeDelta::days(30));
+        assert_eq!(parse_age("7").unwrap(), TimeDelta::days(7));
+        assert_eq!(parse_age("12h").unwrap(), TimeDelta::hours(12));
+        assert_eq!(parse_age("2w").unwrap(), TimeDelta::weeks(2));
+        assert_eq!(parse_age("90m").unwrap(), TimeDelta::minutes(90));
+        assert_eq!(parse_age("45s").unwrap(), TimeDelta::seconds(45));
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3y").is_err());
+    }
+
+    #[test]
+    fn test_entry_filter_requires_all_conditions() {
+        let filter = EntryFilter {
+            larger_than: Some(100),
+            older_than: Some(TimeDelta::days(7)),
+            ..Default::default()
+        };
+        let now = at(31);
+
+        assert!(filter.matches(101, Some(at(1)), now), "Large and old should match");
+        assert!(!filter.matches(100, Some(at(1)), now), "Size must exceed the threshold");
+        assert!(!filter.matches(101, Some(at(30)), now), "Recent items should not match");
+        assert!(
+            !filter.matches(101, None, now),
+            "Unknown dates should not match an age filter"
+        );
+
+        let size_only = EntryFilter {
+            larger_than: Some(100),
+            ..Default::default()
+        };
+        assert!(size_only.is_active());
+        assert!(size_only.matches(101, None, now));
+        assert!(!EntryFilter::default().is_active());
+    }
+
+    #[test]
+    fn test_parse_date_bounds() {
+        let midnight = |day| at(day) - TimeDelta::hours(12);
+        assert_eq!(parse_since("2024-01-02").unwrap(), midnight(2));
+        assert_eq!(parse_until("2024-01-02").unwrap(), midnight(3));
+        assert_eq!(parse_since("2024-01-02T12:00:00").unwrap(), at(2));
+        assert_eq!(parse_until("2024-01-02 12:00:00").unwrap(), at(2));
+        assert!(parse_since("last tuesday").is_err());
+        assert!(parse_until("2024-13-01").is_err());
+    }
+
+    #[test]
+    fn test_entry_filter_date_window() {
+        struct TestCase {
+            since: Option<NaiveDateTime>,
+            until: Option<NaiveDateTime>,
+            deleted_at: Option<NaiveDateTime>,
+            expected: bool,
+            description: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                since: Some(at(2)),
+                until: Some(at(4)),
+                deleted_at: Some(at(2)),
+                expected: true,
+                description: "the start is inclusive",
+            },
+            TestCase {
+                since: Some(at(2)),
+                until: Some(at(4)),
+                deleted_at: Some(at(4)),
+                expected: false,
+                description: "the end is exclusive",
+            },
+            TestCase {
+                since: Some(at(2)),
+                until: Some(at(4)),
+                deleted_at: Some(at(4) - TimeDelta::seconds(1)),
+                expected: true,
+                description: "just before the end",
+            },
+            TestCase {
+                since: Some(at(2)),
+                until: Some(at(4)),
+                deleted_at: Some(at(2) - TimeDelta::seconds(1)),
+                expected: false,
+                description: "just before the start",
+            },
+            TestCase {
+                since: Some(at(2)),
+                until: None,
+                deleted_at: Some(at(31)),
+                expected: true,
+                description: "only --since is open towards the future",
+            },
+            TestCase {
+                since: None,
+                until: Some(at(4)),
+                deleted_at: Some(at(1)),
+                expected: true,
+                description: "only --until is open towards the past",
+            },
+            TestCase {
+                since: None,
+                until: Some(at(4)),
+                deleted_at: None,
+                expected: false,
+                description: "an unknown date is outside every window",
+            },
+        ];
+
+        for case in test_cases {
+            let filter = EntryFilter {
+                deleted_since: case.since,
+                deleted_until: case.until,
+                ..Default::default()
+            };
+            assert!(filter.is_active());
+            assert_eq!(
+                filter.matches(0, case.deleted_at, at(31)),
+                case.expected,
+                "{}",
+                case.description
+            );
+        }
+    }
+}
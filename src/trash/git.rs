@@ -0,0 +1,236 @@
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path, PathBuf};
+
+/// The signature at the start of a git index file ("dircache").
+const INDEX_SIGNATURE: &[u8] = b"DIRC";
+
+/// The size of the fixed part of an index entry, up to and including its flags.
+const INDEX_ENTRY_FIXED_SIZE: usize = 62;
+
+/// Set in an entry's flags when two more bytes of extended flags follow (index version 3 and up).
+const INDEX_EXTENDED_FLAG: u16 = 0x4000;
+
+/// Returns the work tree of the git repository that tracks `source` (an absolute, symlink-free path),
+/// or `None` when it is not inside a repository or the repository's index does not list it.
+/// A directory counts as tracked when any file below it is.
+///
+/// The index is read directly rather than running `git`, so this is only a heuristic: a file that
+/// was just `git rm --cached` is already untracked, and an unreadable index means untracked.
+pub(crate) fn tracking_repository(source: &Path) -> Option<PathBuf> {
+    let work_tree = source.ancestors().skip(1).find(|dir| dir.join(".git").exists())?;
+    let relative = relative_index_path(source.strip_prefix(work_tree).ok()?)?;
+    let index = fs::read(git_dir(work_tree)?.join("index")).ok()?;
+    let names = index_entry_names(&index)?;
+    let prefix = [relative.as_slice(), b"/"].concat();
+    names
+        .iter()
+        .any(|name| *name == relative || name.starts_with(&prefix))
+        .then(|| work_tree.to_path_buf())
+}
+
+/// The git directory of `work_tree`: `.git` itself, or where a `.git` file points to
+/// (`gitdir: <path>`), as in linked worktrees and submodules.
+fn git_dir(work_tree: &Path) -> Option<PathBuf> {
+    let dot_git = work_tree.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let content = fs::read_to_string(&dot_git).ok()?;
+    let target = content.lines().next()?.strip_prefix("gitdir:")?.trim();
+    Some(work_tree.join(target))
+}
+
+/// The path as the index spells it: relative to the work tree, with `/` separators.
+fn relative_index_path(relative: &Path) -> Option<Vec<u8>> {
+    let mut parts = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.as_bytes()),
+            _ => return None,
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join(&b'/'))
+}
+
+/// Reads the path names of all entries of a git index (versions 2 to 4).
+/// Returns `None` for anything that does not look like a valid index.
+fn index_entry_names(index: &[u8]) -> Option<Vec<Vec<u8>>> {
+    if index.get(..4)? != INDEX_SIGNATURE {
+        return None;
+    }
+    let version = read_u32(index, 4)?;
+    if !(2..=4).contains(&version) {
+        return None;
+    }
+    let count = read_u32(index, 8)? as usize;
+    let mut names: Vec<Vec<u8>> = Vec::with_capacity(count.min(index.len() / INDEX_ENTRY_FIXED_SIZE));
+    let mut offset = 12;
+    for _ in 0..count {
+        let entry_start = offset;
+        let flags = u16::from_be_bytes(index.get(offset + 60..offset + 62)?.try_into().ok()?);
+        offset += INDEX_ENTRY_FIXED_SIZE;
+        if version >= 3 && flags & INDEX_EXTENDED_FLAG != 0 {
+            offset += 2;
+        }
+        let name = if version == 4 {
+            // The name drops the given number of bytes from the end of the previous one and appends the rest.
+            let (strip, varint_len) = read_offset_varint(index.get(offset..)?)?;
+            offset += varint_len;
+            let suffix_len = index.get(offset..)?.iter().position(|&b| b == 0)?;
+            let previous = names.last().map(Vec::as_slice).unwrap_or_default();
+            let kept = previous.len().checked_sub(strip)?;
+            let name = [&previous[..kept], &index[offset..offset + suffix_len]].concat();
+            offset += suffix_len + 1;
+            name
+        } else {
+            // The name is NUL-terminated and padded with more NULs to a multiple of 8 bytes per entry.
+            let name_len = index.get(offset..)?.iter().position(|&b| b == 0)?;
+            let name = index[offset..offset + name_len].to_vec();
+            let entry_len = offset + name_len - entry_start;
+            offset = entry_start + (entry_len + 8) / 8 * 8;
+            name
+        };
+        names.push(name);
+    }
+    Some(names)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Reads git's "offset" varint, in which each continuation also adds one, and returns it with its length.
+fn read_offset_varint(bytes: &[u8]) -> Option<(usize, usize)> {
+    let mut value = (*bytes.first()? & 0x7f) as usize;
+    let mut len = 1;
+    while bytes[len - 1] & 0x80 != 0 {
+        let byte = *bytes.get(len)?;
+        value = ((value + 1) << 7) | (byte & 0x7f) as usize;
+        len += 1;
+    }
+    Some((value, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Builds a version 2 index listing `names`, with zeroed stat data and object ids.
+    fn index_v2(names: &[&str]) -> Vec<u8> {
+        let mut index = Vec::new();
+        index.extend_from_slice(INDEX_SIGNATURE);
+        index.extend_from_slice(&2u32.to_be_bytes());
+        index.extend_from_slice(&(names.len() as u32).to_be_bytes());
+        for name in names {
+            let start = index.len();
+            index.extend_from_slice(&[0u8; 60]);
+            index.extend_from_slice(&(name.len() as u16).to_be_bytes());
+            index.extend_from_slice(name.as_bytes());
+            let entry_len = index.len() - start;
+            index.resize(start + (entry_len + 8) / 8 * 8, 0);
+        }
+        index.extend_from_slice(&[0u8; 20]);
+        index
+    }
+
+    #[test]
+    fn test_index_entry_names() {
+        let names = index_entry_names(&index_v2(&["Cargo.toml", "src/main.rs", "src/trash/git.rs"])).unwrap();
+        assert_eq!(names, [&b"Cargo.toml"[..], b"src/main.rs", b"src/trash/git.rs"]);
+
+        // Version 4: "src/main.rs", then "src/trash/git.rs" as "drop 7 bytes, append `trash/git.rs`".
+        let mut index = Vec::new();
+        index.extend_from_slice(INDEX_SIGNATURE);
+        index.extend_from_slice(&4u32.to_be_bytes());
+        index.extend_from_slice(&2u32.to_be_bytes());
+        for (strip, suffix) in [(0u8, "src/main.rs"), (7, "trash/git.rs")] {
+            index.extend_from_slice(&[0u8; 62]);
+            index.push(strip);
+            index.extend_from_slice(suffix.as_bytes());
+            index.push(0);
+        }
+        let names = index_entry_names(&index).unwrap();
+        assert_eq!(names, [&b"src/main.rs"[..], b"src/trash/git.rs"]);
+
+        assert!(index_entry_names(b"not an index").is_none());
+        let mut truncated = index_v2(&["a.txt", "b.txt"]);
+        truncated.truncate(80);
+        assert!(index_entry_names(&truncated).is_none());
+    }
+
+    #[test]
+    fn test_read_offset_varint() {
+        assert_eq!(read_offset_varint(&[0x05]), Some((5, 1)));
+        // 0x80 0x00 is (0 + 1) << 7 = 128, the first value that needs two bytes.
+        assert_eq!(read_offset_varint(&[0x80, 0x00]), Some((128, 2)));
+        assert_eq!(read_offset_varint(&[0x80]), None);
+    }
+
+    #[test]
+    fn test_tracking_repository() -> std::io::Result<()> {
+        let root = tempdir()?;
+        let repo = root.path().canonicalize()?.join("project");
+        fs::create_dir_all(repo.join(".git"))?;
+        fs::create_dir_all(repo.join("src"))?;
+        fs::create_dir_all(repo.join("target"))?;
+        fs::write(repo.join(".git/index"), index_v2(&["README.md", "src/main.rs"]))?;
+        // A linked worktree, whose `.git` file points to its git directory.
+        let worktree = root.path().canonicalize()?.join("worktree");
+        fs::create_dir_all(&worktree)?;
+        fs::create_dir_all(repo.join(".git/worktrees/wt"))?;
+        fs::write(repo.join(".git/worktrees/wt/index"), index_v2(&["notes.txt"]))?;
+        fs::write(
+            worktree.join(".git"),
+            format!("gitdir: {}\n", repo.join(".git/worktrees/wt").display()),
+        )?;
+
+        struct TestCase {
+            source: PathBuf,
+            expected: Option<PathBuf>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                source: repo.join("README.md"),
+                expected: Some(repo.clone()),
+            },
+            TestCase {
+                source: repo.join("src"),
+                expected: Some(repo.clone()),
+            },
+            TestCase {
+                source: repo.join("src/main.rs"),
+                expected: Some(repo.clone()),
+            },
+            TestCase {
+                source: repo.join("target"),
+                expected: None,
+            },
+            TestCase {
+                // Not a prefix match on the name: `src/main.rs` does not make `src/main` tracked.
+                source: repo.join("src/main"),
+                expected: None,
+            },
+            TestCase {
+                source: worktree.join("notes.txt"),
+                expected: Some(worktree.clone()),
+            },
+            TestCase {
+                source: root.path().canonicalize()?.join("elsewhere.txt"),
+                expected: None,
+            },
+        ];
+
+        for case in test_cases {
+            assert_eq!(
+                tracking_repository(&case.source),
+                case.expected,
+                "{}",
+                case.source.display()
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,390 @@
+use std::fs::{self, DirBuilder, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, TimeDelta};
+
+use crate::trash::error::AppError;
+use crate::trash::spec::{TRASH_FILES_DIR_NAME, TRASH_INFO_DIR_NAME};
+
+/// The directory inside a trash root that holds the `files` and `info` directories of an
+/// emptied trash until the grace period ends.
+const PENDING_EMPTY_DIR_NAME: &str = ".pending-empty";
+
+/// The manifest of pending empties, stored under `$XDG_STATE_HOME/trash-tool`.
+/// Each line is `<deadline in unix seconds>\t<trash root>`.
+const PENDING_EMPTY_MANIFEST_NAME: &str = "pending-empty";
+
+/// A trash directory whose contents were emptied with a grace period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingEmpty {
+    pub trash_root: PathBuf,
+    pub deadline: i64,
+}
+
+impl PendingEmpty {
+    fn holding_path(&self) -> PathBuf {
+        self.trash_root.join(PENDING_EMPTY_DIR_NAME)
+    }
+
+    pub fn is_expired(&self, now: DateTime<Local>) -> bool {
+        now.timestamp() >= self.deadline
+    }
+}
+
+/// Permanently deletes every pending empty right away.
+pub fn handle_commit_empty() -> Result<(), AppError> {
+    let committed = commit_pending(&default_manifest_path()?, false, Local::now())?;
+    if committed.is_empty() {
+        println!("No pending empty to commit.");
+    }
+    for trash_root in committed {
+        println!("Emptied trash at: {}", trash_root.display());
+    }
+    Ok(())
+}
+
+/// Puts back the contents of every pending empty that is still within its grace period.
+pub fn handle_cancel_empty() -> Result<(), AppError> {
+    let cancelled = cancel_pending(&default_manifest_path()?, Local::now())?;
+    if cancelled.is_empty() {
+        println!("No pending empty to cancel.");
+    }
+    for trash_root in cancelled {
+        println!("Cancelled empty of: {}", trash_root.display());
+    }
+    Ok(())
+}
+
+/// Returns the manifest path, e.g. `~/.local/state/trash-tool/pending-empty`.
+pub fn default_manifest_path() -> Result<PathBuf, AppError> {
+    dirs::state_dir()
+        .map(|dir| dir.join("trash-tool").join(PENDING_EMPTY_MANIFEST_NAME))
+        .ok_or_else(|| AppError::Message("Could not determine the state directory".into()))
+}
+
+fn read_manifest(manifest_path: &Path) -> Result<Vec<PendingEmpty>, AppError> {
+    let content = match fs::read_to_string(manifest_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => {
+            return Err(AppError::Io {
+                path: manifest_path.to_path_buf(),
+                source,
+            })
+        }
+    };
+    Ok(content
+        .lines()
+        .filter_map(|line| {
+            let (deadline, trash_root) = line.split_once('\t')?;
+            Some(PendingEmpty {
+                trash_root: PathBuf::from(trash_root),
+                deadline: deadline.parse().ok()?,
+            })
+        })
+        .collect())
+}
+
+/// Replaces the manifest atomically and durably: the new content is written to a temporary
+/// file, flushed to disk and renamed over the old one, so a crash leaves either version intact.
+fn write_manifest(manifest_path: &Path, pending: &[PendingEmpty]) -> Result<(), AppError> {
+    let parent = manifest_path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(parent).map_err(|source| AppError::Io {
+        path: parent.to_path_buf(),
+        source,
+    })?;
+    let content: String = pending
+        .iter()
+        .map(|p| format!("{}\t{}\n", p.deadline, p.trash_root.display()))
+        .collect();
+    let mut temp_name = manifest_path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = manifest_path.with_file_name(temp_name);
+    let written = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    });
+    if let Err(source) = written.and_then(|()| fs::rename(&temp_path, manifest_path)) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(AppError::Io {
+            path: manifest_path.to_path_buf(),
+            source,
+        });
+    }
+    // Make the rename itself durable; not every filesystem supports syncing a directory.
+    if let Ok(dir) = File::open(parent) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Empties `trash_root` recoverably: its `files` and `info` directories are moved into a holding
+/// directory and fresh empty ones are created. Nothing is deleted until the pending empty is
+/// committed or its grace period has expired.
+pub fn stage_empty(
+    manifest_path: &Path,
+    trash_root: &Path,
+    grace: TimeDelta,
+    now: DateTime<Local>,
+) -> Result<PendingEmpty, AppError> {
+    let mut pending = read_manifest(manifest_path)?;
+    if pending.iter().any(|p| p.trash_root == trash_root) {
+        return Err(AppError::Message(format!(
+            "An empty of '{}' is already pending. Run --commit-empty or --cancel-empty first.",
+            trash_root.display()
+        )));
+    }
+
+    let entry = PendingEmpty {
+        trash_root: trash_root.to_path_buf(),
+        deadline: (now + grace).timestamp(),
+    };
+    pending.push(entry.clone());
+    // Recorded before anything is moved, so an empty interrupted midway can still be cancelled.
+    write_manifest(manifest_path, &pending)?;
+
+    let holding_path = trash_root.join(PENDING_EMPTY_DIR_NAME);
+    let mut builder = DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    if let Err(source) = builder.create(&holding_path) {
+        pending.pop();
+        write_manifest(manifest_path, &pending)?;
+        return Err(AppError::Io {
+            path: holding_path,
+            source,
+        });
+    }
+    for target in [TRASH_FILES_DIR_NAME, TRASH_INFO_DIR_NAME] {
+        let dir = trash_root.join(target);
+        if dir.is_dir() {
+            fs::rename(&dir, holding_path.join(target)).map_err(|source| AppError::Io {
+                path: dir.clone(),
+                source,
+            })?;
+        }
+        fs::create_dir_all(&dir).map_err(|source| AppError::Io { path: dir, source })?;
+    }
+    Ok(entry)
+}
+
+/// Permanently deletes pending empties. With `only_expired`, those still within their grace period are kept.
+/// Returns the trash roots whose pending contents were deleted.
+pub fn commit_pending(
+    manifest_path: &Path,
+    only_expired: bool,
+    now: DateTime<Local>,
+) -> Result<Vec<PathBuf>, AppError> {
+    let (to_commit, to_keep): (Vec<_>, Vec<_>) = read_manifest(manifest_path)?
+        .into_iter()
+        .partition(|p| !only_expired || p.is_expired(now));
+    if to_commit.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut committed = Vec::new();
+    for pending in to_commit {
+        let holding_path = pending.holding_path();
+        match fs::remove_dir_all(&holding_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(source) => {
+                return Err(AppError::Io {
+                    path: holding_path,
+                    source,
+                })
+            }
+        }
+        committed.push(pending.trash_root);
+    }
+    write_manifest(manifest_path, &to_keep)?;
+    Ok(committed)
+}
+
+/// Puts the contents of every pending empty that is still within its grace period back into its trash.
+/// Items trashed in the meantime are kept; if one has the same name as a held item, the held item
+/// stays in the holding directory and a warning is printed.
+/// Returns the trash roots that were restored.
+pub fn cancel_pending(manifest_path: &Path, now: DateTime<Local>) -> Result<Vec<PathBuf>, AppError> {
+    let (expired, active): (Vec<_>, Vec<_>) = read_manifest(manifest_path)?
+        .into_iter()
+        .partition(|p| p.is_expired(now));
+    for pending in &expired {
+        eprintln!(
+            "warning: The grace period for emptying '{}' has expired. It can no longer be cancelled.",
+            pending.trash_root.display()
+        );
+    }
+
+    let mut cancelled = Vec::new();
+    let mut incomplete = Vec::new();
+    for pending in active {
+        let holding_path = pending.holding_path();
+        let mut is_complete = true;
+        for target in [TRASH_FILES_DIR_NAME, TRASH_INFO_DIR_NAME] {
+            let held_dir = holding_path.join(target);
+            let Ok(held_entries) = fs::read_dir(&held_dir) else {
+                continue;
+            };
+            let dest_dir = pending.trash_root.join(target);
+            for held in held_entries.filter_map(Result::ok) {
+                let dest = dest_dir.join(held.file_name());
+                if dest.symlink_metadata().is_ok() {
+                    eprintln!(
+                        "warning: '{}' already exists. Leaving '{}' pending.",
+                        dest.display(),
+                        held.path().display()
+                    );
+                    is_complete = false;
+                    continue;
+                }
+                fs::rename(held.path(), &dest).map_err(|source| AppError::Io {
+                    path: held.path(),
+                    source,
+                })?;
+            }
+        }
+        if is_complete {
+            fs::remove_dir_all(&holding_path).map_err(|source| AppError::Io {
+                path: holding_path,
+                source,
+            })?;
+            cancelled.push(pending.trash_root);
+        } else {
+            incomplete.push(pending);
+        }
+    }
+
+    // Expired empties stay in the manifest so that the next commit deletes them.
+    write_manifest(manifest_path, &[expired, incomplete].concat())?;
+    Ok(cancelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tempfile::tempdir;
+
+    fn make_trash(root: &Path) -> Result<(), AppError> {
+        fs::create_dir_all(root.join(TRASH_FILES_DIR_NAME))?;
+        fs::create_dir_all(root.join(TRASH_INFO_DIR_NAME))?;
+        File::create(root.join(TRASH_FILES_DIR_NAME).join("a.txt"))?;
+        File::create(root.join(TRASH_INFO_DIR_NAME).join("a.txt.trashinfo"))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_stage_then_cancel_restores_contents() -> Result<(), AppError> {
+        let state = tempdir()?;
+        let manifest = state.path().join("pending-empty");
+        let trash = tempdir()?;
+        make_trash(trash.path())?;
+        let now = Local::now();
+
+        stage_empty(&manifest, trash.path(), TimeDelta::minutes(10), now)?;
+        assert!(
+            !trash.path().join("files/a.txt").exists(),
+            "The trash should look empty"
+        );
+        assert!(trash.path().join(TRASH_FILES_DIR_NAME).is_dir());
+
+        let cancelled = cancel_pending(&manifest, now)?;
+        assert_eq!(cancelled, vec![trash.path().to_path_buf()]);
+        assert!(trash.path().join("files/a.txt").exists());
+        assert!(trash.path().join("info/a.txt.trashinfo").exists());
+        assert!(!trash.path().join(PENDING_EMPTY_DIR_NAME).exists());
+        assert!(read_manifest(&manifest)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stage_empty_records_manifest_before_moving() -> Result<(), AppError> {
+        let state = tempdir()?;
+        let trash = tempdir()?;
+        make_trash(trash.path())?;
+        let now = Local::now();
+
+        // The manifest cannot be written, so nothing may be moved.
+        let blocker = state.path().join("not-a-dir");
+        File::create(&blocker)?;
+        let result = stage_empty(
+            &blocker.join("pending-empty"),
+            trash.path(),
+            TimeDelta::minutes(10),
+            now,
+        );
+        assert!(result.is_err());
+        assert!(trash.path().join("files/a.txt").exists());
+        assert!(!trash.path().join(PENDING_EMPTY_DIR_NAME).exists());
+
+        // A pending empty that is already held leaves the manifest as it was.
+        let manifest = state.path().join("pending-empty");
+        fs::create_dir(trash.path().join(PENDING_EMPTY_DIR_NAME))?;
+        assert!(stage_empty(&manifest, trash.path(), TimeDelta::minutes(10), now).is_err());
+        assert!(read_manifest(&manifest)?.is_empty());
+        fs::remove_dir(trash.path().join(PENDING_EMPTY_DIR_NAME))?;
+
+        stage_empty(&manifest, trash.path(), TimeDelta::minutes(10), now)?;
+        assert_eq!(read_manifest(&manifest)?.len(), 1);
+        assert_eq!(
+            fs::read_dir(state.path())?.count(),
+            2,
+            "no temporary manifest is left behind"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_only_expired() -> Result<(), AppError> {
+        let state = tempdir()?;
+        let manifest = state.path().join("pending-empty");
+        let trash = tempdir()?;
+        make_trash(trash.path())?;
+        let now = Local::now();
+
+        stage_empty(&manifest, trash.path(), TimeDelta::minutes(10), now)?;
+
+        let committed = commit_pending(&manifest, true, now)?;
+        assert!(
+            committed.is_empty(),
+            "Nothing should be deleted within the grace period"
+        );
+        assert!(trash.path().join(PENDING_EMPTY_DIR_NAME).exists());
+
+        let later = now + TimeDelta::minutes(11);
+        let committed = commit_pending(&manifest, true, later)?;
+        assert_eq!(committed, vec![trash.path().to_path_buf()]);
+        assert!(!trash.path().join(PENDING_EMPTY_DIR_NAME).exists());
+        assert!(read_manifest(&manifest)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cancel_after_expiry_is_refused() -> Result<(), AppError> {
+        let state = tempdir()?;
+        let manifest = state.path().join("pending-empty");
+        let trash = tempdir()?;
+        make_trash(trash.path())?;
+        let now = Local::now();
+
+        stage_empty(&manifest, trash.path(), TimeDelta::minutes(1), now)?;
+        let cancelled = cancel_pending(&manifest, now + TimeDelta::minutes(2))?;
+
+        assert!(cancelled.is_empty());
+        assert!(!trash.path().join("files/a.txt").exists());
+        assert_eq!(
+            read_manifest(&manifest)?.len(),
+            1,
+            "The expired empty should still be committable"
+        );
+
+        Ok(())
+    }
+}
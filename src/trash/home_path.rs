@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::OnceCell;
+
+/// `$HOME`, set only when `--relative` asks for `~/...` paths.
+static TILDE_HOME: OnceCell<PathBuf> = OnceCell::new();
+
+/// Enables shortening paths under `$HOME` to `~/...` in displays for the rest of the run, based on `--relative`.
+pub fn set_relative_paths(enabled: bool) {
+    if !enabled {
+        return;
+    }
+    // With `/` as home every path would be "relative"; leave them alone instead.
+    if let Some(home) = dirs::home_dir().filter(|home| home.parent().is_some()) {
+        let _ = TILDE_HOME.set(home);
+    }
+}
+
+/// Formats `path` for humans, as `~/...` when it is under `$HOME` and `--relative` is set.
+/// Machine-readable output (porcelain, `--print0`) keeps using the full path.
+pub(crate) fn display_path(path: &Path) -> String {
+    match TILDE_HOME.get() {
+        Some(home) => tilde_path(path, home),
+        None => path.display().to_string(),
+    }
+}
+
+/// Replaces a leading `home` in `path` with `~`. Only whole components match, so
+/// `/home/alice2` is not shortened for the home `/home/alice`.
+fn tilde_path(path: &Path, home: &Path) -> String {
+    match path.strip_prefix(home) {
+        Ok(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Ok(rest) => format!("~/{}", rest.display()),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tilde_path() {
+        struct TestCase {
+            path: &'static str,
+            expected: &'static str,
+        }
+
+        let home = Path::new("/home/alice");
+        let test_cases = vec![
+            TestCase {
+                path: "/home/alice/.local/share/Trash/files",
+                expected: "~/.local/share/Trash/files",
+            },
+            TestCase {
+                path: "/home/alice",
+                expected: "~",
+            },
+            TestCase {
+                path: "/home/alice2/notes.txt",
+                expected: "/home/alice2/notes.txt",
+            },
+            TestCase {
+                path: "/mnt/usb/.Trash-1000/files",
+                expected: "/mnt/usb/.Trash-1000/files",
+            },
+        ];
+
+        for case in test_cases {
+            assert_eq!(
+                tilde_path(Path::new(case.path), home),
+                case.expected,
+                "path: {}",
+                case.path
+            );
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::trash::error::AppError;
+use crate::trash::spec::{
+    TRASH_INFO_DATE_KEY, TRASH_INFO_DIR_NAME, TRASH_INFO_HEADER, TRASH_INFO_MODE_KEY, TRASH_INFO_PARENT_MODE_KEY,
+    TRASH_INFO_PATH_KEY, TRASH_INFO_SUFFIX,
+};
+
+static PATH_RE: Lazy<Regex> = Lazy::new(|| Regex::new(&format!(r"^{}=(.*)$", TRASH_INFO_PATH_KEY)).unwrap());
+static DATE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(&format!(r"^{}=(.*)$", TRASH_INFO_DATE_KEY)).unwrap());
+static MODE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(&format!(r"^{}=([0-7]{{1,4}})$", TRASH_INFO_MODE_KEY)).unwrap());
+static PARENT_MODE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(&format!(r"^{}=([0-7]{{1,4}})$", TRASH_INFO_PARENT_MODE_KEY)).unwrap());
+
+/// The keys of a `.trashinfo` file this tool understands. Values are kept raw:
+/// `path` is still percent-encoded and `deletion_date` is unparsed.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct TrashInfo {
+    pub(crate) path: Option<String>,
+    pub(crate) deletion_date: Option<String>,
+    pub(crate) mode: Option<u32>,
+    pub(crate) parent_mode: Option<u32>,
+}
+
+fn get_capture(re: &Regex, line: &str) -> Option<String> {
+    re.captures(line)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().to_string())
+}
+
+/// Parses the contents of a `.trashinfo` file. The first occurrence of each key wins.
+pub(crate) fn parse_trash_info(content: &str) -> TrashInfo {
+    let mut info = TrashInfo::default();
+    for line in content.lines() {
+        if info.mode.is_none() {
+            info.mode = get_capture(&MODE_RE, line).and_then(|m| u32::from_str_radix(&m, 8).ok());
+        }
+        if info.parent_mode.is_none() {
+            info.parent_mode = get_capture(&PARENT_MODE_RE, line).and_then(|m| u32::from_str_radix(&m, 8).ok());
+        }
+        if info.path.is_none() {
+            info.path = get_capture(&PATH_RE, line);
+        }
+        if info.deletion_date.is_none() {
+            info.deletion_date = get_capture(&DATE_RE, line);
+        }
+    }
+    info
+}
+
+/// Checks that the first non-empty line of a `.trashinfo` file is the `[Trash Info]` group header,
+/// so that any text file with `Path=` and `DeletionDate=` lines is not taken for one.
+pub(crate) fn check_trash_info_header(content: &str) -> Result<(), String> {
+    match content.lines().find(|line| !line.trim().is_empty()) {
+        Some(TRASH_INFO_HEADER) => Ok(()),
+        Some(line) if line.starts_with('[') => {
+            Err(format!("expected the '{}' header, found '{}'", TRASH_INFO_HEADER, line))
+        }
+        _ => Err(format!("missing the '{}' header", TRASH_INFO_HEADER)),
+    }
+}
+
+/// Reads and parses the `.trashinfo` file at `info_path`.
+/// Fails with `AppError::TrashInfoParse` if it does not start with the `[Trash Info]` header.
+pub(crate) fn read_trash_info(info_path: &Path) -> Result<TrashInfo, AppError> {
+    let content = fs::read_to_string(info_path).map_err(|source| AppError::Io {
+        path: info_path.to_path_buf(),
+        source,
+    })?;
+    check_trash_info_header(&content).map_err(|reason| AppError::TrashInfoParse {
+        path: info_path.to_path_buf(),
+        reason,
+    })?;
+    Ok(parse_trash_info(&content))
+}
+
+/// Returns where the `.trashinfo` file for an item in a trash `files` directory lives.
+pub(crate) fn info_path_for(trashed_path: &Path) -> Option<PathBuf> {
+    let trash_dir = trashed_path.parent()?.parent()?;
+    let mut info_name = trashed_path.file_name()?.to_os_string();
+    info_name.push(TRASH_INFO_SUFFIX);
+    Some(trash_dir.join(TRASH_INFO_DIR_NAME).join(info_name))
+}
+
+/// Returns the name of the item in `files` that the info file named `info_file_name` describes.
+/// Exactly one suffix is removed, so `report.trashinfo.trashinfo` belongs to `report.trashinfo`.
+pub(crate) fn data_name_for(info_file_name: &OsStr) -> Option<&OsStr> {
+    info_file_name
+        .as_bytes()
+        .strip_suffix(TRASH_INFO_SUFFIX.as_bytes())
+        .map(OsStr::from_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trash_info() {
+        let info = parse_trash_info(
+            "[Trash Info]\nPath=/home/user/a%20b.txt\nDeletionDate=2024-01-01T12:00:00\nMode=0640\nParentMode=0700\nPath=/ignored\n",
+        );
+        assert_eq!(
+            info,
+            TrashInfo {
+                path: Some("/home/user/a%20b.txt".to_string()),
+                deletion_date: Some("2024-01-01T12:00:00".to_string()),
+                mode: Some(0o640),
+                parent_mode: Some(0o700),
+            }
+        );
+        assert_eq!(parse_trash_info("[Trash Info]\nMode=999\n"), TrashInfo::default());
+    }
+
+    #[test]
+    fn test_check_trash_info_header() {
+        struct TestCase {
+            content: &'static str,
+            expected: Result<(), &'static str>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                content: "[Trash Info]\nPath=/a\nDeletionDate=2024-01-01T12:00:00\n",
+                expected: Ok(()),
+            },
+            TestCase {
+                content: "\n\n[Trash Info]\nPath=/a\n",
+                expected: Ok(()),
+            },
+            TestCase {
+                content: "Path=/a\nDeletionDate=2024-01-01T12:00:00\n",
+                expected: Err("missing the '[Trash Info]' header"),
+            },
+            TestCase {
+                content: "",
+                expected: Err("missing the '[Trash Info]' header"),
+            },
+            TestCase {
+                content: "[Desktop Entry]\nPath=/a\nDeletionDate=2024-01-01T12:00:00\n",
+                expected: Err("expected the '[Trash Info]' header, found '[Desktop Entry]'"),
+            },
+        ];
+
+        for case in test_cases {
+            assert_eq!(
+                check_trash_info_header(case.content),
+                case.expected.map_err(str::to_string),
+                "{:?}",
+                case.content
+            );
+        }
+    }
+
+    #[test]
+    fn test_data_name_for() {
+        assert_eq!(data_name_for(OsStr::new("a.txt.trashinfo")), Some(OsStr::new("a.txt")));
+        assert_eq!(
+            data_name_for(OsStr::new("report.trashinfo.trashinfo")),
+            Some(OsStr::new("report.trashinfo"))
+        );
+        assert_eq!(data_name_for(OsStr::new("notes.txt")), None);
+    }
+
+    #[test]
+    fn test_info_path_for() {
+        assert_eq!(
+            info_path_for(Path::new("/home/user/.local/share/Trash/files/a.txt")),
+            Some(PathBuf::from("/home/user/.local/share/Trash/info/a.txt.trashinfo"))
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+
+use crate::trash::error::AppError;
+use crate::trash::info_file::{data_name_for, parse_trash_info};
+use crate::trash::locations::get_target_trash_dirs;
+use crate::trash::restoring::parse_deletion_date;
+use crate::trash::spec::{
+    TRASH_FILES_DIR_NAME, TRASH_INFO_DATE_FORMAT, TRASH_INFO_DATE_KEY, TRASH_INFO_DIR_NAME, TRASH_INFO_HEADER,
+    TRASH_INFO_MODE_KEY, TRASH_INFO_PARENT_MODE_KEY, TRASH_INFO_PATH_KEY, TRASH_INFO_SUFFIX,
+};
+use crate::trash::url_escape::trash_spec_url_decode;
+
+/// Keys in the order this tool writes them; `Mode` and `ParentMode` are its own extensions.
+const KNOWN_KEYS: &[&str] = &[
+    TRASH_INFO_PATH_KEY,
+    TRASH_INFO_DATE_KEY,
+    TRASH_INFO_MODE_KEY,
+    TRASH_INFO_PARENT_MODE_KEY,
+];
+
+/// How far a finding strays from the spec.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Severity {
+    /// Other tools may fail to restore the item.
+    Error,
+    /// Readable, but not what the spec asks for.
+    Warning,
+    /// Harmless, but differs from how the spec's reference implementations write it.
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Finding {
+    pub(crate) severity: Severity,
+    pub(crate) message: String,
+}
+
+impl Finding {
+    fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Finding {
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+/// Reports the spec deviations of every info file in the target trash directories, without changing anything.
+/// Fails with `AppError::Ignorable` when any error-level finding was reported.
+pub fn handle_trashinfo_lint(all_trash: bool) -> Result<(), AppError> {
+    let trash_dirs = get_target_trash_dirs(all_trash)?;
+    let mut writer = io::stdout();
+    let mut has_errors = false;
+    for trash_dir in trash_dirs {
+        has_errors |= write_lint_report(&mut writer, &trash_dir)?;
+    }
+    if has_errors {
+        return Err(AppError::Ignorable);
+    }
+    Ok(())
+}
+
+/// Writes the findings for each info file of `trash_dir` that has any, and returns whether one was an error.
+fn write_lint_report<W: Write>(writer: &mut W, trash_dir: &Path) -> Result<bool, AppError> {
+    let info_dir = trash_dir.join(TRASH_INFO_DIR_NAME);
+    let mut info_paths: Vec<_> = match fs::read_dir(&info_dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.to_string_lossy().ends_with(TRASH_INFO_SUFFIX))
+            .collect(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(source) => return Err(AppError::Io { path: info_dir, source }),
+    };
+    info_paths.sort();
+
+    let mut flagged_files = 0;
+    let mut finding_count = 0;
+    let mut has_errors = false;
+    for info_path in &info_paths {
+        let findings = match fs::read(info_path) {
+            Ok(bytes) => match String::from_utf8(bytes) {
+                Ok(content) => {
+                    let data_name = info_path.file_name().and_then(data_name_for).unwrap_or_default();
+                    let data_path = trash_dir.join(TRASH_FILES_DIR_NAME).join(data_name);
+                    lint_trash_info(&content, data_path.symlink_metadata().is_ok())
+                }
+                Err(_) => vec![Finding::new(Severity::Error, "the file is not valid UTF-8")],
+            },
+            Err(e) => vec![Finding::new(Severity::Error, format!("the file cannot be read: {}", e))],
+        };
+        if findings.is_empty() {
+            continue;
+        }
+        flagged_files += 1;
+        finding_count += findings.len();
+        writeln!(writer, "{}", info_path.display())?;
+        for finding in findings {
+            has_errors |= finding.severity == Severity::Error;
+            writeln!(writer, "  {}: {}", finding.severity, finding.message)?;
+        }
+    }
+    writeln!(
+        writer,
+        "{}: {} findings in {} of {} info files",
+        trash_dir.display(),
+        finding_count,
+        flagged_files,
+        info_paths.len()
+    )?;
+    Ok(has_errors)
+}
+
+/// Lints the contents of one `.trashinfo` file. `data_exists` tells whether its item is still in `files/`.
+/// Findings are ordered by severity, most serious first.
+pub(crate) fn lint_trash_info(content: &str, data_exists: bool) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    let has_header = content.lines().next() == Some(TRASH_INFO_HEADER);
+    if !has_header {
+        findings.push(Finding::new(
+            Severity::Error,
+            format!("the first line is not '{}'", TRASH_INFO_HEADER),
+        ));
+    }
+    if !content.ends_with('\n') {
+        findings.push(Finding::new(Severity::Warning, "missing trailing newline"));
+    }
+
+    // Only the shape of the keys is checked here; their values come from the regular parser below.
+    let mut seen_keys: Vec<&str> = Vec::new();
+    for (index, line) in content.lines().enumerate().skip(usize::from(has_header)) {
+        if line.is_empty() {
+            continue;
+        }
+        let Some((key, _)) = line.split_once('=') else {
+            findings.push(Finding::new(
+                Severity::Warning,
+                format!("line {} is not a key=value pair", index + 1),
+            ));
+            continue;
+        };
+        if seen_keys.contains(&key) {
+            findings.push(Finding::new(
+                Severity::Warning,
+                format!("duplicate key '{}' (the first one is used)", key),
+            ));
+            continue;
+        }
+        seen_keys.push(key);
+        if !KNOWN_KEYS.contains(&key) {
+            findings.push(Finding::new(Severity::Warning, format!("unknown key '{}'", key)));
+        }
+    }
+
+    let known_order: Vec<usize> = seen_keys
+        .iter()
+        .filter_map(|key| KNOWN_KEYS.iter().position(|known| known == key))
+        .collect();
+    if known_order.windows(2).any(|pair| pair[0] > pair[1]) {
+        findings.push(Finding::new(
+            Severity::Info,
+            format!("keys are not in canonical order ({})", KNOWN_KEYS.join(", ")),
+        ));
+    }
+
+    let info = parse_trash_info(content);
+    match info.path.as_deref() {
+        None => findings.push(Finding::new(Severity::Error, "missing Path key")),
+        Some(path) => {
+            if trash_spec_url_decode(path).to_str().is_none() {
+                findings.push(Finding::new(
+                    Severity::Warning,
+                    "Path does not decode to valid UTF-8 (some tools cannot restore it)",
+                ));
+            }
+            if let Some(escape) = unnecessary_escape(path) {
+                findings.push(Finding::new(
+                    Severity::Info,
+                    format!("Path percent-encodes a character that needs no encoding ('{}')", escape),
+                ));
+            }
+        }
+    }
+
+    match info.deletion_date.as_deref() {
+        None => findings.push(Finding::new(Severity::Error, "missing DeletionDate key")),
+        Some(date) if NaiveDateTime::parse_from_str(date, TRASH_INFO_DATE_FORMAT).is_ok() => {}
+        Some(date) if parse_deletion_date(date).is_some() => findings.push(Finding::new(
+            Severity::Warning,
+            format!("DeletionDate '{}' is not in the YYYY-MM-DDThh:mm:ss format", date),
+        )),
+        Some(date) => findings.push(Finding::new(
+            Severity::Error,
+            format!("DeletionDate '{}' cannot be parsed", date),
+        )),
+    }
+
+    if !data_exists {
+        findings.push(Finding::new(Severity::Error, "no matching item in files/"));
+    }
+
+    findings.sort_by_key(|finding| finding.severity);
+    findings
+}
+
+/// Returns the first `%XX` escape in `path` that encodes an unreserved character or `/`.
+fn unnecessary_escape(path: &str) -> Option<&str> {
+    path.match_indices('%').find_map(|(start, _)| {
+        let escape = path.get(start..start + 3)?;
+        let byte = u8::from_str_radix(&escape[1..], 16).ok()?;
+        (byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte)).then_some(escape)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_trash_info() {
+        struct TestCase {
+            content: &'static str,
+            data_exists: bool,
+            expected: Vec<(Severity, &'static str)>,
+            description: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                content: "[Trash Info]\nPath=/home/user/a%20b.txt\nDeletionDate=2024-01-01T12:00:00\n",
+                data_exists: true,
+                expected: vec![],
+                description: "canonical file",
+            },
+            TestCase {
+                content: "[Trash Info]\nPath=/home/user/a.txt\nDeletionDate=2024-01-01T12:00:00\nMode=0644\nParentMode=0700\n",
+                data_exists: true,
+                expected: vec![],
+                description: "Mode and ParentMode extensions are known",
+            },
+            TestCase {
+                content: "[Trash Info]\nDeletionDate=2024-01-01T12:00:00\nPath=/home/user/a.txt",
+                data_exists: true,
+                expected: vec![
+                    (Severity::Warning, "missing trailing newline"),
+                    (
+                        Severity::Info,
+                        "keys are not in canonical order (Path, DeletionDate, Mode, ParentMode)",
+                    ),
+                ],
+                description: "no trailing newline and swapped keys",
+            },
+            TestCase {
+                content: "[Trash Info]\nPath=/home/user/%41.txt\nDeletionDate=2024-01-01T12:00:00+09:00\nX-Tool=kio\n",
+                data_exists: true,
+                expected: vec![
+                    (Severity::Warning, "unknown key 'X-Tool'"),
+                    (
+                        Severity::Warning,
+                        "DeletionDate '2024-01-01T12:00:00+09:00' is not in the YYYY-MM-DDThh:mm:ss format",
+                    ),
+                    (
+                        Severity::Info,
+                        "Path percent-encodes a character that needs no encoding ('%41')",
+                    ),
+                ],
+                description: "unknown key, offset date and needless escape",
+            },
+            TestCase {
+                content: "Path=/home/user/a.txt\nDeletionDate=yesterday\n",
+                data_exists: false,
+                expected: vec![
+                    (Severity::Error, "the first line is not '[Trash Info]'"),
+                    (Severity::Error, "DeletionDate 'yesterday' cannot be parsed"),
+                    (Severity::Error, "no matching item in files/"),
+                ],
+                description: "missing header, bad date and missing data",
+            },
+        ];
+
+        for case in test_cases {
+            let findings: Vec<(Severity, String)> = lint_trash_info(case.content, case.data_exists)
+                .into_iter()
+                .map(|finding| (finding.severity, finding.message))
+                .collect();
+            let expected: Vec<(Severity, String)> = case
+                .expected
+                .into_iter()
+                .map(|(severity, message)| (severity, message.to_string()))
+                .collect();
+            assert_eq!(findings, expected, "{}", case.description);
+        }
+    }
+}
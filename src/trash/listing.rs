@@ -1,16 +1,25 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::fs;
 use std::io::{self, Write};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
 
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDateTime};
 use humansize::{format_size, BINARY};
 use term_grid::{Cell, Direction, Filling, Grid, GridOptions};
 
 use super::color::{colorize_file_size, colorize_modified, colorize_path, colorize_user_group, format_mode};
 use crate::trash::color::colorize_trash_directory;
 use crate::trash::error::AppError;
-use crate::trash::locations::get_target_trash_dirs;
-use crate::trash::spec::TRASH_FILES_DIR_NAME;
+use crate::trash::home_path::display_path;
+use crate::trash::info_file::{info_path_for, read_trash_info};
+use crate::trash::locations::{select_source_trashes, TargetTrash, TrashType};
+use crate::trash::porcelain::write_record;
+use crate::trash::restoring::{find_trash_entries_in_dirs, parse_deletion_date};
+use crate::trash::size::dir_size;
+use crate::trash::terminal::terminal_width;
+use crate::trash::url_escape::{trash_spec_url_decode, trash_spec_url_decode_strict};
 
 #[cfg(unix)]
 use {
@@ -18,43 +27,136 @@ use {
     users::{get_group_by_gid, get_user_by_uid},
 };
 
-pub fn handle_display_trash(all_trash: bool, long_format: bool) -> Result<(), AppError> {
-    let trash_dirs = get_target_trash_dirs(all_trash)?;
-    if trash_dirs.is_empty() {
+#[derive(Default)]
+pub struct DisplayTrashOptions {
+    pub all_trash: bool,
+    /// List only this trash directory instead (`--trash-dir`).
+    pub trash_dir: Option<PathBuf>,
+    pub long_format: bool,
+    pub porcelain: bool,
+    /// Whether to use the terminal grid layout instead of one entry per line.
+    pub tty: bool,
+    pub order: ListOrder,
+    /// Add the type of each entry's trash (home, shared or private) to long and porcelain listings.
+    pub show_trash_type: bool,
+    /// Show each entry's original path instead of its name in the trash in the plain listing.
+    pub print_original: bool,
+    /// Show sizes in the long listing as exact byte counts instead of `1.2 MiB`.
+    pub bytes: bool,
+}
+
+/// What a listing is sorted by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+    /// File name, case-insensitively.
+    Name,
+    /// Deletion date from the matching .trashinfo file (the mtime if there is none), newest first.
+    Date,
+    /// Directories first, then files by size, largest first.
+    Size,
+}
+
+impl SortKey {
+    /// Returns the sort key named by `--sort`, if it is known.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "name" => Some(SortKey::Name),
+            "date" => Some(SortKey::Date),
+            "size" => Some(SortKey::Size),
+            _ => None,
+        }
+    }
+}
+
+/// The order of entries in a listing. Without a sort key, entries appear in directory order.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ListOrder {
+    pub sort: Option<SortKey>,
+    pub reverse: bool,
+}
+
+pub fn handle_display_trash<W: Write>(writer: &mut W, opts: DisplayTrashOptions) -> Result<(), AppError> {
+    let trashes = select_source_trashes(opts.all_trash, opts.trash_dir.as_deref())?;
+    if trashes.is_empty() {
         return Err(AppError::NoTrashDirectories);
     }
-    let mut writer = io::stdout();
-    for path in trash_dirs.iter() {
-        list_directory_contents_single_trash(&mut writer, path, long_format)?;
+    for trash in trashes.iter() {
+        list_directory_contents_single_trash(writer, trash, &opts)?;
     }
+    writer.flush()?;
     Ok(())
 }
 
 pub fn list_directory_contents_single_trash<W: Write>(
     writer: &mut W,
-    trash_dir: &Path,
-    long_format: bool,
+    trash: &TargetTrash,
+    opts: &DisplayTrashOptions,
 ) -> Result<(), AppError> {
-    let files_dir = trash_dir.join(TRASH_FILES_DIR_NAME);
+    let files_dir = trash.files_path();
+    let trash_type = opts.show_trash_type.then(|| trash.trash_type());
+    if opts.porcelain {
+        return list_directory_contents_porcelain(writer, &files_dir, opts.order, trash_type);
+    }
     print_absolute_path(writer, &files_dir)?;
-    if long_format {
-        list_directory_contents_long(writer, &files_dir)?;
+    if opts.long_format {
+        list_directory_contents_long(writer, &files_dir, opts.order, trash_type, opts.bytes)?;
     } else {
-        list_directory_contents(writer, &files_dir)?;
+        list_directory_contents(writer, &files_dir, opts.tty, opts.order, opts.print_original)?;
     }
+    write_summary(writer, &files_dir)?;
+    Ok(())
+}
+
+/// Returns the number of items in a trash `files` directory and their total recursive size,
+/// which is what emptying that trash would reclaim.
+fn trash_totals(files_dir: &Path) -> Result<(usize, u64), AppError> {
+    let paths = get_dir_entry_paths(files_dir)?;
+    let total_size = paths.iter().map(|path| dir_size(path)).sum();
+    Ok((paths.len(), total_size))
+}
+
+/// Writes a `N items, SIZE` line after a non-empty listing.
+fn write_summary<W: Write>(writer: &mut W, files_dir: &Path) -> Result<(), AppError> {
+    let (item_count, total_size) = trash_totals(files_dir)?;
+    if item_count == 0 {
+        return Ok(());
+    }
+    let noun = if item_count == 1 { "item" } else { "items" };
+    writeln!(writer, "{} {}, {}", item_count, noun, format_size(total_size, BINARY))?;
     Ok(())
 }
 
 fn print_absolute_path<W: Write>(writer: &mut W, dir_path: &Path) -> Result<(), AppError> {
-    let absolute_path = fs::canonicalize(dir_path).unwrap_or_else(|_| dir_path.to_path_buf());
-    writeln!(
-        writer,
-        "{}",
-        colorize_trash_directory(&absolute_path.display().to_string())
-    )?;
+    let absolute_path = fs::canonicalize(dir_path).unwrap_or_else(|_| normalize_lexically(dir_path));
+    writeln!(writer, "{}", colorize_trash_directory(&display_path(&absolute_path)))?;
     Ok(())
 }
 
+/// Removes `.` components, redundant separators and `dir/..` pairs from `path` without touching
+/// the filesystem, for a path that cannot be canonicalized (e.g. a trash that does not exist yet).
+/// Symlinks are not resolved, so `link/..` is taken to be the directory holding `link`.
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // `..` at the root stays at the root.
+                Some(Component::RootDir) => {}
+                _ => normalized.push(".."),
+            },
+            component => normalized.push(component),
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    normalized
+}
+
 fn get_dir_entry_paths(dir_path: &Path) -> Result<Vec<PathBuf>, AppError> {
     let entries = match fs::read_dir(dir_path) {
         Ok(entries) => entries,
@@ -67,90 +169,283 @@ fn get_dir_entry_paths(dir_path: &Path) -> Result<Vec<PathBuf>, AppError> {
         }
     };
 
-    entries
-        .map(|res| res.map(|e| e.path()))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(AppError::from)
+    let mut paths = Vec::new();
+    for entry in entries {
+        match entry {
+            Ok(entry) => paths.push(entry.path()),
+            // An entry can be removed by another process while we are iterating.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(source) => {
+                return Err(AppError::Io {
+                    path: dir_path.to_path_buf(),
+                    source,
+                })
+            }
+        }
+    }
+    Ok(paths)
 }
 
-fn list_directory_contents<W: Write>(writer: &mut W, dir_path: &Path) -> Result<(), AppError> {
-    let entries = get_dir_entry_paths(dir_path)?;
+/// Returns the entries of `dir_path` (a trash `files` directory) in the requested order.
+fn sorted_entry_paths(dir_path: &Path, order: ListOrder) -> Result<Vec<PathBuf>, AppError> {
+    let mut paths = get_dir_entry_paths(dir_path)?;
+    let name_key = |path: &PathBuf| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    };
+    match order.sort {
+        None => {}
+        Some(SortKey::Name) => paths.sort_by_cached_key(name_key),
+        Some(SortKey::Date) => {
+            let deletion_times = deletion_times(dir_path);
+            paths.sort_by_cached_key(|path| {
+                let deleted_at = deletion_times.get(path).copied().or_else(|| {
+                    let modified = fs::symlink_metadata(path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()?;
+                    Some(DateTime::<Local>::from(modified).naive_local())
+                });
+                (Reverse(deleted_at), name_key(path))
+            });
+        }
+        Some(SortKey::Size) => paths.sort_by_cached_key(|path| {
+            let metadata = fs::symlink_metadata(path).ok();
+            let is_dir = metadata.as_ref().is_some_and(|metadata| metadata.is_dir());
+            let size = metadata.filter(|_| !is_dir).map_or(0, |metadata| metadata.len());
+            (!is_dir, Reverse(size), name_key(path))
+        }),
+    }
+    if order.reverse {
+        paths.reverse();
+    }
+    Ok(paths)
+}
+
+/// Maps each data file of the trash whose `files` directory is `files_dir` to its deletion date.
+fn deletion_times(files_dir: &Path) -> HashMap<PathBuf, NaiveDateTime> {
+    let Some(trash_dir) = files_dir.parent() else {
+        return HashMap::new();
+    };
+    find_trash_entries_in_dirs(&[trash_dir.to_path_buf()])
+        .into_iter()
+        .filter_map(|entry| Some((entry.trashed_path.clone(), entry.deletion_time()?)))
+        .collect()
+}
+
+/// Writes one porcelain record per entry: `<files dir>\t<name>\t<size in bytes>\t<mtime in unix seconds>`,
+/// followed by `\t<trash type>` when `trash_type` is given.
+fn list_directory_contents_porcelain<W: Write>(
+    writer: &mut W,
+    dir_path: &Path,
+    order: ListOrder,
+    trash_type: Option<TrashType>,
+) -> Result<(), AppError> {
+    let files_dir = dir_path.display().to_string();
+    for path in sorted_entry_paths(dir_path, order)? {
+        let metadata = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(source) => return Err(AppError::Io { path, source }),
+        };
+        let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?.as_secs();
+        let filename = path.file_name().unwrap().to_string_lossy();
+        let size = metadata.len().to_string();
+        let modified = modified.to_string();
+        let mut fields = vec![files_dir.as_str(), &filename, &size, &modified];
+        fields.extend(trash_type.map(TrashType::label));
+        write_record(writer, &fields)?;
+    }
+    Ok(())
+}
+
+fn list_directory_contents<W: Write>(
+    writer: &mut W,
+    dir_path: &Path,
+    tty: bool,
+    order: ListOrder,
+    print_original: bool,
+) -> Result<(), AppError> {
+    let entries = sorted_entry_paths(dir_path, order)?;
 
     if entries.is_empty() {
         writeln!(writer, "  (empty)")?;
         return Ok(());
     };
 
-    if let Some(width) = term_size::dimensions().map(|(w, _)| w) {
-        let mut grid = Grid::new(GridOptions {
-            direction: Direction::TopToBottom,
-            filling: Filling::Spaces(2),
-        });
-
-        for entry in entries {
-            let path = entry;
+    let names: Vec<(String, String)> = entries
+        .iter()
+        .map(|path| {
             let filename = path
                 .file_name()
                 .map(|s| s.to_string_lossy())
                 .unwrap_or_else(|| "(Unknown)".into());
+            let label = if print_original {
+                original_path_label(path).unwrap_or_else(|| format!("{} {}", filename, NO_ORIGINAL_MARKER))
+            } else {
+                filename.into_owned()
+            };
+            let colored_string = colorize_path(&label, path.as_path()).to_string();
+            (label, colored_string)
+        })
+        .collect();
 
-            let colored_string = colorize_path(filename.as_ref(), path.as_path());
-
+    if tty {
+        let mut grid = Grid::new(GridOptions {
+            direction: Direction::TopToBottom,
+            filling: Filling::Spaces(2),
+        });
+        for (filename, colored_string) in &names {
             grid.add(Cell {
-                contents: colored_string.to_string(),
+                contents: colored_string.clone(),
                 width: filename.chars().count(),
             });
         }
-
-        if let Some(display) = grid.fit_into_width(width) {
+        if let Some(display) = grid.fit_into_width(terminal_width()) {
             write!(writer, "{}", display)?;
+            return Ok(());
         }
     }
 
+    // Not a terminal (or a name too wide for the grid): print one entry per line, like `ls`.
+    for (_, colored_string) in &names {
+        writeln!(writer, "{}", colored_string)?;
+    }
     Ok(())
 }
 
-fn list_directory_contents_long<W: Write>(writer: &mut W, dir_path: &Path) -> Result<(), AppError> {
-    let entries = get_dir_entry_paths(dir_path)?;
+fn list_directory_contents_long<W: Write>(
+    writer: &mut W,
+    dir_path: &Path,
+    order: ListOrder,
+    trash_type: Option<TrashType>,
+    bytes: bool,
+) -> Result<(), AppError> {
+    let entries = sorted_entry_paths(dir_path, order)?;
 
     if entries.is_empty() {
         writeln!(writer, "  (empty)")?;
         return Ok(());
     };
 
-    for entry in entries {
-        let path = entry;
-        let metadata = std::fs::metadata(&path).map_err(|source| AppError::Io {
-            path: path.clone(),
-            source,
-        })?;
-
-        #[cfg(unix)]
-        {
-            let mode_str = format_mode(metadata.mode(), metadata.is_dir());
-            let nlink = metadata.nlink();
-            let user = get_user_by_uid(metadata.uid())
-                .map(|u| u.name().to_string_lossy().into_owned())
-                .unwrap_or_else(|| metadata.uid().to_string());
-            let group = get_group_by_gid(metadata.gid())
-                .map(|g| g.name().to_string_lossy().into_owned())
-                .unwrap_or_else(|| metadata.gid().to_string());
-            let size = format_size(metadata.len(), BINARY);
-            let modified: DateTime<Local> = DateTime::from(metadata.modified()?);
-            let filename = path.file_name().unwrap().to_string_lossy();
-
-            writeln!(
-                writer,
-                "{} {:>2} {:<7} {:<7} {:>10} {} {}",
-                mode_str,
-                nlink,
-                colorize_user_group(&user),
-                colorize_user_group(&group),
-                colorize_file_size(size.as_str()),
-                colorize_modified(modified.format("%b %d %H:%M").to_string().as_str()),
-                colorize_path(&filename, &path)
-            )?;
+    // Directory sizes take a walk each, so they are computed once, before the widest one decides
+    // the column width that keeps sizes right-aligned however long they get.
+    let sizes: Vec<String> = entries
+        .iter()
+        .map(|path| format_entry_size(entry_size(path), bytes))
+        .collect();
+    let size_width = sizes.iter().map(String::len).max().unwrap_or(0);
+    for (path, size) in entries.iter().zip(&sizes) {
+        write_long_entry(writer, path, trash_type, size, size_width)?;
+    }
+    Ok(())
+}
+
+/// The size shown for a trashed item: the total size of its contents for a directory (see `dir_size`),
+/// otherwise its own size. A symbolic link is not followed, even to a directory.
+fn entry_size(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => dir_size(path),
+        Ok(_) => fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0),
+        Err(_) => 0,
+    }
+}
+
+/// Formats a size for the long listing: the exact byte count with `bytes`, otherwise in binary units.
+fn format_entry_size(len: u64, bytes: bool) -> String {
+    if bytes {
+        len.to_string()
+    } else {
+        format_size(len, BINARY)
+    }
+}
+
+/// Placeholder for the deletion date and original path of an item without a readable `.trashinfo` file.
+const MISSING_INFO: &str = "-";
+
+/// Appended to the trashed name by `--print-original` when the original path cannot be determined.
+const NO_ORIGINAL_MARKER: &str = "[no info]";
+
+/// Returns the original path of a trashed item for display, or `None` when its `.trashinfo` file
+/// is missing, unreadable, lacks a `Path` or has a malformed percent escape in it.
+fn original_path_label(trashed_path: &Path) -> Option<String> {
+    let info_path = info_path_for(trashed_path)?;
+    let encoded_path = read_trash_info(&info_path).ok()?.path?;
+    let original_path = trash_spec_url_decode_strict(&encoded_path, &info_path).ok()?;
+    Some(display_path(Path::new(&original_path)))
+}
+
+/// Returns the deletion date and decoded original path recorded for a trashed item,
+/// or placeholders when its `.trashinfo` file is missing or incomplete.
+fn deletion_details(trashed_path: &Path) -> (String, String) {
+    let info = info_path_for(trashed_path)
+        .and_then(|info_path| read_trash_info(&info_path).ok())
+        .unwrap_or_default();
+    let deleted = info
+        .deletion_date
+        .map(|date| match parse_deletion_date(&date) {
+            Some(time) => time.format("%Y-%m-%d %H:%M").to_string(),
+            None => date,
+        })
+        .unwrap_or_else(|| MISSING_INFO.to_string());
+    let original_path = info
+        .path
+        .map(|path| display_path(Path::new(&trash_spec_url_decode(&path))))
+        .unwrap_or_else(|| MISSING_INFO.to_string());
+    (deleted, original_path)
+}
+
+/// Writes a single `ls -l` style line for `path`, followed by its deletion date, the type of its trash
+/// if `trash_type` is given, and its original path. The formatted `size` is right-aligned to `size_width`.
+/// An entry that vanished after the directory was enumerated is skipped rather than aborting the listing.
+/// A symbolic link is described itself, so a dangling one is listed too.
+fn write_long_entry<W: Write>(
+    writer: &mut W,
+    path: &Path,
+    trash_type: Option<TrashType>,
+    size: &str,
+    size_width: usize,
+) -> Result<(), AppError> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(source) => {
+            return Err(AppError::Io {
+                path: path.to_path_buf(),
+                source,
+            })
         }
+    };
+
+    #[cfg(unix)]
+    {
+        let mode_str = format_mode(metadata.mode(), metadata.is_dir());
+        let nlink = metadata.nlink();
+        let user = get_user_by_uid(metadata.uid())
+            .map(|u| u.name().to_string_lossy().into_owned())
+            .unwrap_or_else(|| metadata.uid().to_string());
+        let group = get_group_by_gid(metadata.gid())
+            .map(|g| g.name().to_string_lossy().into_owned())
+            .unwrap_or_else(|| metadata.gid().to_string());
+        let modified: DateTime<Local> = DateTime::from(metadata.modified()?);
+        let filename = path.file_name().unwrap().to_string_lossy();
+        let (deleted, original_path) = deletion_details(path);
+        let type_column = trash_type.map_or(String::new(), |trash_type| format!("{:<7} ", trash_type.label()));
+
+        writeln!(
+            writer,
+            "{} {:>2} {:<7} {:<7} {:>size_width$} {} {:<16} {}{} {}",
+            mode_str,
+            nlink,
+            colorize_user_group(&user),
+            colorize_user_group(&group),
+            colorize_file_size(size),
+            colorize_modified(modified.format("%b %d %H:%M").to_string().as_str()),
+            colorize_modified(&deleted),
+            type_column,
+            colorize_path(&filename, path),
+            original_path
+        )?;
     }
     Ok(())
 }
@@ -158,6 +453,7 @@ fn list_directory_contents_long<W: Write>(writer: &mut W, dir_path: &Path) -> Re
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::trash::spec::{TRASH_FILES_DIR_NAME, TRASH_INFO_DIR_NAME};
     use std::fs::{self, File};
     #[cfg(unix)]
     use std::os::unix::fs::PermissionsExt;
@@ -169,6 +465,26 @@ mod tests {
         re.replace_all(s, "").to_string()
     }
 
+    #[test]
+    fn test_listing_nonexistent_trash_creates_nothing() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let trash_dir = root.path().join("Trash");
+
+        // With nothing to list, listing must not create the trash it was pointed at.
+        for long_format in [false, true] {
+            let opts = DisplayTrashOptions {
+                long_format,
+                ..Default::default()
+            };
+            let trash = TargetTrash::new(trash_dir.clone(), TrashType::Home);
+            list_directory_contents_single_trash(&mut Vec::new(), &trash, &opts)?;
+        }
+
+        assert!(!trash_dir.exists(), "Listing must not create the trash directory");
+        assert_eq!(fs::read_dir(root.path())?.count(), 0);
+        Ok(())
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_list_directory_contents_long() -> Result<(), AppError> {
@@ -194,7 +510,7 @@ mod tests {
             .unwrap_or_else(|| gid.to_string());
 
         let mut output_buffer = Vec::new();
-        list_directory_contents_long(&mut output_buffer, files_dir)?;
+        list_directory_contents_long(&mut output_buffer, files_dir, ListOrder::default(), None, false)?;
 
         let output = String::from_utf8(output_buffer)?;
         let stripped_output = strip_ansi(&output);
@@ -207,6 +523,73 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn test_list_directory_contents_long_bytes() -> Result<(), AppError> {
+        let temp_dir = tempdir()?;
+        let files_dir = temp_dir.path();
+        fs::write(files_dir.join("big.bin"), vec![0u8; 1_234_567])?;
+        fs::write(files_dir.join("small.txt"), "hello")?;
+
+        let listing = |bytes: bool| -> Result<Vec<String>, AppError> {
+            let mut output_buffer = Vec::new();
+            let order = ListOrder {
+                sort: Some(SortKey::Name),
+                reverse: false,
+            };
+            list_directory_contents_long(&mut output_buffer, files_dir, order, None, bytes)?;
+            let output = strip_ansi(&String::from_utf8(output_buffer)?);
+            Ok(output.lines().map(str::to_string).collect())
+        };
+        // Where each expected size ends in its line; right-aligned sizes all end in the same column.
+        let size_ends = |lines: &[String], sizes: [&str; 2]| -> Vec<usize> {
+            lines
+                .iter()
+                .zip(sizes)
+                .map(|(line, size)| {
+                    let start = line
+                        .find(&format!(" {} ", size))
+                        .unwrap_or_else(|| panic!("{:?} not in {:?}", size, line));
+                    start + 1 + size.len()
+                })
+                .collect()
+        };
+
+        let raw = size_ends(&listing(true)?, ["1234567", "5"]);
+        assert_eq!(raw[0], raw[1], "sizes are right-aligned");
+
+        let human = size_ends(&listing(false)?, ["1.18 MiB", "5 B"]);
+        assert_eq!(human[0], human[1], "sizes are right-aligned");
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_list_directory_contents_long_sums_directory_sizes() -> Result<(), AppError> {
+        let temp_dir = tempdir()?;
+        let files_dir = temp_dir.path();
+        let project = files_dir.join("project");
+        fs::create_dir_all(project.join("src/nested"))?;
+        fs::write(project.join("README"), vec![b'r'; 100])?;
+        fs::write(project.join("src/main.rs"), vec![b'm'; 2_000])?;
+        fs::write(project.join("src/nested/data.bin"), vec![0u8; 30_000])?;
+        // A link back up the tree is not followed, so it cannot loop or count anything twice.
+        std::os::unix::fs::symlink(&project, project.join("src/nested/loop"))?;
+
+        let link_len = fs::symlink_metadata(project.join("src/nested/loop"))?.len();
+        assert_eq!(entry_size(&project), 32_100 + link_len);
+
+        let mut output_buffer = Vec::new();
+        list_directory_contents_long(&mut output_buffer, files_dir, ListOrder::default(), None, true)?;
+        let output = strip_ansi(&String::from_utf8(output_buffer)?);
+        assert!(
+            output.contains(&format!(" {} ", 32_100 + link_len)),
+            "the directory shows the sum of its contents: {}",
+            output
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_list_directory_contents() -> Result<(), AppError> {
         let temp_dir_with_files = tempdir()?;
@@ -215,26 +598,31 @@ mod tests {
         File::create(files_dir.join("file1.txt"))?;
         File::create(files_dir.join("another-file.log"))?;
 
-        let mut output_buffer = Vec::new();
-        list_directory_contents(&mut output_buffer, files_dir)?;
+        for tty in [true, false] {
+            let mut output_buffer = Vec::new();
+            list_directory_contents(&mut output_buffer, files_dir, tty, ListOrder::default(), false)?;
 
-        let output = String::from_utf8(output_buffer)?;
-        let stripped_output = strip_ansi(&output);
+            let output = String::from_utf8(output_buffer)?;
+            let stripped_output = strip_ansi(&output);
 
-        assert!(
-            stripped_output.contains("file1.txt"),
-            "Should contain the first filename"
-        );
-        assert!(
-            stripped_output.contains("another-file.log"),
-            "Should contain the second filename"
-        );
+            assert!(
+                stripped_output.contains("file1.txt"),
+                "Should contain the first filename"
+            );
+            assert!(
+                stripped_output.contains("another-file.log"),
+                "Should contain the second filename"
+            );
+            if !tty {
+                assert_eq!(stripped_output.lines().count(), 2, "Should print one entry per line");
+            }
+        }
 
         let temp_dir_empty = tempdir()?;
         let empty_dir = temp_dir_empty.path();
 
         let mut output_buffer_empty = Vec::new();
-        list_directory_contents(&mut output_buffer_empty, empty_dir)?;
+        list_directory_contents(&mut output_buffer_empty, empty_dir, false, ListOrder::default(), false)?;
 
         let output_empty = String::from_utf8(output_buffer_empty)?;
         let stripped_output_empty = strip_ansi(&output_empty);
@@ -253,7 +641,13 @@ mod tests {
         let non_existent_path = temp_dir.path().join("does-not-exist");
 
         let mut output_buffer = Vec::new();
-        let result = list_directory_contents(&mut output_buffer, &non_existent_path);
+        let result = list_directory_contents(
+            &mut output_buffer,
+            &non_existent_path,
+            false,
+            ListOrder::default(),
+            false,
+        );
 
         assert!(
             result.is_ok(),
@@ -269,6 +663,355 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_list_directory_contents_sorted() -> Result<(), AppError> {
+        let trash_dir = tempdir()?;
+        let files_dir = trash_dir.path().join(TRASH_FILES_DIR_NAME);
+        let info_dir = trash_dir.path().join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(files_dir.join("Zdir"))?;
+        fs::create_dir_all(&info_dir)?;
+        for (name, size, deleted) in [
+            ("beta.txt", 30, "2024-01-02T00:00:00"),
+            ("Alpha.txt", 10, "2024-01-03T00:00:00"),
+            ("gamma.txt", 20, "2024-01-01T00:00:00"),
+        ] {
+            fs::write(files_dir.join(name), vec![0u8; size])?;
+            fs::write(
+                info_dir.join(format!("{}.trashinfo", name)),
+                format!("[Trash Info]\nPath=/home/user/{}\nDeletionDate={}\n", name, deleted),
+            )?;
+        }
+        // No info file: sorted by its mtime, which is newer than every deletion date above.
+        fs::write(info_dir.join("Zdir.trashinfo"), "")?;
+
+        struct TestCase {
+            sort: SortKey,
+            reverse: bool,
+            expected: &'static [&'static str],
+        }
+        let test_cases = vec![
+            TestCase {
+                sort: SortKey::Name,
+                reverse: false,
+                expected: &["Alpha.txt", "beta.txt", "gamma.txt", "Zdir"],
+            },
+            TestCase {
+                sort: SortKey::Name,
+                reverse: true,
+                expected: &["Zdir", "gamma.txt", "beta.txt", "Alpha.txt"],
+            },
+            TestCase {
+                sort: SortKey::Date,
+                reverse: false,
+                expected: &["Zdir", "Alpha.txt", "beta.txt", "gamma.txt"],
+            },
+            TestCase {
+                sort: SortKey::Size,
+                reverse: false,
+                expected: &["Zdir", "beta.txt", "gamma.txt", "Alpha.txt"],
+            },
+            TestCase {
+                sort: SortKey::Size,
+                reverse: true,
+                expected: &["Alpha.txt", "gamma.txt", "beta.txt", "Zdir"],
+            },
+        ];
+
+        for case in test_cases {
+            let order = ListOrder {
+                sort: Some(case.sort),
+                reverse: case.reverse,
+            };
+            let mut output_buffer = Vec::new();
+            list_directory_contents(&mut output_buffer, &files_dir, false, order, false)?;
+            let output = strip_ansi(&String::from_utf8(output_buffer)?);
+            let names: Vec<&str> = output.lines().collect();
+            assert_eq!(
+                names, case.expected,
+                "Failed on {:?} (reverse: {})",
+                case.sort, case.reverse
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_listing_summary_sums_recursive_sizes() -> Result<(), AppError> {
+        let trash_dir = tempdir()?;
+        let files_dir = trash_dir.path().join(TRASH_FILES_DIR_NAME);
+        fs::create_dir_all(files_dir.join("project/src"))?;
+        fs::write(files_dir.join("project/src/lib.rs"), vec![0u8; 1000])?;
+        fs::write(files_dir.join("project/README"), vec![0u8; 24])?;
+        fs::write(files_dir.join("notes.txt"), vec![0u8; 1024])?;
+
+        assert_eq!(trash_totals(&files_dir)?, (2, 2048));
+
+        let mut output_buffer = Vec::new();
+        let trash = TargetTrash::new(trash_dir.path().to_path_buf(), TrashType::Home);
+        list_directory_contents_single_trash(&mut output_buffer, &trash, &DisplayTrashOptions::default())?;
+        let output = strip_ansi(&String::from_utf8(output_buffer)?);
+        assert!(output.ends_with("\n2 items, 2 KiB\n"), "{}", output);
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_directory_contents_porcelain() -> Result<(), AppError> {
+        let temp_dir = tempdir()?;
+        let files_dir = temp_dir.path();
+        fs::write(files_dir.join("data.bin"), b"12345")?;
+
+        let mut output_buffer = Vec::new();
+        list_directory_contents_porcelain(&mut output_buffer, files_dir, ListOrder::default(), None)?;
+
+        let output = String::from_utf8(output_buffer)?;
+        let fields: Vec<&str> = output.trim_end().split('\t').collect();
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields[0], files_dir.display().to_string());
+        assert_eq!(fields[1], "data.bin");
+        assert_eq!(fields[2], "5");
+        assert!(fields[3].parse::<u64>().is_ok(), "mtime should be unix seconds");
+        assert!(!output.contains('\x1b'), "Porcelain output must be color-free");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_entry_shows_deletion_date_and_original_path() -> Result<(), AppError> {
+        let trash_dir = tempdir()?;
+        let files_dir = trash_dir.path().join(TRASH_FILES_DIR_NAME);
+        let info_dir = trash_dir.path().join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(&files_dir)?;
+        fs::create_dir_all(&info_dir)?;
+        File::create(files_dir.join("report.pdf"))?;
+        fs::write(
+            info_dir.join("report.pdf.trashinfo"),
+            "[Trash Info]\nPath=/home/user/My%20Docs/report.pdf\nDeletionDate=2024-03-05T14:07:00\n",
+        )?;
+        File::create(files_dir.join("orphan.bin"))?;
+
+        let mut output_buffer = Vec::new();
+        write_long_entry(&mut output_buffer, &files_dir.join("report.pdf"), None, "0 B", 3)?;
+        write_long_entry(&mut output_buffer, &files_dir.join("orphan.bin"), None, "0 B", 3)?;
+        let output = strip_ansi(&String::from_utf8(output_buffer)?);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert!(
+            lines[0].ends_with("2024-03-05 14:07 report.pdf /home/user/My Docs/report.pdf"),
+            "{}",
+            lines[0]
+        );
+        assert!(lines[1].ends_with(" -                orphan.bin -"), "{}", lines[1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_directory_contents_prints_original_paths() -> Result<(), AppError> {
+        let trash_dir = tempdir()?;
+        let files_dir = trash_dir.path().join(TRASH_FILES_DIR_NAME);
+        let info_dir = trash_dir.path().join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(&files_dir)?;
+        fs::create_dir_all(&info_dir)?;
+        File::create(files_dir.join("report.2.pdf"))?;
+        fs::write(
+            info_dir.join("report.2.pdf.trashinfo"),
+            "[Trash Info]\nPath=/home/user/My%20Docs/report.pdf\nDeletionDate=2024-03-05T14:07:00\n",
+        )?;
+        File::create(files_dir.join("corrupt.txt"))?;
+        fs::write(
+            info_dir.join("corrupt.txt.trashinfo"),
+            "[Trash Info]\nPath=/home/user/bad%GG.txt\nDeletionDate=2024-03-05T14:07:00\n",
+        )?;
+        File::create(files_dir.join("orphan.bin"))?;
+
+        let mut output_buffer = Vec::new();
+        let order = ListOrder {
+            sort: Some(SortKey::Name),
+            reverse: false,
+        };
+        list_directory_contents(&mut output_buffer, &files_dir, false, order, true)?;
+        assert_eq!(
+            strip_ansi(&String::from_utf8(output_buffer)?),
+            "corrupt.txt [no info]\norphan.bin [no info]\n/home/user/My Docs/report.pdf\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_trash_lists_only_the_selected_trash_dir() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let home_trash = root.path().join("home/Trash");
+        let usb_trash = root.path().join("usb/.Trash-1000");
+        for (trash, name) in [(&home_trash, "from-home.txt"), (&usb_trash, "from-usb.txt")] {
+            fs::create_dir_all(trash.join(TRASH_FILES_DIR_NAME))?;
+            fs::create_dir_all(trash.join(TRASH_INFO_DIR_NAME))?;
+            fs::write(trash.join(TRASH_FILES_DIR_NAME).join(name), "data")?;
+        }
+
+        let mut output_buffer = Vec::new();
+        handle_display_trash(
+            &mut output_buffer,
+            DisplayTrashOptions {
+                trash_dir: Some(usb_trash.clone()),
+                ..Default::default()
+            },
+        )?;
+        let output = strip_ansi(&String::from_utf8(output_buffer)?);
+        assert!(output.contains("from-usb.txt"), "{}", output);
+        assert!(!output.contains("from-home.txt"), "{}", output);
+
+        let result = handle_display_trash(
+            &mut Vec::new(),
+            DisplayTrashOptions {
+                trash_dir: Some(root.path().join("home")),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(result, Err(AppError::NotATrashDirectory { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_listing_shows_trash_type() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let home_trash = TargetTrash::new(root.path().join("home/Trash"), TrashType::Home);
+        let private_trash = TargetTrash::new(root.path().join("usb/.Trash-1000"), TrashType::TopdirPrivate);
+        for trash in [&home_trash, &private_trash] {
+            fs::create_dir_all(trash.files_path())?;
+            fs::write(trash.files_path().join("notes.txt"), "notes")?;
+        }
+
+        let porcelain = DisplayTrashOptions {
+            porcelain: true,
+            show_trash_type: true,
+            ..Default::default()
+        };
+        let long = DisplayTrashOptions {
+            long_format: true,
+            show_trash_type: true,
+            ..Default::default()
+        };
+        for (trash, expected) in [(&home_trash, "home"), (&private_trash, "private")] {
+            let mut output_buffer = Vec::new();
+            list_directory_contents_single_trash(&mut output_buffer, trash, &porcelain)?;
+            let output = String::from_utf8(output_buffer)?;
+            let fields: Vec<&str> = output.trim_end().split('\t').collect();
+            assert_eq!(fields.len(), 5);
+            assert_eq!(fields[4], expected);
+
+            let mut output_buffer = Vec::new();
+            list_directory_contents_single_trash(&mut output_buffer, trash, &long)?;
+            let output = strip_ansi(&String::from_utf8(output_buffer)?);
+            let entry = output.lines().nth(1).unwrap_or_default();
+            assert!(entry.ends_with(&format!(" {:<7} notes.txt -", expected)), "{}", entry);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_lexically() {
+        struct TestCase {
+            path: &'static str,
+            expected: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                path: "/home/user//.local/share/./Trash/files",
+                expected: "/home/user/.local/share/Trash/files",
+            },
+            TestCase {
+                path: "/mnt/usb/sub/../.Trash-1000/files/",
+                expected: "/mnt/usb/.Trash-1000/files",
+            },
+            TestCase {
+                path: "/../Trash",
+                expected: "/Trash",
+            },
+            TestCase {
+                path: "../a/./b/../../c",
+                expected: "../c",
+            },
+            TestCase {
+                path: "./",
+                expected: ".",
+            },
+        ];
+
+        for case in test_cases {
+            assert_eq!(
+                normalize_lexically(Path::new(case.path)),
+                PathBuf::from(case.expected),
+                "path: {}",
+                case.path
+            );
+        }
+    }
+
+    #[test]
+    fn test_header_of_missing_trash_is_normalized() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let root_path = root.path().canonicalize()?;
+        let messy = root_path.join("missing//./Trash/../Trash").join(TRASH_FILES_DIR_NAME);
+
+        let mut output_buffer = Vec::new();
+        print_absolute_path(&mut output_buffer, &messy)?;
+        let output = strip_ansi(&String::from_utf8(output_buffer)?);
+        assert_eq!(
+            output,
+            format!(
+                "{}\n",
+                root_path.join("missing/Trash").join(TRASH_FILES_DIR_NAME).display()
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_long_entry_skips_vanished_entry() -> Result<(), AppError> {
+        let temp_dir = tempdir()?;
+        let vanished_path = temp_dir.path().join("vanished.txt");
+        File::create(&vanished_path)?;
+        // Simulate another process removing the entry between enumeration and stat.
+        fs::remove_file(&vanished_path)?;
+
+        let mut output_buffer = Vec::new();
+        let result = write_long_entry(&mut output_buffer, &vanished_path, None, "0 B", 3);
+
+        assert!(result.is_ok(), "A vanished entry should not abort the listing");
+        assert!(
+            output_buffer.is_empty(),
+            "Nothing should be written for a vanished entry"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_dangling_symlink_is_listed() -> Result<(), AppError> {
+        let temp_dir = tempdir()?;
+        let files_dir = temp_dir.path();
+        let link_path = files_dir.join("dangling");
+        std::os::unix::fs::symlink(files_dir.join("no-such-target"), &link_path)?;
+
+        let mut long_buffer = Vec::new();
+        write_long_entry(&mut long_buffer, &link_path, None, "0 B", 3)?;
+        let long_output = strip_ansi(&String::from_utf8(long_buffer)?);
+        assert!(
+            long_output.contains(" dangling "),
+            "A dangling symlink should get a long entry, got: {long_output:?}"
+        );
+
+        let mut porcelain_buffer = Vec::new();
+        list_directory_contents_porcelain(&mut porcelain_buffer, files_dir, ListOrder::default(), None)?;
+        let porcelain_output = String::from_utf8(porcelain_buffer)?;
+        let fields: Vec<&str> = porcelain_output.trim_end().split('\t').collect();
+        assert_eq!(fields.len(), 4, "A dangling symlink should get a porcelain record");
+        assert_eq!(fields[1], "dangling");
+
+        Ok(())
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_list_directory_permission_error() -> Result<(), AppError> {
@@ -282,7 +1025,7 @@ mod tests {
         fs::set_permissions(&unreadable_dir, perms)?;
 
         let mut output_buffer = Vec::new();
-        let result = list_directory_contents(&mut output_buffer, &unreadable_dir);
+        let result = list_directory_contents(&mut output_buffer, &unreadable_dir, false, ListOrder::default(), false);
 
         assert!(result.is_err(), "Expected an I/O error due to permissions");
         if let Err(AppError::Io { path, .. }) = result {
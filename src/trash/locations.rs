@@ -1,24 +1,177 @@
+use std::collections::HashSet;
 use std::env;
-use std::fs::{self, File};
-use std::io::{BufRead, BufReader};
-use std::os::unix::fs::PermissionsExt;
+use std::ffi::OsStr;
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::trash::error::AppError;
+use crate::trash::verbose::trace;
 
 use crate::trash::spec::{TRASH_FILES_DIR_NAME, TRASH_INFO_DIR_NAME};
 
-#[cfg(unix)]
-const MOUNTS_FILE_PATH: &str = "/proc/mounts";
+#[cfg(target_os = "linux")]
+pub(crate) const MOUNTS_FILE_PATH: &str = "/proc/mounts";
 
-#[derive(Debug, PartialEq)]
+#[cfg(target_os = "macos")]
+const VOLUMES_DIR_PATH: &str = "/Volumes";
+
+/// The environment variable listing the system data directories, separated by `:`.
+const XDG_DATA_DIRS_ENV: &str = "XDG_DATA_DIRS";
+
+/// The data directories used when `$XDG_DATA_DIRS` is unset or empty, per the XDG Base Directory spec.
+const DEFAULT_XDG_DATA_DIRS: &str = "/usr/local/share:/usr/share";
+
+/// Filesystem types whose file names are compared case-insensitively, so that
+/// `File.txt` and `file.txt` name the same entry.
+const CASE_INSENSITIVE_FS_TYPES: &[&str] = &["vfat", "msdos", "fat", "exfat", "hfsplus", "apfs"];
+
+/// Set by `--no-create` and by read-only commands; see `set_no_create`.
+static NO_CREATE: AtomicBool = AtomicBool::new(false);
+
+/// Forbids (or allows again) creating trash directories for the rest of the run.
+/// Read-only commands enable this so they are guaranteed to leave the disk untouched.
+pub fn set_no_create(enabled: bool) {
+    NO_CREATE.store(enabled, Ordering::Relaxed);
+}
+
+fn is_no_create() -> bool {
+    NO_CREATE.load(Ordering::Relaxed)
+}
+
+/// Fails if directory creation has been forbidden with `set_no_create`.
+pub(crate) fn ensure_creation_allowed(path: &Path) -> Result<(), AppError> {
+    refuse_creation_if(is_no_create(), path)
+}
+
+fn refuse_creation_if(no_create: bool, path: &Path) -> Result<(), AppError> {
+    if no_create {
+        return Err(AppError::Message(format!(
+            "Refusing to create '{}' in no-create mode",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum TrashType {
     Home,             // $XDG_DATA_HOME/Trash, $HOME/.local/share/Trash
     TopdirShared,     // $topdir/.Trash
     TopdirSharedUser, // $topdir/.Trash/$uid
     TopdirPrivate,    // $topdir/.Trash-$uid
+    DataDir,          // $XDG_DATA_DIRS/Trash, only listed and restored from
+}
+
+impl TrashType {
+    /// A short name for listings: `home`, `shared` (`$topdir/.Trash`) or `private` (`$topdir/.Trash-$uid`).
+    pub fn label(self) -> &'static str {
+        match self {
+            TrashType::Home => "home",
+            TrashType::TopdirShared | TrashType::TopdirSharedUser => "shared",
+            TrashType::TopdirPrivate => "private",
+            TrashType::DataDir => "data",
+        }
+    }
+}
+
+/// A mounted filesystem: where it is mounted and its type (e.g. `ext4`, `vfat`), when known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Mount {
+    path: PathBuf,
+    fs_type: Option<String>,
+}
+
+/// The mount table, read once per run and passed down to everything that needs to know
+/// which filesystem a path is on, instead of being read again for every file.
+#[derive(Debug, Clone, Default)]
+pub struct Mounts {
+    mounts: Vec<Mount>,
 }
 
+impl Mounts {
+    /// Reads the mount table: /proc/mounts on Linux, getmntinfo(3) on the BSDs.
+    pub fn read() -> Result<Mounts, AppError> {
+        #[cfg(target_os = "linux")]
+        return Mounts::read_from(Path::new(MOUNTS_FILE_PATH));
+
+        #[cfg(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+        return Ok(bsd_mounts());
+
+        #[cfg(not(any(
+            target_os = "linux",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "netbsd"
+        )))]
+        {
+            let mounts = mountpoints::mountinfos()?
+                .into_iter()
+                .map(|info| Mount {
+                    path: info.path,
+                    fs_type: info.format,
+                })
+                .collect();
+            Ok(Mounts { mounts })
+        }
+    }
+
+    /// Reads a mount table in the format of /proc/mounts.
+    #[cfg(target_os = "linux")]
+    fn read_from(mounts_path: &Path) -> Result<Mounts, AppError> {
+        let content = fs::read_to_string(mounts_path).map_err(|source| AppError::Io {
+            path: mounts_path.to_path_buf(),
+            source,
+        })?;
+        let mounts = content
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace().skip(1);
+                let path = unescape_mount_field(fields.next()?);
+                let fs_type = fields.next().map(str::to_string);
+                Some(Mount { path, fs_type })
+            })
+            .collect();
+        Ok(Mounts { mounts })
+    }
+
+    /// A mount table holding just the given mount points, with unknown filesystem types.
+    #[cfg(test)]
+    pub(crate) fn from_paths(paths: Vec<PathBuf>) -> Mounts {
+        let mounts = paths.into_iter().map(|path| Mount { path, fs_type: None }).collect();
+        Mounts { mounts }
+    }
+
+    /// The mount points, in the order of the mount table.
+    fn mount_points(&self) -> impl Iterator<Item = &Path> {
+        self.mounts.iter().map(|mount| mount.path.as_path())
+    }
+
+    /// The filesystem `path` is on: the mount with the longest mount point that contains it.
+    fn mount_for(&self, path: &Path) -> Option<&Mount> {
+        self.mounts
+            .iter()
+            .filter(|mount| path.starts_with(&mount.path))
+            .max_by_key(|mount| mount.path.as_os_str().len())
+    }
+
+    /// The mount point of the filesystem `path` is on, if any mount contains it.
+    pub fn mount_point_for(&self, path: &Path) -> Option<&Path> {
+        self.mount_for(path).map(|mount| mount.path.as_path())
+    }
+
+    /// Returns true if `path` lives on a filesystem type known to be case-insensitive.
+    /// A filesystem of unknown type is assumed to be case-sensitive.
+    pub fn is_case_insensitive(&self, path: &Path) -> bool {
+        self.mount_for(path)
+            .and_then(|mount| mount.fs_type.as_deref())
+            .is_some_and(is_case_insensitive_fs_type)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
 pub struct TargetTrash {
     root_path: PathBuf,
     trash_type: TrashType,
@@ -29,6 +182,10 @@ impl TargetTrash {
         Self { root_path, trash_type }
     }
 
+    pub fn trash_type(&self) -> TrashType {
+        self.trash_type
+    }
+
     pub fn root_path(&self) -> &Path {
         &self.root_path
     }
@@ -42,6 +199,16 @@ impl TargetTrash {
     }
 
     pub fn ensure_structure_exists(&self) -> Result<(), AppError> {
+        self.ensure_structure(is_no_create())
+    }
+
+    /// Creates whatever is missing of the trash, or fails without creating anything with `no_create`.
+    fn ensure_structure(&self, no_create: bool) -> Result<(), AppError> {
+        for path in [self.root_path.clone(), self.files_path(), self.info_path()] {
+            if !path.exists() {
+                refuse_creation_if(no_create, &path)?;
+            }
+        }
         self.create_root_dir()?;
 
         // The `files` and `info` directories inherit permissions from their parent, `root_path`.
@@ -60,6 +227,12 @@ impl TargetTrash {
         Ok(())
     }
 
+    /// Fails with `AppError::ReadOnlyFilesystem` if the trash sits on a read-only mount, so
+    /// the user gets a clear message instead of a permission error from deep inside a move.
+    pub fn ensure_writable_filesystem(&self) -> Result<(), AppError> {
+        check_writable_filesystem(&self.root_path, is_read_only_fs)
+    }
+
     fn create_root_dir(&self) -> Result<(), AppError> {
         match self.trash_type {
             TrashType::Home => self.create_with_mode(0o700, true),
@@ -68,6 +241,11 @@ impl TargetTrash {
             // type. It's kept for conceptual completeness according to the specification.
             TrashType::TopdirShared => self.create_with_mode(0o1777, true),
             TrashType::TopdirSharedUser | TrashType::TopdirPrivate => self.create_with_fallback(0o700, 0o1777),
+            // Never a target: these are found only when they already exist, and only searched.
+            TrashType::DataDir => Err(AppError::Message(format!(
+                "Refusing to create '{}': trash directories under $XDG_DATA_DIRS are only searched",
+                self.root_path.display()
+            ))),
         }
     }
 
@@ -128,17 +306,124 @@ impl TargetTrash {
     }
 }
 
+/// Probes the filesystem of the nearest existing ancestor of `trash_root` (the trash itself may
+/// not exist yet) with `is_read_only`. A failing probe is not treated as read-only.
+fn check_writable_filesystem<F>(trash_root: &Path, is_read_only: F) -> Result<(), AppError>
+where
+    F: Fn(&Path) -> std::io::Result<bool>,
+{
+    let Some(existing) = trash_root.ancestors().find(|path| path.exists()) else {
+        return Ok(());
+    };
+    if is_read_only(existing).unwrap_or(false) {
+        return Err(AppError::ReadOnlyFilesystem {
+            path: trash_root.to_path_buf(),
+        });
+    }
+    Ok(())
+}
+
+/// Returns true if `path` is on a filesystem mounted read-only (`ST_RDONLY`).
+fn is_read_only_fs(path: &Path) -> std::io::Result<bool> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `c_path` is a valid NUL-terminated string and `stat` is only read after a successful call.
+    if unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // SAFETY: `statvfs` returned 0, so it filled in `stat`.
+    let stat = unsafe { stat.assume_init() };
+    Ok(stat.f_flag & libc::ST_RDONLY != 0)
+}
+
 /// Gets the trash directories to operate on, either all available or just the one for the current context.
 pub fn get_target_trash_dirs(all_trash: bool) -> Result<Vec<PathBuf>, AppError> {
-    let trash_dirs = if all_trash {
-        find_all_trash_dirs()?
-    } else {
-        let current_dir_path = env::current_dir()?;
-        let mounts = mountpoints::mountpaths()?;
-        let target_trash = resolve_target_trash(&current_dir_path, &mounts)?;
-        vec![target_trash.root_path().to_path_buf()]
+    Ok(get_target_trashes(all_trash)?
+        .into_iter()
+        .map(|trash| trash.root_path)
+        .collect())
+}
+
+/// Like `get_target_trash_dirs`, but keeps the type of each trash, e.g. for listings that show it.
+pub fn get_target_trashes(all_trash: bool) -> Result<Vec<TargetTrash>, AppError> {
+    if all_trash {
+        return Ok(find_all_trashes());
+    }
+    let current_dir_path = env::current_dir()?;
+    let mounts = Mounts::read()?;
+    Ok(vec![resolve_target_trash(&current_dir_path, &mounts)?])
+}
+
+/// Gets the trash directories to operate on: only `trash_dir` when one was chosen with
+/// `--trash-dir`, otherwise the same ones as `get_target_trashes`.
+pub fn select_target_trashes(all_trash: bool, trash_dir: Option<&Path>) -> Result<Vec<TargetTrash>, AppError> {
+    match trash_dir {
+        Some(trash_dir) => Ok(vec![open_trash_dir(trash_dir)?]),
+        None => get_target_trashes(all_trash),
+    }
+}
+
+/// Like `select_target_trashes`, but returns only the root paths.
+pub fn select_target_trash_dirs(all_trash: bool, trash_dir: Option<&Path>) -> Result<Vec<PathBuf>, AppError> {
+    Ok(select_target_trashes(all_trash, trash_dir)?
+        .into_iter()
+        .map(|trash| trash.root_path)
+        .collect())
+}
+
+/// Gets the trash directories to list or restore from. These are the same as `select_target_trashes`,
+/// but with `all_trash` (and no `trash_dir`) they also include the trash directories under
+/// `$XDG_DATA_DIRS`. Those are never emptied, purged, checked or trashed into.
+pub fn select_source_trashes(all_trash: bool, trash_dir: Option<&Path>) -> Result<Vec<TargetTrash>, AppError> {
+    let mut trashes = select_target_trashes(all_trash, trash_dir)?;
+    if all_trash && trash_dir.is_none() {
+        let data_dirs = env::var_os(XDG_DATA_DIRS_ENV);
+        trashes.extend(
+            find_data_dir_trashes(data_dirs.as_deref(), get_local_trash_path().as_deref())
+                .into_iter()
+                .map(|trash_dir| TargetTrash::new(trash_dir, TrashType::DataDir)),
+        );
+    }
+    Ok(trashes)
+}
+
+/// Like `select_source_trashes`, but returns only the root paths.
+pub fn select_source_trash_dirs(all_trash: bool, trash_dir: Option<&Path>) -> Result<Vec<PathBuf>, AppError> {
+    Ok(select_source_trashes(all_trash, trash_dir)?
+        .into_iter()
+        .map(|trash| trash.root_path)
+        .collect())
+}
+
+/// Checks that `path` is an existing trash, with `files` and `info` subdirectories, and tells its
+/// type from where it is: the home trash, `.Trash/$uid` (shared) or `.Trash-$uid` (private).
+/// Nothing is created, so a typo never turns into a new trash directory.
+pub(crate) fn open_trash_dir(path: &Path) -> Result<TargetTrash, AppError> {
+    let not_a_trash = || AppError::NotATrashDirectory {
+        path: path.to_path_buf(),
+    };
+    let root_path = path.canonicalize().map_err(|_| not_a_trash())?;
+    if !root_path.join(TRASH_FILES_DIR_NAME).is_dir() || !root_path.join(TRASH_INFO_DIR_NAME).is_dir() {
+        return Err(not_a_trash());
+    }
+    let is_home_trash = get_local_trash_path()
+        .and_then(|home_trash| home_trash.canonicalize().ok())
+        .is_some_and(|home_trash| home_trash == root_path);
+    let name = root_path.file_name().map(|name| name.to_string_lossy().into_owned());
+    let parent_name = root_path
+        .parent()
+        .and_then(Path::file_name)
+        .map(|name| name.to_string_lossy().into_owned());
+    let trash_type = match (name.as_deref(), parent_name.as_deref()) {
+        _ if is_home_trash => TrashType::Home,
+        (Some(name), _) if name.starts_with(".Trash-") => TrashType::TopdirPrivate,
+        (_, Some(".Trash" | ".Trashes")) => TrashType::TopdirSharedUser,
+        _ => TrashType::Home,
     };
-    Ok(trash_dirs)
+    Ok(TargetTrash::new(root_path, trash_type))
 }
 
 /// Determines the correct trash directory for a given path.
@@ -147,19 +432,12 @@ pub fn get_target_trash_dirs(all_trash: bool) -> Result<Vec<PathBuf>, AppError>
 /// file is on the same filesystem as the user's home directory. If so, it returns
 /// the home trash. Otherwise, it returns a trash directory on the file's own
 /// filesystem (`$topdir/.Trash` or `$topdir/.Trash-$uid`).
-pub fn resolve_target_trash(path_to_trash: &Path, mounts: &[PathBuf]) -> Result<TargetTrash, AppError> {
+pub fn resolve_target_trash(path_to_trash: &Path, mounts: &Mounts) -> Result<TargetTrash, AppError> {
     let absolute_path = path_to_trash.canonicalize()?;
     let home_trash_path = get_local_trash_path().ok_or_else(|| AppError::Message("Home trash not found".into()))?;
 
-    let file_mount_point = mounts
-        .iter()
-        .filter(|m| absolute_path.starts_with(m))
-        .max_by_key(|m| m.as_os_str().len());
-
-    let home_mount_point = mounts
-        .iter()
-        .filter(|m| home_trash_path.starts_with(m))
-        .max_by_key(|m| m.as_os_str().len());
+    let file_mount_point = mounts.mount_point_for(&absolute_path);
+    let home_mount_point = mounts.mount_point_for(&home_trash_path);
 
     // If the file is on the same filesystem as the home directory, use the home trash.
     if file_mount_point.is_some() && file_mount_point == home_mount_point {
@@ -192,7 +470,21 @@ pub fn resolve_target_trash(path_to_trash: &Path, mounts: &[PathBuf]) -> Result<
 
         if is_valid_shared_trash {
             let user_trash_path = shared_trash_base.join(uid.to_string());
-            return Ok(TargetTrash::new(user_trash_path, TrashType::TopdirSharedUser));
+            // Anyone can create entries in the sticky `.Trash`, so another user may have planted
+            // our `$uid` directory (or a symlink to somewhere else) there. Only a missing one,
+            // which we create ourselves, or a real directory we own is safe to trash into.
+            match user_trash_path.symlink_metadata() {
+                Err(_) => return Ok(TargetTrash::new(user_trash_path, TrashType::TopdirSharedUser)),
+                Ok(_) if is_owned_user_trash_dir(&user_trash_path, uid) => {
+                    return Ok(TargetTrash::new(user_trash_path, TrashType::TopdirSharedUser));
+                }
+                Ok(_) => trace(|| {
+                    format!(
+                        "not using '{}': it is a symlink, not a directory or owned by another user",
+                        user_trash_path.display()
+                    )
+                }),
+            }
         }
 
         // Fallback to private trash `$topdir/.Trash-$uid`
@@ -209,23 +501,103 @@ pub fn resolve_target_trash(path_to_trash: &Path, mounts: &[PathBuf]) -> Result<
     )))
 }
 
+/// Returns true if `path` is a real directory (not a symlink to one) owned by `uid`,
+/// as a `$topdir/.Trash/$uid` directory must be before it is used.
+fn is_owned_user_trash_dir(path: &Path, uid: u32) -> bool {
+    path.symlink_metadata()
+        .map(|m| m.is_dir() && m.uid() == uid)
+        .unwrap_or(false)
+}
+
+/// Returns true if the given filesystem type (as in /proc/mounts) ignores case in file names.
+fn is_case_insensitive_fs_type(fs_type: &str) -> bool {
+    CASE_INSENSITIVE_FS_TYPES.contains(&fs_type.to_ascii_lowercase().as_str())
+}
+
+/// Undoes the octal escaping the kernel applies to /proc/mounts fields, where a space is
+/// written as `\040`, a tab as `\011`, a newline as `\012` and a backslash as `\134`.
+#[cfg(target_os = "linux")]
+fn unescape_mount_field(field: &str) -> PathBuf {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt;
+
+    let bytes = field.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes
+            .get(i + 1..i + 4)
+            .filter(|digits| bytes[i] == b'\\' && digits.iter().all(|digit| (b'0'..=b'7').contains(digit)));
+        match escape.and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok()) {
+            Some(byte) => {
+                unescaped.push(byte);
+                i += 4;
+            }
+            None => {
+                unescaped.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    PathBuf::from(OsString::from_vec(unescaped))
+}
+
 /// Finds trash directories on mounted drives by parsing /proc/mounts.
 /// This is a Linux-specific implementation.
+#[cfg(target_os = "linux")]
+pub(crate) fn find_trash_dirs_on_mounts(uid: u32, mounts_path: &Path) -> Vec<TargetTrash> {
+    // /proc/mounts may not exist
+    let mounts = Mounts::read_from(mounts_path).unwrap_or_default();
+    find_trash_dirs_at_mount_points(uid, mounts.mount_points())
+}
+
+/// Returns the mounted filesystems as reported by getmntinfo(3), the BSD counterpart of /proc/mounts.
+#[cfg(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+fn bsd_mounts() -> Mounts {
+    use std::ffi::CStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    #[cfg(target_os = "netbsd")]
+    type MountStat = libc::statvfs;
+    #[cfg(not(target_os = "netbsd"))]
+    type MountStat = libc::statfs;
+
+    let mut stats: *mut MountStat = std::ptr::null_mut();
+    // MNT_NOWAIT returns the cached information instead of querying every filesystem,
+    // so an unresponsive network mount cannot hang the listing.
+    // SAFETY: `stats` is only read after a successful call, which points it to `count` entries.
+    let count = unsafe { libc::getmntinfo(&mut stats, libc::MNT_NOWAIT) };
+    if count <= 0 || stats.is_null() {
+        return Mounts::default();
+    }
+    // SAFETY: the buffer belongs to libc and stays valid until the next call.
+    let stats = unsafe { std::slice::from_raw_parts(stats, count as usize) };
+    let mounts = stats
+        .iter()
+        .map(|stat| {
+            // SAFETY: both fields are NUL-terminated strings filled in by the kernel.
+            let (path, fs_type) = unsafe {
+                (
+                    CStr::from_ptr(stat.f_mntonname.as_ptr()),
+                    CStr::from_ptr(stat.f_fstypename.as_ptr()),
+                )
+            };
+            Mount {
+                path: PathBuf::from(OsStr::from_bytes(path.to_bytes())),
+                fs_type: Some(fs_type.to_string_lossy().into_owned()),
+            }
+        })
+        .collect();
+    Mounts { mounts }
+}
+
+/// Finds the trash directories of `uid` at the top of the given mount points.
 /// It checks for both shared (`$topdir/.Trash/$uid`) and private (`$topdir/.Trash-$uid`) trash directories
 /// as per the FreeDesktop.org specification.
-#[cfg(unix)]
-fn find_trash_dirs_on_mounts(uid: u32, mounts_path: &Path) -> Vec<PathBuf> {
-    let file = match File::open(mounts_path) {
-        Ok(f) => f,
-        Err(_) => return Vec::new(), // /proc/mounts may not exist
-    };
-
+fn find_trash_dirs_at_mount_points<'a>(uid: u32, mount_points: impl Iterator<Item = &'a Path>) -> Vec<TargetTrash> {
     let uid_str = uid.to_string();
 
-    BufReader::new(file)
-        .lines()
-        .filter_map(Result::ok)
-        .filter_map(|line| line.split_whitespace().nth(1).map(PathBuf::from)) // Get mount point
+    mount_points
         .filter_map(|mount_point| {
             // According to the spec, check for a shared trash directory first.
             // This is `$topdir/.Trash` with the sticky bit set.
@@ -234,8 +606,9 @@ fn find_trash_dirs_on_mounts(uid: u32, mounts_path: &Path) -> Vec<PathBuf> {
                 // Check if it's a directory and has the sticky bit (0o1000).
                 if metadata.is_dir() && (metadata.permissions().mode() & 0o1000 != 0) {
                     let user_shared_trash = shared_trash_base.join(&uid_str);
-                    if user_shared_trash.is_dir() {
-                        return Some(user_shared_trash); // Use `$topdir/.Trash/$uid`
+                    if is_owned_user_trash_dir(&user_shared_trash, uid) {
+                        // Use `$topdir/.Trash/$uid`
+                        return Some(TargetTrash::new(user_shared_trash, TrashType::TopdirSharedUser));
                     }
                 }
             }
@@ -244,7 +617,7 @@ fn find_trash_dirs_on_mounts(uid: u32, mounts_path: &Path) -> Vec<PathBuf> {
             // This is `$topdir/.Trash-$uid`.
             let private_trash = mount_point.join(format!(".Trash-{}", uid));
             if private_trash.is_dir() {
-                return Some(private_trash);
+                return Some(TargetTrash::new(private_trash, TrashType::TopdirPrivate));
             }
 
             None
@@ -259,10 +632,18 @@ fn find_trash_dirs_on_mounts(uid: u32, mounts_path: &Path) -> Vec<PathBuf> {
 /// 2. Falling back to the default `$HOME/.local/share` if `$XDG_DATA_HOME` is not set.
 ///
 /// This function is a thin wrapper around `get_local_trash_path_from` for production use.
-fn get_local_trash_path() -> Option<PathBuf> {
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn get_local_trash_path() -> Option<PathBuf> {
     get_local_trash_path_from(dirs::data_dir())
 }
 
+/// Returns the path to the user's trash directory on macOS, `$HOME/.Trash`, which is
+/// where Finder keeps items trashed from the boot volume.
+#[cfg(target_os = "macos")]
+pub(crate) fn get_local_trash_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".Trash"))
+}
+
 /// Helper function that constructs the trash path from a given data directory `Option`.
 /// This makes the logic testable by allowing injection of the data directory path.
 fn get_local_trash_path_from(data_dir: Option<PathBuf>) -> Option<PathBuf> {
@@ -272,31 +653,103 @@ fn get_local_trash_path_from(data_dir: Option<PathBuf>) -> Option<PathBuf> {
     })
 }
 
-fn find_all_trash_dirs() -> Result<Vec<PathBuf>, AppError> {
-    let mut trash_dirs = Vec::new();
+fn find_all_trashes() -> Vec<TargetTrash> {
+    let mut trashes = Vec::new();
 
-    match get_local_trash_path() {
-        Some(local_trash) => {
-            if local_trash.is_dir() {
-                trash_dirs.push(local_trash);
-            }
+    if let Some(local_trash) = get_local_trash_path() {
+        if local_trash.is_dir() {
+            trashes.push(TargetTrash::new(local_trash, TrashType::Home));
         }
-        None => {}
     }
 
-    #[cfg(unix)]
-    trash_dirs.extend(find_trash_dirs_on_mounts(
-        users::get_current_uid(),
-        Path::new(MOUNTS_FILE_PATH),
-    ));
+    trashes.extend(find_mounted_trashes(users::get_current_uid()));
 
-    Ok(trash_dirs)
+    trashes
+}
+
+/// Finds the `Trash` directories under the data directories listed in `data_dirs` (the value of
+/// `$XDG_DATA_DIRS`, or its default when unset or empty), so their items can be listed and restored.
+/// Nothing is ever trashed into them. Relative entries are ignored, as the XDG spec requires, and so
+/// are directories without a trash and any trash already found, including `home_trash`.
+fn find_data_dir_trashes(data_dirs: Option<&OsStr>, home_trash: Option<&Path>) -> Vec<PathBuf> {
+    let data_dirs = data_dirs
+        .filter(|value| !value.is_empty())
+        .unwrap_or(OsStr::new(DEFAULT_XDG_DATA_DIRS));
+    let mut seen: HashSet<PathBuf> = home_trash
+        .and_then(|path| path.canonicalize().ok())
+        .into_iter()
+        .collect();
+    env::split_paths(data_dirs)
+        .filter(|data_dir| data_dir.is_absolute())
+        .map(|data_dir| data_dir.join("Trash"))
+        .filter(|trash_dir| trash_dir.is_dir())
+        .filter(|trash_dir| trash_dir.canonicalize().is_ok_and(|canonical| seen.insert(canonical)))
+        .collect()
+}
+
+/// Finds the trash directories of `uid` on mounted filesystems other than the home one.
+/// On Linux these are read from /proc/mounts and on the BSDs from getmntinfo(3). On macOS they
+/// are Finder's trashes of the volumes under /Volumes plus the FreeDesktop topdir trashes of
+/// every mount, which is where `resolve_target_trash` puts items from other volumes.
+pub(crate) fn find_mounted_trashes(uid: u32) -> Vec<TargetTrash> {
+    #[cfg(target_os = "linux")]
+    return find_trash_dirs_on_mounts(uid, Path::new(MOUNTS_FILE_PATH));
+
+    #[cfg(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+    return find_trash_dirs_at_mount_points(uid, bsd_mounts().mount_points());
+
+    #[cfg(target_os = "macos")]
+    return find_volume_and_topdir_trashes(Path::new(VOLUMES_DIR_PATH), uid, &Mounts::read().unwrap_or_default());
+
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd",
+        target_os = "macos"
+    )))]
+    {
+        let _ = uid;
+        Vec::new()
+    }
+}
+
+/// Finds both Finder's per-user volume trashes under `volumes_dir` and the `$topdir/.Trash/$uid`
+/// and `$topdir/.Trash-$uid` trashes at the mount points in `mounts`, each only once.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn find_volume_and_topdir_trashes(volumes_dir: &Path, uid: u32, mounts: &Mounts) -> Vec<TargetTrash> {
+    let mut trashes = find_trash_dirs_in_volumes(volumes_dir, uid);
+    for trash in find_trash_dirs_at_mount_points(uid, mounts.mount_points()) {
+        if !trashes.contains(&trash) {
+            trashes.push(trash);
+        }
+    }
+    trashes
+}
+
+/// Finds the per-user trash directories of mounted volumes, `<volume>/.Trashes/$uid`, as
+/// used by Finder on macOS. `volumes_dir` is the directory holding the volumes, normally /Volumes.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn find_trash_dirs_in_volumes(volumes_dir: &Path, uid: u32) -> Vec<TargetTrash> {
+    let Ok(entries) = fs::read_dir(volumes_dir) else {
+        return Vec::new();
+    };
+    let mut trash_dirs: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path().join(".Trashes").join(uid.to_string()))
+        .filter(|trash_dir| trash_dir.is_dir())
+        .collect();
+    trash_dirs.sort();
+    trash_dirs
+        .into_iter()
+        .map(|trash_dir| TargetTrash::new(trash_dir, TrashType::TopdirSharedUser))
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
+    use std::fs::{self, File};
     use std::io::Write;
     use std::os::unix::fs::PermissionsExt;
     use tempfile::tempdir;
@@ -329,7 +782,89 @@ mod tests {
     }
 
     #[test]
-    #[cfg(unix)]
+    fn test_find_data_dir_trashes() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let root = root.path();
+        let home_trash = root.join("home/.local/share/Trash");
+        let local_trash = root.join("usr/local/share/Trash");
+        let system_trash = root.join("usr/share/Trash");
+        for trash_dir in [&home_trash, &local_trash, &system_trash] {
+            fs::create_dir_all(trash_dir.join(TRASH_FILES_DIR_NAME))?;
+            fs::create_dir_all(trash_dir.join(TRASH_INFO_DIR_NAME))?;
+        }
+        fs::create_dir_all(root.join("opt/share"))?;
+        fs::File::create(root.join("usr/local/share/Trash/files/notes.txt"))?;
+        fs::write(
+            root.join("usr/local/share/Trash/info/notes.txt.trashinfo"),
+            "[Trash Info]\nPath=/srv/notes.txt\nDeletionDate=2024-01-01T12:00:00\n",
+        )?;
+
+        let data_dirs = env::join_paths([
+            root.join("usr/local/share"),
+            root.join("home/.local/share"), // the home trash is already listed
+            root.join("missing/share"),
+            root.join("opt/share"), // no trash in it
+            PathBuf::from("relative/share"),
+            root.join("usr/share"),
+            root.join("usr/local/share/"), // the same directory again
+        ])
+        .unwrap();
+        let found = find_data_dir_trashes(Some(&data_dirs), Some(&home_trash));
+        assert_eq!(found, vec![local_trash, system_trash]);
+
+        let entries = crate::trash::restoring::find_trash_entries_in_dirs(&found);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].original_path, PathBuf::from("/srv/notes.txt"));
+
+        // An empty value means the same as an unset one: the spec's default directories.
+        assert_eq!(
+            find_data_dir_trashes(Some(OsStr::new("")), None),
+            find_data_dir_trashes(None, None)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_data_dir_trashes_are_only_searched() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let data_dir = root.path().join("usr/share");
+        let data_trash = data_dir.join("Trash");
+        fs::create_dir_all(data_trash.join(TRASH_FILES_DIR_NAME))?;
+        fs::create_dir_all(data_trash.join(TRASH_INFO_DIR_NAME))?;
+
+        let original_data_dirs = std::env::var_os(XDG_DATA_DIRS_ENV);
+        std::env::set_var(XDG_DATA_DIRS_ENV, &data_dir);
+        let targets = get_target_trashes(true)?;
+        let sources = select_source_trashes(true, None)?;
+        let chosen = select_source_trashes(true, Some(&data_trash))?;
+        match original_data_dirs {
+            Some(val) => std::env::set_var(XDG_DATA_DIRS_ENV, val),
+            None => std::env::remove_var(XDG_DATA_DIRS_ENV),
+        }
+
+        // Emptying, purging and checking never see it; listing and restoring do.
+        assert!(targets.iter().all(|trash| trash.root_path != data_trash));
+        assert!(sources.contains(&TargetTrash::new(data_trash.clone(), TrashType::DataDir)));
+        assert_eq!(chosen.len(), 1);
+        assert_eq!(TrashType::DataDir.label(), "data");
+        assert!(TargetTrash::new(root.path().join("new/Trash"), TrashType::DataDir)
+            .ensure_structure_exists()
+            .is_err());
+        assert!(!root.path().join("new").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_case_insensitive_fs_type() {
+        assert!(is_case_insensitive_fs_type("vfat"));
+        assert!(is_case_insensitive_fs_type("exfat"));
+        assert!(is_case_insensitive_fs_type("HFSPLUS"));
+        assert!(!is_case_insensitive_fs_type("ext4"));
+        assert!(!is_case_insensitive_fs_type("btrfs"));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
     fn test_find_trash_dirs_on_mounts() -> Result<(), AppError> {
         let uid = users::get_current_uid();
         let uid_str = uid.to_string();
@@ -369,23 +904,223 @@ mod tests {
         fs::create_dir(&mount4)?;
         writeln!(mounts_file, "none {} none 0 0", mount4.display())?;
 
+        // A sticky shared trash whose `$uid` entry is a symlink is not used.
+        let mount5 = root_dir.path().join("mount5");
+        fs::create_dir_all(mount5.join(".Trash"))?;
+        fs::set_permissions(mount5.join(".Trash"), fs::Permissions::from_mode(0o1777))?;
+        std::os::unix::fs::symlink(&shared_trash_user, mount5.join(".Trash").join(&uid_str))?;
+        writeln!(mounts_file, "none {} none 0 0", mount5.display())?;
+
         let found_dirs = find_trash_dirs_on_mounts(uid, &mounts_file_path);
 
         assert_eq!(found_dirs.len(), 3, "Should find three valid trash directories");
 
-        let expected_dirs: std::collections::HashSet<PathBuf> =
-            [shared_trash_user, private_trash, private_trash_fallback]
-                .iter()
-                .cloned()
-                .collect();
+        let expected_dirs: std::collections::HashSet<TargetTrash> = [
+            TargetTrash::new(shared_trash_user, TrashType::TopdirSharedUser),
+            TargetTrash::new(private_trash, TrashType::TopdirPrivate),
+            TargetTrash::new(private_trash_fallback, TrashType::TopdirPrivate),
+        ]
+        .into_iter()
+        .collect();
 
-        let found_dirs_set: std::collections::HashSet<PathBuf> = found_dirs.into_iter().collect();
+        let found_dirs_set: std::collections::HashSet<TargetTrash> = found_dirs.into_iter().collect();
 
         assert_eq!(found_dirs_set, expected_dirs);
 
         Ok(())
     }
 
+    #[test]
+    #[cfg(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+    fn test_bsd_mounts() {
+        let mounts = bsd_mounts();
+        let mount_points: Vec<&Path> = mounts.mount_points().collect();
+        assert!(mount_points.contains(&Path::new("/")), "{:?}", mount_points);
+        assert!(
+            mount_points.iter().all(|mount_point| mount_point.is_absolute()),
+            "{:?}",
+            mount_points
+        );
+        assert!(mounts.mounts.iter().all(|mount| mount.fs_type.is_some()));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_mounts_resolve_longest_mount_point() -> Result<(), AppError> {
+        let root_dir = tempdir()?;
+        let mounts_file_path = root_dir.path().join("test_mounts");
+        fs::write(
+            &mounts_file_path,
+            "# a comment\n\
+             /dev/sda1 / ext4 rw 0 0\n\
+             /dev/sda2 /home ext4 rw 0 0\n\
+             /dev/sdb1 /media/usb vfat rw 0 0\n\
+             /dev/sdc1 /media/usb\\040stick exfat rw 0 0\n\
+             proc /proc proc rw 0 0\n",
+        )?;
+        let mounts = Mounts::read_from(&mounts_file_path)?;
+        // Everything below is answered from the table read once above.
+        fs::remove_file(&mounts_file_path)?;
+
+        struct TestCase {
+            path: &'static str,
+            mount_point: Option<&'static str>,
+            case_insensitive: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                path: "/etc/hosts",
+                mount_point: Some("/"),
+                case_insensitive: false,
+            },
+            TestCase {
+                path: "/home/user/notes.txt",
+                mount_point: Some("/home"),
+                case_insensitive: false,
+            },
+            TestCase {
+                // A prefix of the name is not a parent: `/homework` is on `/`.
+                path: "/homework",
+                mount_point: Some("/"),
+                case_insensitive: false,
+            },
+            TestCase {
+                path: "/media/usb/.Trash-1000/files",
+                mount_point: Some("/media/usb"),
+                case_insensitive: true,
+            },
+            TestCase {
+                path: "/media/usb stick/photo.jpg",
+                mount_point: Some("/media/usb stick"),
+                case_insensitive: true,
+            },
+            TestCase {
+                path: "relative/path",
+                mount_point: None,
+                case_insensitive: false,
+            },
+        ];
+
+        for case in test_cases {
+            let path = Path::new(case.path);
+            assert_eq!(
+                mounts.mount_point_for(path),
+                case.mount_point.map(Path::new),
+                "{}",
+                case.path
+            );
+            assert_eq!(mounts.is_case_insensitive(path), case.case_insensitive, "{}", case.path);
+        }
+        assert!(Mounts::read_from(&mounts_file_path).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_trash_dirs_at_mount_points() -> Result<(), AppError> {
+        let uid = users::get_current_uid();
+        let root_dir = tempdir()?;
+        let with_trash = root_dir.path().join("usb");
+        let private_trash = with_trash.join(format!(".Trash-{}", uid));
+        fs::create_dir_all(&private_trash)?;
+        let without_trash = root_dir.path().join("nfs");
+        fs::create_dir_all(&without_trash)?;
+
+        let gone = root_dir.path().join("gone");
+        let found = find_trash_dirs_at_mount_points(
+            uid,
+            [&without_trash, &with_trash, &gone].into_iter().map(PathBuf::as_path),
+        );
+
+        assert_eq!(found, vec![TargetTrash::new(private_trash, TrashType::TopdirPrivate)]);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_find_trash_dirs_on_mounts_with_escaped_spaces() -> Result<(), AppError> {
+        let uid = users::get_current_uid();
+        let root_dir = tempdir()?;
+        let mount = root_dir.path().join("My Drive\tbackup");
+        let private_trash = mount.join(format!(".Trash-{}", uid));
+        fs::create_dir_all(&private_trash)?;
+
+        let escaped = mount.display().to_string().replace(' ', "\\040").replace('\t', "\\011");
+        let mounts_file_path = root_dir.path().join("test_mounts");
+        fs::write(&mounts_file_path, format!("/dev/sdb1 {} vfat rw 0 0\n", escaped))?;
+
+        assert_eq!(
+            find_trash_dirs_on_mounts(uid, &mounts_file_path),
+            vec![TargetTrash::new(private_trash, TrashType::TopdirPrivate)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_unescape_mount_field() {
+        assert_eq!(
+            unescape_mount_field("/media/My\\040Drive"),
+            PathBuf::from("/media/My Drive")
+        );
+        assert_eq!(
+            unescape_mount_field("/mnt/a\\134b\\011c"),
+            PathBuf::from("/mnt/a\\b\tc")
+        );
+        // Anything that is not a three-digit octal escape is kept as is.
+        assert_eq!(unescape_mount_field("/mnt/a\\09x\\"), PathBuf::from("/mnt/a\\09x\\"));
+    }
+
+    #[test]
+    fn test_find_trash_dirs_in_volumes() -> Result<(), AppError> {
+        let volumes_dir = tempdir()?;
+        let usb_trash = volumes_dir.path().join("USB Stick/.Trashes/501");
+        let backup_trash = volumes_dir.path().join("Backup/.Trashes/501");
+        fs::create_dir_all(&usb_trash)?;
+        fs::create_dir_all(&backup_trash)?;
+        // Another user's trash and a volume without any are ignored.
+        fs::create_dir_all(volumes_dir.path().join("Shared/.Trashes/502"))?;
+        fs::create_dir_all(volumes_dir.path().join("Empty"))?;
+
+        assert_eq!(
+            find_trash_dirs_in_volumes(volumes_dir.path(), 501),
+            vec![
+                TargetTrash::new(backup_trash, TrashType::TopdirSharedUser),
+                TargetTrash::new(usb_trash, TrashType::TopdirSharedUser)
+            ]
+        );
+        assert!(find_trash_dirs_in_volumes(&volumes_dir.path().join("missing"), 501).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_volume_and_topdir_trashes() -> Result<(), AppError> {
+        let volumes_dir = tempdir()?;
+        let uid = users::get_current_uid();
+        let usb = volumes_dir.path().join("USB Stick");
+        let finder_trash = usb.join(".Trashes").join(uid.to_string());
+        let private_trash = usb.join(format!(".Trash-{}", uid));
+        fs::create_dir_all(&finder_trash)?;
+        fs::create_dir_all(&private_trash)?;
+        let mounts = Mounts::from_paths(vec![PathBuf::from("/"), usb.clone()]);
+
+        // Items trashed from the volume by this tool land in `.Trash-$uid`, so it must be found too.
+        assert_eq!(
+            find_volume_and_topdir_trashes(volumes_dir.path(), uid, &mounts),
+            vec![
+                TargetTrash::new(finder_trash, TrashType::TopdirSharedUser),
+                TargetTrash::new(private_trash, TrashType::TopdirPrivate)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn test_get_local_trash_path_on_macos() {
+        assert_eq!(get_local_trash_path(), dirs::home_dir().map(|home| home.join(".Trash")));
+    }
+
     #[test]
     fn test_resolve_target_trash_for_home_file_uses_home_trash() -> Result<(), AppError> {
         let root = tempdir()?;
@@ -402,7 +1137,7 @@ mod tests {
         let original_data_dir = std::env::var("XDG_DATA_HOME");
         std::env::set_var("XDG_DATA_HOME", home.join(".local/share"));
 
-        let mounts = vec![PathBuf::from("/")];
+        let mounts = Mounts::from_paths(vec![PathBuf::from("/")]);
         let target_trash = resolve_target_trash(&file_in_home, &mounts)?;
 
         assert_eq!(target_trash.root_path, home_trash_path);
@@ -434,7 +1169,7 @@ mod tests {
         let original_data_dir = std::env::var("XDG_DATA_HOME");
         std::env::set_var("XDG_DATA_HOME", home.join(".local/share"));
 
-        let mounts = vec![PathBuf::from("/"), usb.clone()];
+        let mounts = Mounts::from_paths(vec![PathBuf::from("/"), usb.clone()]);
 
         // --- Case 1: No shared or private trash exists, should create private ---
         let target_trash = resolve_target_trash(&file_on_usb, &mounts)?;
@@ -450,6 +1185,20 @@ mod tests {
         assert_eq!(target_trash_shared.trash_type, TrashType::TopdirSharedUser);
         assert_eq!(target_trash_shared.root_path, shared_trash_base.join(uid.to_string()));
 
+        // --- Case 2b: `$uid` in the shared trash is a symlink planted elsewhere, should fall back to private ---
+        let planted = root.path().join("planted");
+        fs::create_dir(&planted)?;
+        std::os::unix::fs::symlink(&planted, shared_trash_base.join(uid.to_string()))?;
+        let target_trash_symlinked = resolve_target_trash(&file_on_usb, &mounts)?;
+        assert_eq!(target_trash_symlinked.trash_type, TrashType::TopdirPrivate);
+        fs::remove_file(shared_trash_base.join(uid.to_string()))?;
+
+        // --- Case 2c: `$uid` is our own directory, should use it ---
+        fs::create_dir(shared_trash_base.join(uid.to_string()))?;
+        let target_trash_existing = resolve_target_trash(&file_on_usb, &mounts)?;
+        assert_eq!(target_trash_existing.trash_type, TrashType::TopdirSharedUser);
+        fs::remove_dir(shared_trash_base.join(uid.to_string()))?;
+
         // --- Case 3: Shared trash exists but is invalid (no sticky bit), should fall back to private ---
         fs::set_permissions(&shared_trash_base, fs::Permissions::from_mode(0o755))?;
         let target_trash_fallback = resolve_target_trash(&file_on_usb, &mounts)?;
@@ -471,6 +1220,56 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_open_trash_dir() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let private_trash = root.path().join("usb/.Trash-1000");
+        let shared_trash = root.path().join("disk/.Trash/1000");
+        for trash in [&private_trash, &shared_trash] {
+            fs::create_dir_all(trash.join(TRASH_FILES_DIR_NAME))?;
+            fs::create_dir_all(trash.join(TRASH_INFO_DIR_NAME))?;
+        }
+        let half_trash = root.path().join("half");
+        fs::create_dir_all(half_trash.join(TRASH_FILES_DIR_NAME))?;
+
+        let opened = open_trash_dir(&private_trash)?;
+        assert_eq!(opened.root_path(), private_trash.canonicalize()?);
+        assert_eq!(opened.trash_type(), TrashType::TopdirPrivate);
+        assert_eq!(open_trash_dir(&shared_trash)?.trash_type(), TrashType::TopdirSharedUser);
+        assert!(matches!(
+            open_trash_dir(&half_trash),
+            Err(AppError::NotATrashDirectory { .. })
+        ));
+        assert!(matches!(
+            open_trash_dir(&root.path().join("missing")),
+            Err(AppError::NotATrashDirectory { .. })
+        ));
+        assert!(!root.path().join("missing").exists(), "nothing is created");
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_owned_user_trash_dir() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let uid = users::get_current_uid();
+        let user_dir = root.path().join(uid.to_string());
+        fs::create_dir(&user_dir)?;
+        let link = root.path().join("link");
+        std::os::unix::fs::symlink(&user_dir, &link)?;
+        let file = root.path().join("file");
+        File::create(&file)?;
+
+        assert!(is_owned_user_trash_dir(&user_dir, uid));
+        assert!(
+            !is_owned_user_trash_dir(&user_dir, uid.wrapping_add(1)),
+            "a directory owned by someone else"
+        );
+        assert!(!is_owned_user_trash_dir(&link, uid), "a symlink to our own directory");
+        assert!(!is_owned_user_trash_dir(&file, uid), "a regular file");
+        assert!(!is_owned_user_trash_dir(&root.path().join("missing"), uid));
+        Ok(())
+    }
+
     #[test]
     fn test_resolve_target_trash_symlink_check() -> Result<(), AppError> {
         let root = tempdir()?;
@@ -490,7 +1289,7 @@ mod tests {
 
         let file_in_home = home.join("file.txt");
         File::create(&file_in_home)?;
-        let mounts = vec![PathBuf::from("/")];
+        let mounts = Mounts::from_paths(vec![PathBuf::from("/")]);
 
         #[cfg(unix)]
         {
@@ -517,7 +1316,7 @@ mod tests {
         File::create(&file)?;
 
         // Provide an empty list of mounts, so none will be found for the file.
-        let mounts = vec![];
+        let mounts = Mounts::from_paths(vec![]);
         let result = resolve_target_trash(&file, &mounts);
 
         assert!(
@@ -528,6 +1327,46 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_check_writable_filesystem() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let trash_root = root.path().join("missing/.Trash-1000");
+        let read_only = |path: &Path| -> std::io::Result<bool> { Ok(path == root.path()) };
+
+        // The trash does not exist yet, so its nearest existing ancestor is probed.
+        let result = check_writable_filesystem(&trash_root, read_only);
+        assert!(matches!(result, Err(AppError::ReadOnlyFilesystem { path }) if path == trash_root));
+
+        check_writable_filesystem(&trash_root, |_| Ok(false))?;
+        // A probe that cannot tell does not block trashing.
+        check_writable_filesystem(&trash_root, |_| Err(std::io::Error::other("unsupported")))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_read_only_fs() -> Result<(), AppError> {
+        let root = tempdir()?;
+        assert!(!is_read_only_fs(root.path())?);
+        assert!(is_read_only_fs(&root.path().join("missing")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ensure_structure_exists_in_no_create_mode() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let trash_path = root.path().join("TestTrash");
+        let home_trash = TargetTrash::new(trash_path.clone(), TrashType::Home);
+
+        let result = home_trash.ensure_structure(true);
+        assert!(result.is_err(), "Creating a missing trash must be refused");
+        assert!(!trash_path.exists());
+
+        // An already complete structure needs no creation, so it is accepted.
+        home_trash.ensure_structure(false)?;
+        assert!(home_trash.ensure_structure(true).is_ok());
+        Ok(())
+    }
+
     #[test]
     fn test_ensure_structure_exists() -> Result<(), AppError> {
         let root = tempdir()?;
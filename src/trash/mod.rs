@@ -1,18 +1,58 @@
 mod color;
+mod confirm;
+mod copy;
+mod directory_sizes;
 mod file_type;
+mod file_type_config;
+mod filter;
+mod git;
+mod home_path;
+mod info_file;
+mod pager;
+mod porcelain;
+mod preview;
+mod quota;
+mod size;
 mod spec;
+mod terminal;
 mod url_escape;
+mod verbose;
+mod walk;
 
+pub mod check;
+pub mod doctor;
 pub mod emptying;
 pub mod error;
+pub mod grace;
+pub mod lint;
 pub mod listing;
 pub mod locations;
 pub mod restoring;
+pub mod stats;
 pub mod trashing;
 
-pub use color::apply_color_setting;
-pub use emptying::{handle_empty_trash, EmptyTrashOptions};
+pub use check::{handle_check, CheckOptions};
+pub use color::{apply_color_setting, apply_ls_colors_from_env, apply_theme_from_env};
+pub use doctor::handle_doctor;
+pub use emptying::{handle_empty_trash, handle_purge_paths, purge_entry, EmptyTrashOptions, PurgeOptions};
 pub use error::AppError;
-pub use listing::handle_display_trash;
-pub use restoring::handle_interactive_restore;
-pub use trashing::handle_move_to_trash;
+pub use file_type::{set_file_type_classifier, FileTypeClassifier};
+pub use file_type_config::load_file_type_config;
+pub use filter::{parse_age, parse_since, parse_until, EntryFilter};
+pub use grace::{handle_cancel_empty, handle_commit_empty};
+pub use home_path::set_relative_paths;
+pub use lint::handle_trashinfo_lint;
+pub use listing::{handle_display_trash, DisplayTrashOptions, ListOrder, SortKey};
+pub use locations::set_no_create;
+pub use pager::Pager;
+pub use preview::handle_preview;
+pub use restoring::{
+    handle_interactive_restore, handle_put_back, handle_restore_glob, handle_restore_latest, handle_restore_names,
+    handle_restore_paths, RestoreOptions,
+};
+pub use size::parse_size;
+pub use stats::{handle_count, handle_disk_usage, handle_stats, DiskUsageOptions, StatsOptions};
+pub use terminal::resolve_tty;
+pub use trashing::{handle_move_to_trash, read_file_args, TrashOptions, DEFAULT_MAX_COLLISIONS};
+pub use url_escape::PathEncoding;
+pub use verbose::set_verbose;
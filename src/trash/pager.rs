@@ -0,0 +1,182 @@
+use std::env;
+use std::io::{self, ErrorKind, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+use crate::trash::error::AppError;
+
+/// The environment variable naming the pager, as for `git` and `man`.
+const PAGER_ENV: &str = "PAGER";
+
+/// The pager used when `$PAGER` is unset. `-R` lets the colors through.
+const DEFAULT_PAGER: &str = "less -R";
+
+/// The `less` options set when `$LESS` is unset: quit at once if the listing fits on
+/// one screen (`F`), keep colors (`R`) and do not clear the screen on exit (`X`).
+const DEFAULT_LESS: &str = "FRX";
+
+/// A running pager; what is written to it is shown through the pager instead of on stdout.
+/// Dropping it closes the pager's input and waits for the user to quit it.
+pub struct Pager {
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
+
+impl Pager {
+    /// Starts the pager named by `$PAGER` (or `less -R`), writing to the inherited stdout.
+    /// Returns `None` when `$PAGER` is set to an empty string, which disables paging.
+    pub fn from_env() -> Result<Option<Pager>, AppError> {
+        match pager_command(env::var(PAGER_ENV).ok().as_deref())? {
+            Some(command) => Pager::spawn(&command, Stdio::inherit()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn spawn(command: &[String], stdout: Stdio) -> Result<Pager, AppError> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| AppError::Message("The pager command is empty".to_string()))?;
+        let mut process = Command::new(program);
+        process.args(args).stdin(Stdio::piped()).stdout(stdout);
+        if env::var_os("LESS").is_none() {
+            process.env("LESS", DEFAULT_LESS);
+        }
+        let mut child = process
+            .spawn()
+            .map_err(|e| AppError::Message(format!("Failed to start the pager '{}': {}", program, e)))?;
+        let stdin = child.stdin.take();
+        Ok(Pager { child, stdin })
+    }
+
+    /// Runs `write` on the pager's input. Once the user has quit the pager, the rest of the
+    /// output is discarded instead of failing the listing with a broken pipe.
+    fn forward<T>(&mut self, done: T, write: impl FnOnce(&mut ChildStdin) -> io::Result<T>) -> io::Result<T> {
+        let Some(stdin) = self.stdin.as_mut() else {
+            return Ok(done);
+        };
+        match write(stdin) {
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => {
+                self.stdin = None;
+                Ok(done)
+            }
+            result => result,
+        }
+    }
+}
+
+impl Write for Pager {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.forward(buf.len(), |stdin| stdin.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.forward((), |stdin| stdin.flush())
+    }
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        // Closing its input lets the pager see the end of the listing.
+        drop(self.stdin.take());
+        let _ = self.child.wait();
+    }
+}
+
+/// Splits the pager command from the value of `$PAGER`: the default when it is unset,
+/// and no pager at all when it is empty.
+fn pager_command(env_value: Option<&str>) -> Result<Option<Vec<String>>, AppError> {
+    let value = env_value.unwrap_or(DEFAULT_PAGER);
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+    shlex::split(value)
+        .map(Some)
+        .ok_or_else(|| AppError::Message(format!("Invalid {} value: '{}'", PAGER_ENV, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trash::listing::{handle_display_trash, DisplayTrashOptions};
+    use crate::trash::spec::{TRASH_FILES_DIR_NAME, TRASH_INFO_DIR_NAME};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_pager_command() {
+        struct TestCase {
+            env_value: Option<&'static str>,
+            expected: Option<Vec<&'static str>>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                env_value: None,
+                expected: Some(vec!["less", "-R"]),
+            },
+            TestCase {
+                env_value: Some("more"),
+                expected: Some(vec!["more"]),
+            },
+            TestCase {
+                env_value: Some("bat --paging 'always'"),
+                expected: Some(vec!["bat", "--paging", "always"]),
+            },
+            TestCase {
+                env_value: Some("  "),
+                expected: None,
+            },
+        ];
+
+        for case in test_cases {
+            let expected = case
+                .expected
+                .map(|words| words.into_iter().map(String::from).collect::<Vec<_>>());
+            assert_eq!(pager_command(case.env_value).unwrap(), expected, "{:?}", case.env_value);
+        }
+        assert!(pager_command(Some("less 'unterminated")).is_err());
+    }
+
+    #[test]
+    fn test_listing_through_pager() -> Result<(), AppError> {
+        let trash_dir = tempdir()?;
+        let files_dir = trash_dir.path().join(TRASH_FILES_DIR_NAME);
+        fs::create_dir_all(&files_dir)?;
+        fs::create_dir_all(trash_dir.path().join(TRASH_INFO_DIR_NAME))?;
+        fs::write(files_dir.join("notes.txt"), "notes")?;
+        let paged = tempdir()?;
+        let paged_path = paged.path().join("paged.txt");
+
+        // A pager-like sink: it reads the listing from its stdin and saves it.
+        let command = [
+            "sh".to_string(),
+            "-c".to_string(),
+            "cat > \"$0\"".to_string(),
+            paged_path.display().to_string(),
+        ];
+        let mut pager = Pager::spawn(&command, Stdio::null())?;
+        handle_display_trash(
+            &mut pager,
+            DisplayTrashOptions {
+                trash_dir: Some(trash_dir.path().to_path_buf()),
+                ..Default::default()
+            },
+        )?;
+        drop(pager);
+
+        let paged = fs::read_to_string(&paged_path)?;
+        assert!(paged.contains("notes.txt"), "{}", paged);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pager_quit_early_discards_the_rest() -> Result<(), AppError> {
+        // The pager exits without reading, as when the user quits `less` right away.
+        let mut pager = Pager::spawn(&["true".to_string()], Stdio::null())?;
+        pager.child.wait()?;
+        for _ in 0..1000 {
+            pager.write_all(&[b'x'; 1024])?;
+        }
+        pager.flush()?;
+        Ok(())
+    }
+}
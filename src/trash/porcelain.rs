@@ -0,0 +1,46 @@
+use std::io::{self, Write};
+
+/// The last field of every record written under `--dry-run`, so a preview is never mistaken for
+/// changes that were made.
+pub const DRY_RUN_FIELD: &str = "dry-run";
+
+/// Escapes a porcelain field so that it cannot break the record structure.
+/// Backslashes, tabs and newlines are written as `\\`, `\t` and `\n` respectively.
+pub fn escape_field(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes a single porcelain record: escaped fields separated by tabs and terminated by a newline.
+pub fn write_record<W: Write>(writer: &mut W, fields: &[&str]) -> io::Result<()> {
+    let escaped: Vec<String> = fields.iter().map(|f| escape_field(f)).collect();
+    writeln!(writer, "{}", escaped.join("\t"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_field() {
+        assert_eq!(escape_field("plain.txt"), "plain.txt");
+        assert_eq!(escape_field("a\tb"), "a\\tb");
+        assert_eq!(escape_field("line\nbreak"), "line\\nbreak");
+        assert_eq!(escape_field(r"back\slash"), r"back\\slash");
+    }
+
+    #[test]
+    fn test_write_record() {
+        let mut output = Vec::new();
+        write_record(&mut output, &["/trash/files", "my\tfile", "42"]).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "/trash/files\tmy\\tfile\t42\n");
+    }
+}
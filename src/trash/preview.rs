@@ -0,0 +1,353 @@
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::Read;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use humansize::{format_size, BINARY};
+
+use crate::trash::color::format_mode;
+use crate::trash::error::AppError;
+use crate::trash::home_path::display_path;
+use crate::trash::info_file::{info_path_for, read_trash_info};
+use crate::trash::restoring::TrashEntry;
+use crate::trash::size::tree_summary_reporting;
+use crate::trash::url_escape::trash_spec_url_decode;
+
+/// How many lines of a file, or entries of a directory, the preview shows.
+const PREVIEW_LINES: usize = 20;
+
+/// How many bytes of a file are read for its preview.
+const PREVIEW_BYTES: u64 = 8 * 1024;
+
+/// How many items of a directory are counted at most for its size.
+const PREVIEW_MAX_ITEMS: u64 = 100_000;
+
+/// Whether the restore UI shows `render_preview` for the highlighted entry. It is only
+/// enabled when the user has not configured a `--preview` command of their own.
+static BUILTIN_PREVIEW: AtomicBool = AtomicBool::new(false);
+
+/// Makes the restore UI preview entries with `render_preview` for the rest of the run.
+pub(crate) fn enable_builtin_preview() {
+    BUILTIN_PREVIEW.store(true, Ordering::Relaxed);
+}
+
+/// Returns whether the restore UI previews entries with `render_preview`.
+pub(crate) fn is_builtin_preview_enabled() -> bool {
+    BUILTIN_PREVIEW.load(Ordering::Relaxed)
+}
+
+/// Prints the preview of the item at `trashed_path` in a trash `files` directory, as the restore UI shows it.
+pub fn handle_preview(trashed_path: &Path) -> Result<(), AppError> {
+    let entry = entry_for_trashed_path(trashed_path)?;
+    print!("{}", render_preview(&entry));
+    Ok(())
+}
+
+/// Builds the entry for `trashed_path` from its info file. Without a readable info file,
+/// the original path and deletion date are left empty rather than failing the preview.
+fn entry_for_trashed_path(trashed_path: &Path) -> Result<TrashEntry, AppError> {
+    if let Err(source) = trashed_path.symlink_metadata() {
+        return Err(AppError::Io {
+            path: trashed_path.to_path_buf(),
+            source,
+        });
+    }
+    let info_path = info_path_for(trashed_path).unwrap_or_default();
+    let info = read_trash_info(&info_path).unwrap_or_default();
+    Ok(TrashEntry {
+        trashed_path: trashed_path.to_path_buf(),
+        original_path: info
+            .path
+            .map(|path| PathBuf::from(trash_spec_url_decode(&path)))
+            .unwrap_or_default(),
+        info_path,
+        deletion_date: info.deletion_date.unwrap_or_default(),
+        mode: info.mode,
+        parent_mode: info.parent_mode,
+    })
+}
+
+/// Renders what the restore UI shows for `entry`: where it came from, when it was deleted,
+/// its type, size and mode, and then the start of its contents or its directory listing.
+pub(crate) fn render_preview(entry: &TrashEntry) -> String {
+    let mut text = String::new();
+    let _ = writeln!(
+        text,
+        "Original: {}",
+        strip_controls(&display_path(&entry.original_path))
+    );
+    let _ = writeln!(text, "Deleted:  {}", entry.deletion_date);
+
+    let metadata = match entry.trashed_path.symlink_metadata() {
+        Ok(metadata) => metadata,
+        Err(_) => {
+            let _ = writeln!(text, "\n(the item is missing from the trash)");
+            return text;
+        }
+    };
+    let mode = metadata.permissions().mode() & 0o7777;
+    if metadata.file_type().is_symlink() {
+        let target = fs::read_link(&entry.trashed_path).unwrap_or_default();
+        let _ = writeln!(
+            text,
+            "Type:     symbolic link -> {}",
+            strip_controls(&target.to_string_lossy())
+        );
+        return text;
+    }
+    if metadata.is_dir() {
+        // The preview is drawn over the UI, so what could not be read is shown in it, not on stderr.
+        let mut skipped = Vec::new();
+        let summary = tree_summary_reporting(&entry.trashed_path, PREVIEW_MAX_ITEMS, |e| skipped.push(e));
+        let plus = if summary.truncated { "+" } else { "" };
+        let noun = if summary.item_count == 1 { "item" } else { "items" };
+        let _ = writeln!(text, "Type:     directory");
+        let _ = writeln!(
+            text,
+            "Size:     {}{} ({}{} {})",
+            format_size(summary.total_size, BINARY),
+            plus,
+            summary.item_count,
+            plus,
+            noun
+        );
+        let _ = writeln!(text, "Mode:     {} ({:04o})", format_mode(mode, true), mode);
+        for e in &skipped {
+            let _ = writeln!(text, "Skipped:  {}", strip_controls(&e.to_string()));
+        }
+        text.push('\n');
+        text.push_str(&preview_directory(&entry.trashed_path));
+    } else {
+        let _ = writeln!(text, "Type:     file");
+        let _ = writeln!(text, "Size:     {}", format_size(metadata.len(), BINARY));
+        let _ = writeln!(text, "Mode:     {} ({:04o})", format_mode(mode, false), mode);
+        text.push('\n');
+        text.push_str(&preview_file(&entry.trashed_path));
+    }
+    text
+}
+
+/// Lists the first `PREVIEW_LINES` entries of `dir` by name, marking directories with a trailing `/`.
+fn preview_directory(dir: &Path) -> String {
+    let mut names: Vec<String> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| {
+                let name = strip_controls(&entry.file_name().to_string_lossy());
+                match entry.file_type() {
+                    Ok(file_type) if file_type.is_dir() => format!("{}/", name),
+                    _ => name,
+                }
+            })
+            .collect(),
+        Err(e) => return format!("(cannot list the directory: {})\n", e),
+    };
+    if names.is_empty() {
+        return "(empty directory)\n".to_string();
+    }
+    names.sort();
+    let mut text: String = names
+        .iter()
+        .take(PREVIEW_LINES)
+        .map(|name| format!("{}\n", name))
+        .collect();
+    if names.len() > PREVIEW_LINES {
+        let _ = writeln!(text, "... and {} more", names.len() - PREVIEW_LINES);
+    }
+    text
+}
+
+/// Returns the first `PREVIEW_LINES` lines of the file at `path`, like `head`, or a note
+/// instead when it is empty or looks binary (contains a NUL byte).
+fn preview_file(path: &Path) -> String {
+    let mut bytes = Vec::new();
+    let read = File::open(path).and_then(|file| file.take(PREVIEW_BYTES).read_to_end(&mut bytes));
+    match read {
+        Ok(0) => "(empty file)\n".to_string(),
+        Ok(_) if bytes.contains(&0) => "(binary file)\n".to_string(),
+        Ok(_) => String::from_utf8_lossy(&bytes)
+            .lines()
+            .take(PREVIEW_LINES)
+            .map(|line| format!("{}\n", strip_controls(line)))
+            .collect(),
+        Err(e) => format!("(cannot read the file: {})\n", e),
+    }
+}
+
+/// Replaces control characters other than tabs with `?`, as `ls` does, so the contents and names
+/// of trashed files cannot move the cursor or recolor the restore UI with escape sequences.
+fn strip_controls(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_control() && c != '\t' { '?' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trash::spec::{TRASH_FILES_DIR_NAME, TRASH_INFO_DIR_NAME, TRASH_INFO_SUFFIX};
+    use tempfile::tempdir;
+
+    fn entry(trashed_path: PathBuf) -> TrashEntry {
+        TrashEntry {
+            trashed_path,
+            info_path: PathBuf::new(),
+            original_path: PathBuf::from("/home/user/item"),
+            deletion_date: "2024-01-01T12:00:00".to_string(),
+            mode: None,
+            parent_mode: None,
+        }
+    }
+
+    #[test]
+    fn test_render_preview() -> Result<(), AppError> {
+        let trash = tempdir()?;
+        let text_file = trash.path().join("notes.txt");
+        let lines: Vec<String> = (1..=PREVIEW_LINES + 5).map(|n| format!("line {}", n)).collect();
+        fs::write(&text_file, lines.join("\n"))?;
+        fs::set_permissions(&text_file, fs::Permissions::from_mode(0o640))?;
+        let binary_file = trash.path().join("image.bin");
+        fs::write(&binary_file, b"\x89PNG\0\0\0")?;
+        let empty_file = trash.path().join("empty");
+        File::create(&empty_file)?;
+        let dir = trash.path().join("photos");
+        fs::create_dir_all(dir.join("2024"))?;
+        fs::write(dir.join("a.jpg"), "12345")?;
+
+        struct TestCase {
+            path: PathBuf,
+            expected_lines: Vec<String>,
+            unexpected: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                path: text_file.clone(),
+                expected_lines: [
+                    "Original: /home/user/item",
+                    "Deleted:  2024-01-01T12:00:00",
+                    "Type:     file",
+                    "Size:     ",
+                ]
+                .into_iter()
+                .map(String::from)
+                .chain(lines[..PREVIEW_LINES].iter().cloned())
+                .collect(),
+                unexpected: "line 21",
+            },
+            TestCase {
+                path: binary_file,
+                expected_lines: vec!["Size:     7 B".to_string(), "(binary file)".to_string()],
+                unexpected: "PNG",
+            },
+            TestCase {
+                path: empty_file,
+                expected_lines: vec!["Size:     0 B".to_string(), "(empty file)".to_string()],
+                unexpected: "binary",
+            },
+            TestCase {
+                path: dir,
+                expected_lines: vec![
+                    "Type:     directory".to_string(),
+                    "2024/".to_string(),
+                    "a.jpg".to_string(),
+                ],
+                unexpected: "empty",
+            },
+            TestCase {
+                path: trash.path().join("gone.txt"),
+                expected_lines: vec!["(the item is missing from the trash)".to_string()],
+                unexpected: "Size:",
+            },
+        ];
+
+        for case in test_cases {
+            let preview = render_preview(&entry(case.path.clone()));
+            let mut rest = preview.as_str();
+            for expected in &case.expected_lines {
+                let position = rest.find(expected.as_str()).unwrap_or_else(|| {
+                    panic!(
+                        "{}: missing {:?} in order in:\n{}",
+                        case.path.display(),
+                        expected,
+                        preview
+                    )
+                });
+                rest = &rest[position + expected.len()..];
+            }
+            assert!(
+                !preview.contains(case.unexpected),
+                "{}: unexpected {:?} in:\n{}",
+                case.path.display(),
+                case.unexpected,
+                preview
+            );
+        }
+
+        let preview = render_preview(&entry(text_file));
+        assert!(preview.contains("(0640)"), "{}", preview);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_preview_neutralizes_escape_sequences() -> Result<(), AppError> {
+        let trash = tempdir()?;
+        let file = trash.path().join("colors.txt");
+        fs::write(&file, "\x1b[2J\x1b[31mred\x1b[0m\r\tdone\n")?;
+        let dir = trash.path().join("dir");
+        fs::create_dir_all(dir.join("locked"))?;
+        File::create(dir.join("\x1b]0;title\x07"))?;
+        fs::set_permissions(dir.join("locked"), fs::Permissions::from_mode(0o000))?;
+
+        let file_preview = render_preview(&entry(file));
+        let dir_preview = render_preview(&entry(dir.clone()));
+        fs::set_permissions(dir.join("locked"), fs::Permissions::from_mode(0o700))?;
+
+        assert!(file_preview.contains("?[2J?[31mred?[0m?\tdone\n"), "{:?}", file_preview);
+        assert!(dir_preview.contains("?]0;title?\n"), "{:?}", dir_preview);
+        // Only the mode column may color the preview.
+        let body = |preview: &str| preview.split_once("\n\n").map(|(_, body)| body.to_string());
+        assert!(!body(&file_preview).unwrap_or_default().contains('\x1b'));
+        assert!(!body(&dir_preview).unwrap_or_default().contains('\x1b'));
+        // Root can read the locked directory anyway; anyone else gets a note inside the preview.
+        if users::get_current_uid() != 0 {
+            assert!(dir_preview.contains("Skipped:  "), "{}", dir_preview);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_preview_directory_truncates_long_listings() -> Result<(), AppError> {
+        let dir = tempdir()?;
+        for n in 0..PREVIEW_LINES + 3 {
+            File::create(dir.path().join(format!("file{:02}", n)))?;
+        }
+        let listing = preview_directory(dir.path());
+        assert_eq!(listing.lines().count(), PREVIEW_LINES + 1);
+        assert!(listing.starts_with("file00\nfile01\n"));
+        assert!(listing.ends_with("... and 3 more\n"), "{}", listing);
+        Ok(())
+    }
+
+    #[test]
+    fn test_entry_for_trashed_path_reads_info_file() -> Result<(), AppError> {
+        let trash = tempdir()?;
+        let files_dir = trash.path().join(TRASH_FILES_DIR_NAME);
+        let info_dir = trash.path().join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(&files_dir)?;
+        fs::create_dir_all(&info_dir)?;
+        fs::write(files_dir.join("a b.txt"), "hello")?;
+        fs::write(
+            info_dir.join(format!("a b.txt{}", TRASH_INFO_SUFFIX)),
+            "[Trash Info]\nPath=/home/user/a%20b.txt\nDeletionDate=2024-01-01T12:00:00\n",
+        )?;
+
+        let entry = entry_for_trashed_path(&files_dir.join("a b.txt"))?;
+        assert_eq!(entry.original_path, Path::new("/home/user/a b.txt"));
+        assert_eq!(entry.deletion_date, "2024-01-01T12:00:00");
+        assert!(entry_for_trashed_path(&files_dir.join("missing.txt")).is_err());
+        Ok(())
+    }
+}
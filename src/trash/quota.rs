@@ -0,0 +1,266 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+use humansize::{format_size, BINARY};
+
+use crate::trash::emptying::purge_entry;
+use crate::trash::error::AppError;
+use crate::trash::home_path::display_path;
+use crate::trash::restoring::{find_trash_entries_in_dirs, parse_deletion_date, TrashEntry};
+use crate::trash::size::dir_size;
+use crate::trash::spec::TRASH_FILES_DIR_NAME;
+
+/// An item permanently removed from a trash to bring it back under `--max-size`.
+#[derive(Debug)]
+pub(crate) struct Evicted {
+    pub(crate) original_path: PathBuf,
+    pub(crate) size: u64,
+}
+
+/// Brings the trash at `trash_root` back under `max_size` bytes after `just_trashed` (paths in
+/// its `files` directory) were added, by permanently removing the oldest other items first.
+///
+/// Items whose deletion date cannot be parsed are never evicted, since their age is unknown.
+/// Each item just trashed is checked on its own: one larger than `max_size` gets a warning and
+/// nothing is evicted for it, as emptying the rest of the trash could not make room for it.
+/// For the items that fit, older items are evicted until the trash, not counting the oversized
+/// ones, is back under the limit.
+pub(crate) fn enforce_max_size(
+    trash_root: &Path,
+    just_trashed: &[PathBuf],
+    max_size: u64,
+) -> Result<Vec<Evicted>, AppError> {
+    let total = dir_size(&trash_root.join(TRASH_FILES_DIR_NAME));
+    if total <= max_size {
+        return Ok(Vec::new());
+    }
+    let mut oversized_size = 0;
+    let mut any_fits = false;
+    for path in just_trashed {
+        let size = dir_size(path);
+        if size <= max_size {
+            any_fits = true;
+            continue;
+        }
+        oversized_size += size;
+        eprintln!(
+            "warning: '{}' was trashed, but it is larger than the trash's size limit of {} on its own ({}); \
+             nothing older was evicted for it.",
+            path.display(),
+            format_size(max_size, BINARY),
+            format_size(size, BINARY)
+        );
+    }
+    // The oversized items stay over the limit whatever is evicted, so only the rest is brought under it.
+    let total = total.saturating_sub(oversized_size);
+    if !any_fits || total <= max_size {
+        return Ok(Vec::new());
+    }
+
+    let just_trashed: HashSet<&Path> = just_trashed.iter().map(PathBuf::as_path).collect();
+    let candidates: Vec<(TrashEntry, NaiveDateTime, u64)> = find_trash_entries_in_dirs(&[trash_root.to_path_buf()])
+        .into_iter()
+        .filter(|entry| !just_trashed.contains(entry.trashed_path.as_path()))
+        .filter_map(|entry| {
+            let deleted_at = parse_deletion_date(&entry.deletion_date)?;
+            let size = dir_size(&entry.trashed_path);
+            Some((entry, deleted_at, size))
+        })
+        .collect();
+
+    let mut evicted = Vec::new();
+    let mut total = total;
+    for index in plan_eviction(&candidates, total, max_size) {
+        let (entry, _, _) = &candidates[index];
+        match purge_entry(entry) {
+            Ok(size) => {
+                total = total.saturating_sub(size);
+                evicted.push(Evicted {
+                    original_path: entry.original_path.clone(),
+                    size,
+                });
+            }
+            Err(e) => eprintln!(
+                "warning: Failed to evict '{}': {}",
+                display_path(&entry.original_path),
+                e
+            ),
+        }
+    }
+    if total > max_size {
+        eprintln!(
+            "warning: The trash {} is still over its size limit of {} ({} in total).",
+            trash_root.display(),
+            format_size(max_size, BINARY),
+            format_size(total + oversized_size, BINARY)
+        );
+    }
+    Ok(evicted)
+}
+
+/// Returns the indexes of the `candidates` (entry, deletion date, size) to evict, oldest first,
+/// until `total` is no more than `max_size` or there is nothing left to evict.
+fn plan_eviction<T>(candidates: &[(T, NaiveDateTime, u64)], total: u64, max_size: u64) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..candidates.len()).collect();
+    order.sort_by_key(|&index| candidates[index].1);
+    let mut total = total;
+    order
+        .into_iter()
+        .take_while(|&index| {
+            if total <= max_size {
+                return false;
+            }
+            total = total.saturating_sub(candidates[index].2);
+            true
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trash::spec::{TRASH_INFO_DIR_NAME, TRASH_INFO_SUFFIX};
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn date(value: &str) -> NaiveDateTime {
+        parse_deletion_date(value).unwrap()
+    }
+
+    #[test]
+    fn test_plan_eviction() {
+        let candidates = [
+            ("new", date("2024-03-01T00:00:00"), 100),
+            ("oldest", date("2024-01-01T00:00:00"), 300),
+            ("middle", date("2024-02-01T00:00:00"), 200),
+        ];
+
+        struct TestCase {
+            total: u64,
+            max_size: u64,
+            expected: Vec<&'static str>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                total: 600,
+                max_size: 600,
+                expected: vec![],
+            },
+            TestCase {
+                total: 600,
+                max_size: 500,
+                expected: vec!["oldest"],
+            },
+            TestCase {
+                total: 600,
+                max_size: 300,
+                expected: vec!["oldest"],
+            },
+            TestCase {
+                total: 600,
+                max_size: 250,
+                expected: vec!["oldest", "middle"],
+            },
+            TestCase {
+                total: 700,
+                max_size: 50,
+                expected: vec!["oldest", "middle", "new"],
+            },
+        ];
+
+        for case in test_cases {
+            let names: Vec<&str> = plan_eviction(&candidates, case.total, case.max_size)
+                .into_iter()
+                .map(|index| candidates[index].0)
+                .collect();
+            assert_eq!(names, case.expected, "total {} max {}", case.total, case.max_size);
+        }
+    }
+
+    fn add_item(trash_root: &Path, name: &str, deletion_date: &str, size: usize) -> Result<PathBuf, AppError> {
+        let trashed_path = trash_root.join(TRASH_FILES_DIR_NAME).join(name);
+        fs::write(&trashed_path, vec![0u8; size])?;
+        fs::write(
+            trash_root
+                .join(TRASH_INFO_DIR_NAME)
+                .join(format!("{}{}", name, TRASH_INFO_SUFFIX)),
+            format!(
+                "[Trash Info]\nPath=/home/user/{}\nDeletionDate={}\n",
+                name, deletion_date
+            ),
+        )?;
+        Ok(trashed_path)
+    }
+
+    #[test]
+    fn test_enforce_max_size_evicts_oldest_first() -> Result<(), AppError> {
+        let trash = tempdir()?;
+        fs::create_dir_all(trash.path().join(TRASH_FILES_DIR_NAME))?;
+        fs::create_dir_all(trash.path().join(TRASH_INFO_DIR_NAME))?;
+        let january = add_item(trash.path(), "january.log", "2024-01-01T00:00:00", 400)?;
+        let march = add_item(trash.path(), "march.log", "2024-03-01T00:00:00", 400)?;
+        let february = add_item(trash.path(), "february.log", "2024-02-01T00:00:00", 400)?;
+        let unknown = add_item(trash.path(), "unknown.log", "yesterday", 400)?;
+        // Older than everything else by its date, but it was the item just trashed.
+        let new = add_item(trash.path(), "new.log", "2023-01-01T00:00:00", 400)?;
+
+        let evicted = enforce_max_size(trash.path(), std::slice::from_ref(&new), 1_500)?;
+
+        let evicted: Vec<(PathBuf, u64)> = evicted
+            .into_iter()
+            .map(|item| (item.original_path, item.size))
+            .collect();
+        assert_eq!(
+            evicted,
+            [
+                (PathBuf::from("/home/user/january.log"), 400),
+                (PathBuf::from("/home/user/february.log"), 400)
+            ]
+        );
+        assert!(!january.exists() && !february.exists());
+        assert!(march.exists() && unknown.exists() && new.exists());
+        assert!(!trash
+            .path()
+            .join(TRASH_INFO_DIR_NAME)
+            .join(format!("january.log{}", TRASH_INFO_SUFFIX))
+            .exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_enforce_max_size_keeps_trash_for_oversized_item() -> Result<(), AppError> {
+        let trash = tempdir()?;
+        fs::create_dir_all(trash.path().join(TRASH_FILES_DIR_NAME))?;
+        fs::create_dir_all(trash.path().join(TRASH_INFO_DIR_NAME))?;
+        let old = add_item(trash.path(), "old.log", "2024-01-01T00:00:00", 100)?;
+        let huge = add_item(trash.path(), "huge.iso", "2024-02-01T00:00:00", 2_000)?;
+
+        let evicted = enforce_max_size(trash.path(), std::slice::from_ref(&huge), 1_000)?;
+
+        assert!(evicted.is_empty(), "{:?}", evicted);
+        assert!(old.exists() && huge.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_enforce_max_size_checks_each_new_item() -> Result<(), AppError> {
+        let trash = tempdir()?;
+        fs::create_dir_all(trash.path().join(TRASH_FILES_DIR_NAME))?;
+        fs::create_dir_all(trash.path().join(TRASH_INFO_DIR_NAME))?;
+        let oldest = add_item(trash.path(), "oldest.log", "2024-01-01T00:00:00", 600)?;
+        let older = add_item(trash.path(), "older.log", "2024-02-01T00:00:00", 300)?;
+        let huge = add_item(trash.path(), "huge.iso", "2024-03-01T00:00:00", 2_000)?;
+        let small = add_item(trash.path(), "small.txt", "2024-03-01T00:00:00", 400)?;
+
+        // Together the new items are over the limit, but the small one fits on its own.
+        let evicted = enforce_max_size(trash.path(), &[huge.clone(), small.clone()], 1_000)?;
+
+        let evicted: Vec<PathBuf> = evicted.into_iter().map(|item| item.original_path).collect();
+        assert_eq!(evicted, [PathBuf::from("/home/user/oldest.log")]);
+        assert!(!oldest.exists());
+        assert!(older.exists() && huge.exists() && small.exists());
+        Ok(())
+    }
+}
@@ -1,132 +1,353 @@
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, BufRead, ErrorKind, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use once_cell::sync::Lazy;
-use regex::Regex;
+use chrono::{DateTime, Local, NaiveDateTime};
+use glob::Pattern;
 use skim::{prelude::*, SkimOptions};
 
+use crate::trash::confirm::confirm_input;
+use crate::trash::copy::{copy_recursive, move_by_copy, Throttle};
+use crate::trash::directory_sizes::forget_trashed_item;
 use crate::trash::error::AppError;
-use crate::trash::locations::get_target_trash_dirs;
-use crate::trash::spec::{
-    TRASH_FILES_DIR_NAME, TRASH_INFO_DATE_KEY, TRASH_INFO_DIR_NAME, TRASH_INFO_EXTENSION, TRASH_INFO_PATH_KEY,
-    TRASH_INFO_SUFFIX,
-};
-use crate::trash::url_escape::trash_spec_url_decode;
+use crate::trash::home_path::display_path;
+use crate::trash::info_file::{data_name_for, read_trash_info, TrashInfo};
+use crate::trash::locations::{ensure_creation_allowed, select_source_trash_dirs};
+use crate::trash::porcelain::escape_field;
+use crate::trash::preview::{enable_builtin_preview, is_builtin_preview_enabled, render_preview};
+use crate::trash::spec::{TRASH_FILES_DIR_NAME, TRASH_INFO_DATE_FORMAT, TRASH_INFO_DIR_NAME, TRASH_INFO_EXTENSION};
+use crate::trash::url_escape::{trash_spec_url_decode, trash_spec_url_decode_strict};
 
 #[derive(Debug, Clone)]
-struct TrashEntry {
+pub struct TrashEntry {
     // Path to the file/dir inside `Trash/files`
-    trashed_path: PathBuf,
+    pub(crate) trashed_path: PathBuf,
     // Path to the `.trashinfo` file inside `Trash/info`
-    info_path: PathBuf,
+    pub(crate) info_path: PathBuf,
     // Original path of the item
-    original_path: PathBuf,
+    pub(crate) original_path: PathBuf,
     // Deletion date string
-    deletion_date: String,
+    pub(crate) deletion_date: String,
+    // Original permission bits from the `Mode` extension key, if recorded
+    pub(crate) mode: Option<u32>,
+    // Permission bits of the original parent directory from the `ParentMode` extension key, if recorded
+    pub(crate) parent_mode: Option<u32>,
+}
+
+impl TrashEntry {
+    /// Parses the raw `DeletionDate` value as local time, returning `None` if it cannot be parsed.
+    pub(crate) fn deletion_time(&self) -> Option<NaiveDateTime> {
+        parse_deletion_date(&self.deletion_date)
+    }
+
+    /// A key ordering entries by deletion time. Dates that cannot be parsed sort first
+    /// and are only compared as strings among themselves.
+    pub(crate) fn deletion_order_key(&self) -> (Option<NaiveDateTime>, &str) {
+        (self.deletion_time(), &self.deletion_date)
+    }
+
+    /// The trash directory the entry belongs to, i.e. the parent of its `info` directory.
+    pub(crate) fn trash_root(&self) -> &Path {
+        self.info_path.parent().and_then(Path::parent).unwrap_or(Path::new(""))
+    }
+}
+
+/// A short label for the trash at `trash_root`, telling the bins of different drives apart:
+/// `home` for the home trash, or the name of the top directory of a `$topdir/.Trash-$uid`
+/// or `$topdir/.Trash/$uid` trash (e.g. `usb` for `/media/usb/.Trash-1000`).
+fn trash_label(trash_root: &Path) -> String {
+    let name = trash_root.file_name().map(|name| name.to_string_lossy());
+    let parent = trash_root.parent();
+    let topdir = match (name.as_deref(), parent.and_then(Path::file_name)) {
+        (Some(name), _) if name.starts_with(".Trash-") => parent,
+        (_, Some(parent_name)) if parent_name == ".Trash" || parent_name == ".Trashes" => parent.and_then(Path::parent),
+        _ => return "home".to_string(),
+    };
+    match topdir {
+        Some(topdir) => match topdir.file_name() {
+            Some(topdir_name) => topdir_name.to_string_lossy().into_owned(),
+            None => topdir.display().to_string(),
+        },
+        None => trash_root.display().to_string(),
+    }
+}
+
+/// Parses a `DeletionDate` value into local time. Besides the spec format (local time without
+/// an offset), this accepts the ISO-8601 forms some other tools write: a numeric offset
+/// (`+09:00`, `+0900`) or `Z`, optionally with fractional seconds. Those are converted to
+/// local time, so all dates compare as the same kind of instant.
+pub(crate) fn parse_deletion_date(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, TRASH_INFO_DATE_FORMAT) {
+        return Some(naive);
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Some(naive);
+    }
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f%z"))
+        .ok()
+        .map(|date| date.with_timezone(&Local).naive_local())
 }
 
 impl SkimItem for TrashEntry {
     fn text(&self) -> Cow<'_, str> {
         Cow::Owned(format!(
-            "{}  {} <= {}",
+            "[{}] {}  {} <= {}",
+            trash_label(self.trash_root()),
             self.deletion_date,
-            self.original_path.display(),
-            self.trashed_path.display()
+            display_path(&self.original_path),
+            display_path(&self.trashed_path)
         ))
     }
+
+    fn preview(&self, _context: PreviewContext) -> ItemPreview {
+        if is_builtin_preview_enabled() {
+            ItemPreview::AnsiText(render_preview(self))
+        } else {
+            ItemPreview::Global
+        }
+    }
 }
 
-static PATH_RE: Lazy<Regex> = Lazy::new(|| Regex::new(&format!(r"^{}=(.*)$", TRASH_INFO_PATH_KEY)).unwrap());
-static DATE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(&format!(r"^{}=(.*)$", TRASH_INFO_DATE_KEY)).unwrap());
+/// The entries found in several trash directories, along with what went wrong while reading them.
+#[derive(Debug, Default)]
+pub(crate) struct TrashScan {
+    pub(crate) entries: Vec<TrashEntry>,
+    /// One error per unreadable `info` directory or info file; the rest were still scanned.
+    pub(crate) errors: Vec<AppError>,
+}
 
-fn get_capture(re: &Regex, line: &str) -> Option<String> {
-    re.captures(line)
-        .and_then(|caps| caps.get(1))
-        .map(|m| m.as_str().to_string())
+/// Finds the trash entries in `trash_dirs`. An unreadable trash or info file is recorded
+/// in `TrashScan::errors` and skipped, so it never hides the entries of the others.
+pub(crate) fn scan_trash_dirs(trash_dirs: &[PathBuf]) -> TrashScan {
+    scan_trash_dirs_with(trash_dirs, false)
 }
 
-/// Helper function that finds trash entries in a given list of directories.
-fn find_trash_entries_in_dirs(trash_dirs: &[PathBuf]) -> Result<Vec<TrashEntry>, AppError> {
-    let mut entries = Vec::new();
+/// Like `scan_trash_dirs`, but an info file whose `Path` has a malformed percent escape is
+/// recorded as an error instead of being decoded leniently. Only `check` uses it: a bare `%`
+/// is how `PathEncoding::Raw` and some other tools write it, so restoring must accept one.
+pub(crate) fn scan_trash_dirs_strict(trash_dirs: &[PathBuf]) -> TrashScan {
+    scan_trash_dirs_with(trash_dirs, true)
+}
 
+fn scan_trash_dirs_with(trash_dirs: &[PathBuf], strict: bool) -> TrashScan {
+    let mut scan = TrashScan::default();
     for trash_dir in trash_dirs {
-        let info_dir = trash_dir.join(TRASH_INFO_DIR_NAME);
-        if !info_dir.is_dir() {
-            continue;
-        }
+        scan_trash_dir(trash_dir, strict, &mut scan);
+    }
+    scan
+}
 
-        let dir_entries = fs::read_dir(&info_dir).map_err(|source| AppError::Io {
-            path: info_dir.clone(),
-            source,
-        })?;
+/// Helper function that finds trash entries in a given list of directories.
+/// Problems with individual trashes are printed as warnings (see `scan_trash_dirs`).
+pub(crate) fn find_trash_entries_in_dirs(trash_dirs: &[PathBuf]) -> Vec<TrashEntry> {
+    warn_and_take_entries(scan_trash_dirs(trash_dirs))
+}
 
-        for entry in dir_entries {
-            let entry = entry.map_err(|source| AppError::Io {
-                path: info_dir.clone(),
-                source,
-            })?;
-            let info_path = entry.path();
-            if info_path.extension().and_then(|s| s.to_str()) != Some(TRASH_INFO_EXTENSION) {
+fn warn_and_take_entries(scan: TrashScan) -> Vec<TrashEntry> {
+    for error in &scan.errors {
+        eprintln!("warning: {}", error);
+    }
+    scan.entries
+}
+
+fn scan_trash_dir(trash_dir: &Path, strict: bool, scan: &mut TrashScan) {
+    let info_dir = trash_dir.join(TRASH_INFO_DIR_NAME);
+    if !info_dir.is_dir() {
+        return;
+    }
+
+    let dir_entries = match fs::read_dir(&info_dir) {
+        Ok(dir_entries) => dir_entries,
+        Err(source) => {
+            scan.errors.push(AppError::Io { path: info_dir, source });
+            return;
+        }
+    };
+
+    for entry in dir_entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(source) => {
+                scan.errors.push(AppError::Io {
+                    path: info_dir.clone(),
+                    source,
+                });
                 continue;
             }
+        };
+        let info_path = entry.path();
+        if info_path.extension().and_then(|s| s.to_str()) != Some(TRASH_INFO_EXTENSION) {
+            continue;
+        }
 
-            let content = fs::read_to_string(&info_path).map_err(|source| AppError::Io {
-                path: info_path.clone(),
-                source,
-            })?;
-            let mut original_path_str = None;
-            let mut deletion_date = None;
-
-            for line in content.lines() {
-                if original_path_str.is_none() {
-                    original_path_str = get_capture(&PATH_RE, line);
-                }
-                if deletion_date.is_none() {
-                    deletion_date = get_capture(&DATE_RE, line);
-                }
+        let TrashInfo {
+            path: original_path_str,
+            deletion_date,
+            mode,
+            parent_mode,
+        } = match read_trash_info(&info_path) {
+            Ok(info) => info,
+            Err(e) => {
+                scan.errors.push(e);
+                continue;
             }
+        };
 
-            if let (Some(original_path_str), Some(deletion_date)) = (original_path_str, deletion_date) {
-                // Decode the URL-escaped path from the .trashinfo file.
-                match trash_spec_url_decode(&original_path_str) {
-                    Ok(decoded_path) => {
-                        let info_filename = info_path.file_name().unwrap().to_string_lossy();
-                        let base_filename = info_filename.strip_suffix(TRASH_INFO_SUFFIX).unwrap_or(&info_filename);
-
-                        let trashed_path = trash_dir.join(TRASH_FILES_DIR_NAME).join(base_filename);
-
-                        entries.push(TrashEntry {
-                            trashed_path,
-                            info_path: info_path.clone(),
-                            original_path: PathBuf::from(decoded_path),
-                            deletion_date,
-                        });
-                    }
+        if let (Some(original_path_str), Some(deletion_date)) = (original_path_str, deletion_date) {
+            // Decode the URL-escaped path from the .trashinfo file, byte for byte.
+            let original_path = if strict {
+                match trash_spec_url_decode_strict(&original_path_str, &info_path) {
+                    Ok(original_path) => original_path,
                     Err(e) => {
-                        // If decoding fails, the .trashinfo file is likely corrupt.
-                        // Warn the user and skip this entry.
-                        eprintln!(
-                            "warning: Failed to decode path from '{}': {}. Skipping entry.",
-                            info_path.display(),
-                            e
-                        );
+                        scan.errors.push(e);
+                        continue;
                     }
                 }
+            } else {
+                trash_spec_url_decode(&original_path_str)
+            };
+
+            // The extension check above guarantees the suffix, and only one is removed,
+            // so an item that was itself named `x.trashinfo` is found under that name.
+            let info_filename = info_path.file_name().unwrap_or_default();
+            let base_filename = data_name_for(info_filename).unwrap_or(info_filename);
+
+            let trashed_path = trash_dir.join(TRASH_FILES_DIR_NAME).join(base_filename);
+
+            scan.entries.push(TrashEntry {
+                trashed_path,
+                info_path: info_path.clone(),
+                original_path: PathBuf::from(original_path),
+                deletion_date,
+                mode,
+                parent_mode,
+            });
+        }
+    }
+}
+
+/// The mode of directories a restore has to recreate, unless the item's `ParentMode` was
+/// recorded: private to the user, like the trash itself, rather than whatever the umask allows.
+const RECREATED_DIR_MODE: u32 = 0o700;
+
+/// The maximum edit distance between an info file's name and a data file's name
+/// for the two to be considered a mangled pair by `--fuzzy-pair`.
+const FUZZY_PAIR_MAX_DISTANCE: usize = 3;
+
+/// Re-pairs entries whose data file is missing under the name derived from the info file,
+/// e.g. because a sync tool renamed `report.pdf` to `report (conflict).pdf` in `files/`.
+/// A data file without its own info file is used when its name starts with the expected name
+/// or is within a small edit distance of it. Ambiguous candidates are left alone.
+fn pair_mangled_entries(entries: &mut [TrashEntry]) {
+    for index in 0..entries.len() {
+        if entries[index].trashed_path.exists() {
+            continue;
+        }
+        let expected_path = entries[index].trashed_path.clone();
+        let (Some(files_dir), Some(expected_name)) = (expected_path.parent(), expected_path.file_name()) else {
+            continue;
+        };
+        let expected_name = expected_name.to_string_lossy();
+        let Ok(dir_entries) = fs::read_dir(files_dir) else {
+            continue;
+        };
+
+        let mut best: Option<(usize, PathBuf)> = None;
+        let mut is_ambiguous = false;
+        for candidate in dir_entries.filter_map(Result::ok).map(|e| e.path()) {
+            if entries.iter().any(|e| e.trashed_path == candidate) {
+                continue; // Already paired with its own info file.
+            }
+            let candidate_name = candidate.file_name().unwrap().to_string_lossy().into_owned();
+            let distance = if candidate_name.starts_with(expected_name.as_ref()) {
+                0
+            } else {
+                edit_distance(&candidate_name, &expected_name)
+            };
+            if distance > FUZZY_PAIR_MAX_DISTANCE {
+                continue;
+            }
+            match &best {
+                Some((best_distance, _)) if distance > *best_distance => {}
+                Some((best_distance, _)) if distance == *best_distance => is_ambiguous = true,
+                _ => {
+                    best = Some((distance, candidate));
+                    is_ambiguous = false;
+                }
+            }
+        }
+
+        match best {
+            Some((_, candidate)) if !is_ambiguous => {
+                eprintln!(
+                    "warning: '{}' is missing; pairing '{}' with '{}' instead.",
+                    expected_path.display(),
+                    entries[index].info_path.display(),
+                    candidate.display()
+                );
+                entries[index].trashed_path = candidate;
             }
+            Some(_) => eprintln!(
+                "warning: '{}' is missing and several files could match it. Skipping fuzzy pairing.",
+                expected_path.display()
+            ),
+            None => {}
+        }
+    }
+}
+
+/// Computes the Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b_chars.len() + 1];
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
         }
+        previous = current;
     }
-    Ok(entries)
+    previous[b_chars.len()]
+}
+
+pub struct RestoreOptions {
+    pub all_trash: bool,
+    /// Restore only from this trash directory instead (`--trash-dir`).
+    pub trash_dir: Option<PathBuf>,
+    pub porcelain: bool,
+    /// Pair info files with mangled data file names (see `pair_mangled_entries`).
+    pub fuzzy_pair: bool,
+    /// Maximum copy throughput in bytes per second when a restore has to copy across filesystems.
+    pub limit_rate: Option<u64>,
+    /// Copy the selected entries out to this path instead of moving them back,
+    /// leaving the trash entries and their info files intact.
+    pub copy_out: Option<PathBuf>,
+    /// Print each resulting path raw, terminated by a NUL byte, for `xargs -0`.
+    pub print0: bool,
+    /// List the matched entries and ask once before restoring them (non-interactive modes only).
+    pub confirm: bool,
 }
 
 /// Interactively select and restore items from the trash.
-pub fn handle_interactive_restore(all_trash: bool, mut skim_options: SkimOptions) -> Result<(), AppError> {
-    let trash_dirs = get_target_trash_dirs(all_trash)?;
-    let entries = find_trash_entries_in_dirs(&trash_dirs)?;
+pub fn handle_interactive_restore(opts: RestoreOptions, mut skim_options: SkimOptions) -> Result<(), AppError> {
+    let trash_dirs = select_source_trash_dirs(opts.all_trash, opts.trash_dir.as_deref())?;
+    let mut entries = find_trash_entries_in_dirs(&trash_dirs);
+    if opts.fuzzy_pair {
+        pair_mangled_entries(&mut entries);
+    }
     if entries.is_empty() {
         println!("Trash is empty. Nothing to restore.");
         return Ok(());
     }
+    // Keep the items of each trash together, in the order the trashes were searched.
+    entries.sort_by_key(|entry| trash_dirs.iter().position(|trash_dir| entry.trash_root() == trash_dir));
 
     let (tx_skim, rx_skim): (SkimItemSender, SkimItemReceiver) = unbounded();
     for entry in &entries {
@@ -140,81 +361,441 @@ pub fn handle_interactive_restore(all_trash: bool, mut skim_options: SkimOptions
     let default_binds = ["Enter:accept", "Esc:abort", "ctrl-c:abort"].map(String::from);
     skim_options.bind.splice(0..0, default_binds);
 
+    // Without a `--preview` command of the user's own, the preview pane shows each entry's
+    // metadata and contents, rendered in-process since the entries are not files skim can read.
+    if skim_options.preview.is_none() {
+        skim_options.preview = Some(String::new());
+        enable_builtin_preview();
+    }
+
     let skim_output = Skim::run_with(&skim_options, Some(rx_skim));
 
-    let mut messages: Vec<String> = vec![];
+    let selected: Vec<TrashEntry> = match skim_output {
+        Some(output) if !output.is_abort => output
+            .selected_items
+            .iter()
+            .map(|item| (**item).as_any().downcast_ref::<TrashEntry>().unwrap().clone())
+            .collect(),
+        // User cancelled (e.g., with Esc, Ctrl-C).
+        _ => Vec::new(),
+    };
+
+    if !skim_options.no_clear {
+        print!("\x1B[2J\x1B[H");
+    }
+    restore_entries(&selected, &opts)
+}
+
+/// Restores the entries whose original path is one of `paths`, without any interaction.
+/// Paths are compared as `PathBuf`s, so names containing newlines or other control
+/// characters match exactly. If a path was trashed several times, the latest one is restored.
+pub fn handle_restore_paths(paths: &[String], opts: RestoreOptions) -> Result<(), AppError> {
+    // The trash an item went to depends on where it lived, so every trash is searched.
+    let trash_dirs = select_source_trash_dirs(true, opts.trash_dir.as_deref())?;
+    let mut entries = find_trash_entries_in_dirs(&trash_dirs);
+    if opts.fuzzy_pair {
+        pair_mangled_entries(&mut entries);
+    }
+
+    let mut selected = Vec::new();
     let mut had_errors = false;
+    for path in paths {
+        let original_path = absolute_original_path(Path::new(path));
+        match find_entry_for_path(&entries, &original_path) {
+            Some(entry) => selected.push(entry.clone()),
+            None => {
+                eprintln!("No trashed item found for '{}'.", original_path.display());
+                had_errors = true;
+            }
+        }
+    }
 
-    match skim_output {
-        Some(output) if !output.is_abort => {
-            if output.selected_items.is_empty() {
-                // println!("No items selected.");
-            } else {
-                for item in output.selected_items {
-                    let entry = (*item).as_any().downcast_ref::<TrashEntry>().unwrap();
-                    match restore_item(entry) {
-                        Ok(path) => {
-                            messages.push(format!("Restored: {}", path.display()));
-                            // println!("Restored: {}", path.display())
-                        }
-                        Err(e) => {
-                            messages.push(format!("Failed to restore '{}': {}", entry.original_path.display(), e));
-                            had_errors = true;
-                            // eprintln!("Failed to restore '{}': {}", entry.original_path.display(), e);
-                        }
-                    }
-                }
+    let result = restore_confirmed(&selected, &opts);
+    if had_errors && result.is_ok() {
+        return Err(AppError::Ignorable);
+    }
+    result
+}
+
+/// Restores items by the file name they had before being trashed, without the interactive UI.
+/// A name matching items from several places (or trashed several times) is not guessed at:
+/// the candidates are listed with their deletion dates and nothing is restored for it.
+pub fn handle_restore_names(names: &[String], opts: RestoreOptions) -> Result<(), AppError> {
+    let trash_dirs = select_source_trash_dirs(opts.all_trash, opts.trash_dir.as_deref())?;
+    let mut entries = find_trash_entries_in_dirs(&trash_dirs);
+    if opts.fuzzy_pair {
+        pair_mangled_entries(&mut entries);
+    }
+
+    let mut selected = Vec::new();
+    let mut had_errors = false;
+    for name in names {
+        match find_entry_for_name(&entries, name) {
+            Ok(entry) => selected.push(entry.clone()),
+            Err(message) => {
+                eprintln!("{}", message);
+                had_errors = true;
             }
         }
+    }
+
+    let result = restore_confirmed(&selected, &opts);
+    if had_errors && result.is_ok() {
+        return Err(AppError::Ignorable);
+    }
+    result
+}
+
+/// Restores every item whose original file name matches one of the glob `patterns` (e.g. `*.log`),
+/// without the interactive UI, for batch recovery in scripts. Each result is printed as it happens,
+/// followed by a summary line. An item whose original path is taken again is not restored, and of
+/// several items trashed from the same path only the newest is.
+pub fn handle_restore_glob(patterns: &[String], opts: RestoreOptions) -> Result<(), AppError> {
+    let patterns = patterns
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern).map_err(|e| AppError::Message(format!("Invalid glob pattern '{}': {}", pattern, e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let trash_dirs = select_source_trash_dirs(opts.all_trash, opts.trash_dir.as_deref())?;
+    let mut entries = find_trash_entries_in_dirs(&trash_dirs);
+    if opts.fuzzy_pair {
+        pair_mangled_entries(&mut entries);
+    }
+
+    let mut selected = select_entries_by_glob(&entries, &patterns);
+    if selected.is_empty() {
+        let patterns: Vec<&str> = patterns.iter().map(Pattern::as_str).collect();
+        eprintln!("No trashed item matches '{}'.", patterns.join("', '"));
+        return Err(AppError::Ignorable);
+    }
+    // Newest first, so that of several items trashed from the same path, the newest is the one that gets there.
+    selected.sort_by(|a, b| b.deletion_order_key().cmp(&a.deletion_order_key()));
+    skip_older_duplicates(&mut selected);
+    if opts.confirm && !confirm_restore(&mut io::stderr(), &mut io::stdin().lock(), &selected)? {
+        return Ok(());
+    }
+
+    let failed = restore_each(&selected, &opts)?;
+    if !opts.porcelain && !opts.print0 {
+        let verb = if opts.copy_out.is_some() {
+            "Copied out"
+        } else {
+            "Restored"
+        };
+        let noun = if selected.len() == 1 { "item" } else { "items" };
+        println!(
+            "{} {} of {} matching {}.",
+            verb,
+            selected.len() - failed,
+            selected.len(),
+            noun
+        );
+    }
+    if failed > 0 {
+        return Err(AppError::Ignorable);
+    }
+    Ok(())
+}
+
+/// Drops every entry whose original path already belongs to an earlier entry of `entries`,
+/// which is sorted newest first, and notes each one on stderr. They stay in the trash.
+fn skip_older_duplicates(entries: &mut Vec<TrashEntry>) {
+    let mut seen = HashSet::new();
+    entries.retain(|entry| {
+        if seen.insert(entry.original_path.clone()) {
+            return true;
+        }
+        eprintln!(
+            "Skipping '{}' trashed {}: a newer item from the same path is restored instead.",
+            display_path(&entry.original_path),
+            entry.deletion_date
+        );
+        false
+    });
+}
+
+/// Returns the entries whose original file name (decoded from their info file) matches any of `patterns`.
+fn select_entries_by_glob(entries: &[TrashEntry], patterns: &[Pattern]) -> Vec<TrashEntry> {
+    entries
+        .iter()
+        .filter(|entry| {
+            entry.original_path.file_name().is_some_and(|name| {
+                let name = name.to_string_lossy();
+                patterns.iter().any(|pattern| pattern.matches(&name))
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// Finds the only trashed entry whose original file name is `name`, or explains why there is none.
+fn find_entry_for_name<'a>(entries: &'a [TrashEntry], name: &str) -> Result<&'a TrashEntry, String> {
+    let mut candidates: Vec<&TrashEntry> = entries
+        .iter()
+        .filter(|entry| entry.original_path.file_name() == Some(name.as_ref()))
+        .collect();
+    match candidates.len() {
+        0 => Err(format!("No trashed item named '{}' found.", name)),
+        1 => Ok(candidates[0]),
         _ => {
-            // User cancelled (e.g., with Esc, Ctrl-C).
-            // println!("Restore cancelled.");
+            candidates.sort_by(|a, b| b.deletion_order_key().cmp(&a.deletion_order_key()));
+            let listing: Vec<String> = candidates
+                .iter()
+                .map(|entry| format!("  {}  {}", entry.deletion_date, display_path(&entry.original_path)))
+                .collect();
+            Err(format!(
+                "Several trashed items are named '{}'; restore one by its original path instead:\n{}",
+                name,
+                listing.join("\n")
+            ))
         }
     }
+}
 
-    if !skim_options.no_clear {
-        print!("\x1B[2J\x1B[H");
+/// Puts back the items that were trashed from exactly `paths`, like the "Put Back" of the macOS Finder.
+/// Unlike `handle_restore_paths`, a path trashed several times is not guessed at: the candidates
+/// are listed with their deletion dates and nothing is restored for it.
+pub fn handle_put_back(paths: &[String], opts: RestoreOptions) -> Result<(), AppError> {
+    // The trash an item went to depends on where it lived, so every trash is searched.
+    let trash_dirs = select_source_trash_dirs(true, opts.trash_dir.as_deref())?;
+    let mut entries = find_trash_entries_in_dirs(&trash_dirs);
+    if opts.fuzzy_pair {
+        pair_mangled_entries(&mut entries);
+    }
+
+    let mut selected = Vec::new();
+    let mut had_errors = false;
+    for path in paths {
+        let original_path = absolute_original_path(Path::new(path));
+        match find_entry_to_put_back(&entries, &original_path) {
+            Ok(entry) => selected.push(entry.clone()),
+            Err(message) => {
+                eprintln!("{}", message);
+                had_errors = true;
+            }
+        }
+    }
+
+    let result = restore_confirmed(&selected, &opts);
+    if had_errors && result.is_ok() {
+        return Err(AppError::Ignorable);
+    }
+    result
+}
+
+/// Finds the only trashed entry whose decoded original path is exactly `original_path`,
+/// or explains why there is none.
+fn find_entry_to_put_back<'a>(entries: &'a [TrashEntry], original_path: &Path) -> Result<&'a TrashEntry, String> {
+    let mut candidates: Vec<&TrashEntry> = entries
+        .iter()
+        .filter(|entry| entry.original_path == original_path)
+        .collect();
+    match candidates.len() {
+        0 => Err(format!("No trashed item found for '{}'.", display_path(original_path))),
+        1 => Ok(candidates[0]),
+        _ => {
+            candidates.sort_by(|a, b| b.deletion_order_key().cmp(&a.deletion_order_key()));
+            let listing: Vec<String> = candidates
+                .iter()
+                .map(|entry| format!("  {}  {}", entry.deletion_date, display_path(&entry.trashed_path)))
+                .collect();
+            Err(format!(
+                "'{}' was trashed {} times; pick one with `tt -r`, or restore the latest with `tt -r PATH`:\n{}",
+                display_path(original_path),
+                candidates.len(),
+                listing.join("\n")
+            ))
+        }
+    }
+}
+
+/// Makes a restore argument absolute the way it was recorded at trash time.
+/// Only the parent is canonicalized, since the item itself is not there anymore.
+pub(crate) fn absolute_original_path(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(file_name)) => parent
+            .canonicalize()
+            .map(|parent| parent.join(file_name))
+            .unwrap_or(absolute.clone()),
+        _ => absolute,
+    }
+}
+
+/// Restores the single most recently trashed item from any trash directory, undoing the last trashing.
+pub fn handle_restore_latest(opts: RestoreOptions) -> Result<(), AppError> {
+    let trash_dirs = select_source_trash_dirs(true, opts.trash_dir.as_deref())?;
+    let mut entries = find_trash_entries_in_dirs(&trash_dirs);
+    if opts.fuzzy_pair {
+        pair_mangled_entries(&mut entries);
+    }
+    match find_latest_entry(&entries) {
+        Some(entry) => restore_confirmed(std::slice::from_ref(entry), &opts),
+        None => {
+            eprintln!("The trash is empty; there is nothing to restore.");
+            Err(AppError::NothingToDo)
+        }
+    }
+}
+
+/// Finds the most recently trashed entry. `DeletionDate` only has second precision, so ties
+/// are broken by the info file's modification time and then by the trashed path, which keeps
+/// the choice deterministic. Entries with an unparsable date count as the oldest.
+fn find_latest_entry(entries: &[TrashEntry]) -> Option<&TrashEntry> {
+    entries.iter().max_by_key(|entry| {
+        let info_modified = fs::metadata(&entry.info_path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        (entry.deletion_order_key(), info_modified, &entry.trashed_path)
+    })
+}
+
+/// Finds the most recently trashed entry for `original_path`.
+pub(crate) fn find_entry_for_path<'a>(entries: &'a [TrashEntry], original_path: &Path) -> Option<&'a TrashEntry> {
+    entries
+        .iter()
+        .filter(|entry| entry.original_path == original_path)
+        .max_by(|a, b| a.deletion_order_key().cmp(&b.deletion_order_key()))
+}
+
+/// Restores `entries` like `restore_entries`, but first asks for confirmation when `opts.confirm` is set.
+/// Answering no restores nothing.
+fn restore_confirmed(entries: &[TrashEntry], opts: &RestoreOptions) -> Result<(), AppError> {
+    if opts.confirm && !entries.is_empty() && !confirm_restore(&mut io::stderr(), &mut io::stdin().lock(), entries)? {
+        return Ok(());
     }
-    for message in messages {
-        println!("{}", message);
+    restore_entries(entries, opts)
+}
+
+/// Lists the entries about to be restored and asks once for all of them.
+/// Written to `writer` (stderr in practice) so stdout keeps only the restore results.
+fn confirm_restore<W: Write, R: BufRead>(
+    writer: &mut W,
+    reader: &mut R,
+    entries: &[TrashEntry],
+) -> Result<bool, AppError> {
+    for entry in entries {
+        writeln!(writer, "  {}", display_path(&entry.original_path))?;
     }
-    if had_errors {
+    let noun = if entries.len() == 1 { "item" } else { "items" };
+    let message = format!("Restore {} {}? [Y/n]: ", entries.len(), noun);
+    confirm_input(writer, reader, message)
+}
+
+/// Restores (or copies out) each entry and reports the results.
+fn restore_entries(entries: &[TrashEntry], opts: &RestoreOptions) -> Result<(), AppError> {
+    if restore_each(entries, opts)? > 0 {
         return Err(AppError::Ignorable);
     }
     Ok(())
 }
 
+/// Restores (or copies out) each entry, printing the result of each, and returns how many failed.
+fn restore_each(entries: &[TrashEntry], opts: &RestoreOptions) -> Result<usize, AppError> {
+    let mut writer = io::stdout();
+    let mut failed = 0;
+
+    for entry in entries {
+        let result = match &opts.copy_out {
+            Some(dest) => copy_out_item(entry, dest, opts.limit_rate),
+            None => restore_item(entry, opts.limit_rate),
+        };
+        match result {
+            Ok(path) if opts.print0 => {
+                writer.write_all(path.as_os_str().as_bytes())?;
+                writer.write_all(b"\0")?;
+            }
+            Ok(path) if opts.porcelain => {
+                writeln!(writer, "{}", escape_field(&path.display().to_string()))?;
+            }
+            Ok(path) if opts.copy_out.is_some() => {
+                writeln!(writer, "Copied out: {}", path.display())?;
+            }
+            Ok(path) => {
+                writeln!(writer, "{}", restored_message(&path))?;
+            }
+            Err(e) => {
+                let message = format!("Failed to restore '{}': {}", entry.original_path.display(), e);
+                // In machine-readable modes, failures are kept off stdout so that it only contains records.
+                if opts.porcelain || opts.print0 {
+                    eprintln!("{}", message);
+                } else {
+                    writeln!(writer, "{}", message)?;
+                }
+                failed += 1;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(failed)
+}
+
 /// Restores a single TrashEntry.
 /// Returns the path of the restored item on success.
-fn restore_item(entry: &TrashEntry) -> Result<PathBuf, AppError> {
+/// If the original location is on another filesystem, the item is copied back
+/// (at most `limit_rate` bytes per second) and then removed from the trash.
+pub(crate) fn restore_item(entry: &TrashEntry, limit_rate: Option<u64>) -> Result<PathBuf, AppError> {
+    restore_item_with(entry, limit_rate, |src, dst| fs::rename(src, dst))
+}
+
+/// Like `restore_item`, but moves the item with `rename`.
+fn restore_item_with<F>(entry: &TrashEntry, limit_rate: Option<u64>, rename: F) -> Result<PathBuf, AppError>
+where
+    F: FnOnce(&Path, &Path) -> io::Result<()>,
+{
     if entry.original_path.exists() {
         return Err(AppError::RestoreCollision {
             path: entry.original_path.clone(),
         });
     }
 
+    // Checked before recreating parents so a missing item leaves no empty directories behind.
+    if !entry.trashed_path.exists() {
+        return Err(AppError::TrashedItemNotFound {
+            path: entry.trashed_path.clone(),
+        });
+    }
+
+    let mut recreated_parent = None;
     if let Some(parent) = entry.original_path.parent() {
-        if let Err(source) = fs::create_dir_all(parent) {
+        if !parent.exists() {
+            ensure_creation_allowed(parent)?;
+            if let Err(source) = create_missing_dirs(parent) {
+                return Err(AppError::Io {
+                    path: parent.to_path_buf(),
+                    source,
+                });
+            }
+            recreated_parent = Some(parent);
+        }
+    }
+
+    // Move the file from the trash back to its original location.
+    match rename(&entry.trashed_path, &entry.original_path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+            move_by_copy(&entry.trashed_path, &entry.original_path, |src, dst| {
+                copy_recursive(src, dst, &mut Throttle::new(limit_rate))
+            })?;
+        }
+        Err(source) => {
             return Err(AppError::Io {
-                path: parent.to_path_buf(),
+                path: entry.trashed_path.clone(),
                 source,
             });
         }
     }
 
-    if !entry.trashed_path.exists() {
-        return Err(AppError::TrashedItemNotFound {
-            path: entry.trashed_path.clone(),
-        });
-    }
+    forget_trashed_item(&entry.trashed_path);
 
-    // Move the file from the trash back to its original location.
-    if let Err(source) = fs::rename(&entry.trashed_path, &entry.original_path) {
-        // TODO: Implement cross-device move logic here if `rename` fails.
-        return Err(AppError::Io {
-            path: entry.trashed_path.clone(),
-            source,
-        });
+    // A copy (or a umask applied along the way) may have changed the mode, so put the recorded one back.
+    if let Some(mode) = entry.mode {
+        reapply_mode(&entry.original_path, mode);
+    }
+    // Only now, since the recorded mode may not even let us move the item into the parent.
+    if let (Some(parent), Some(parent_mode)) = (recreated_parent, entry.parent_mode) {
+        reapply_mode(parent, parent_mode);
     }
 
     // Clean up the corresponding .trashinfo file.
@@ -231,32 +812,141 @@ fn restore_item(entry: &TrashEntry) -> Result<PathBuf, AppError> {
     Ok(entry.original_path.clone())
 }
 
+/// Creates `dir` and its missing ancestors with `RECREATED_DIR_MODE`, leaving existing ones untouched.
+fn create_missing_dirs(dir: &Path) -> io::Result<()> {
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(RECREATED_DIR_MODE)
+        .create(dir)
+}
+
+/// Sets the recorded permission bits on a restored item, warning instead of failing,
+/// since the item itself is already back in place. Symlinks are left alone.
+fn reapply_mode(path: &Path, mode: u32) {
+    let is_symlink = fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink());
+    if is_symlink {
+        return;
+    }
+    if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(mode)) {
+        eprintln!(
+            "warning: Restored '{}' but failed to re-apply its mode {:04o}: {}",
+            path.display(),
+            mode,
+            e
+        );
+    }
+}
+
+/// Copies a single TrashEntry out of the trash without touching the entry itself.
+/// If `dest` is an existing directory, the item is copied into it under its original
+/// file name; otherwise it is copied to `dest` itself. Returns the path of the copy.
+fn copy_out_item(entry: &TrashEntry, dest: &Path, limit_rate: Option<u64>) -> Result<PathBuf, AppError> {
+    let target = match entry.original_path.file_name() {
+        Some(name) if dest.is_dir() => dest.join(name),
+        _ => dest.to_path_buf(),
+    };
+    if target.symlink_metadata().is_ok() {
+        return Err(AppError::RestoreCollision { path: target });
+    }
+    if entry.trashed_path.symlink_metadata().is_err() {
+        return Err(AppError::TrashedItemNotFound {
+            path: entry.trashed_path.clone(),
+        });
+    }
+    copy_recursive(&entry.trashed_path, &target, &mut Throttle::new(limit_rate))?;
+    Ok(target)
+}
+
+/// Builds the summary line for a restored item. Directories also report how many
+/// entries they directly contain, so the scope of the restore is visible.
+fn restored_message(path: &Path) -> String {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => {
+            let item_count = fs::read_dir(path).map(|entries| entries.count()).unwrap_or(0);
+            let noun = if item_count == 1 { "item" } else { "items" };
+            format!("Restored directory ({} {}): {}", item_count, noun, display_path(path))
+        }
+        _ => format!("Restored: {}", display_path(path)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::trash::spec::TRASH_INFO_SUFFIX;
+    use crate::trash::url_escape::PathEncoding;
     use std::fs::File;
     use std::io::Write;
     use std::os::unix::fs::PermissionsExt;
     use tempfile::tempdir;
 
     #[test]
-    fn test_trash_entry_skim_item_text() {
-        // Create a sample TrashEntry.
-        let entry = TrashEntry {
-            trashed_path: PathBuf::from("/trash/files/test.txt"),
-            info_path: PathBuf::from("/trash/info/test.txt.trashinfo"),
-            original_path: PathBuf::from("/home/user/documents/test.txt"),
-            deletion_date: "2024-01-01T12:00:00".to_string(),
+    fn test_confirm_restore_lists_entries_and_count() -> Result<(), AppError> {
+        let entry = |name: &str| TrashEntry {
+            trashed_path: PathBuf::from(format!("/trash/files/{}", name)),
+            info_path: PathBuf::from(format!("/trash/info/{}.trashinfo", name)),
+            original_path: PathBuf::from(format!("/home/user/{}", name)),
+            deletion_date: String::new(),
+            mode: None,
+            parent_mode: None,
         };
+        let entries = vec![entry("a.txt"), entry("b.txt")];
 
-        // Define the expected output format.
-        let expected_text = "2024-01-01T12:00:00  /home/user/documents/test.txt <= /trash/files/test.txt";
-        // Call the `text` method and assert that the output is correct.
+        let mut writer = Vec::new();
+        assert!(confirm_restore(&mut writer, &mut "y\n".as_bytes(), &entries)?);
         assert_eq!(
-            entry.text(),
-            expected_text,
-            "The SkimItem text format should match the expected output."
+            String::from_utf8(writer)?,
+            "  /home/user/a.txt\n  /home/user/b.txt\nRestore 2 items? [Y/n]: "
         );
+        assert!(!confirm_restore(&mut Vec::new(), &mut "n\n".as_bytes(), &entries)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_entry_skim_item_text() {
+        struct TestCase {
+            trash_root: &'static str,
+            expected_label: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                trash_root: "/home/user/.local/share/Trash",
+                expected_label: "home",
+            },
+            TestCase {
+                trash_root: "/media/usb/.Trash-1000",
+                expected_label: "usb",
+            },
+            TestCase {
+                trash_root: "/mnt/backup disk/.Trash/1000",
+                expected_label: "backup disk",
+            },
+            TestCase {
+                trash_root: "/.Trash-1000",
+                expected_label: "/",
+            },
+        ];
+
+        for case in test_cases {
+            let trash_root = Path::new(case.trash_root);
+            let entry = TrashEntry {
+                trashed_path: trash_root.join("files/test.txt"),
+                info_path: trash_root.join("info/test.txt.trashinfo"),
+                original_path: PathBuf::from("/home/user/documents/test.txt"),
+                deletion_date: "2024-01-01T12:00:00".to_string(),
+                mode: None,
+                parent_mode: None,
+            };
+            assert_eq!(entry.trash_root(), trash_root);
+
+            // The label comes first; the rest keeps the `date  original <= trashed` format.
+            let expected_text = format!(
+                "[{}] 2024-01-01T12:00:00  /home/user/documents/test.txt <= {}/files/test.txt",
+                case.expected_label, case.trash_root
+            );
+            assert_eq!(entry.text(), expected_text, "trash root: {}", case.trash_root);
+        }
     }
 
     #[test]
@@ -278,9 +968,11 @@ mod tests {
             info_path,
             original_path: original_path.clone(),
             deletion_date: String::new(),
+            mode: None,
+            parent_mode: None,
         };
 
-        let restored_path = restore_item(&entry)?;
+        let restored_path = restore_item(&entry, None)?;
 
         assert_eq!(restored_path, original_path);
         // Check that the file was actually moved to the original path.
@@ -312,9 +1004,11 @@ mod tests {
             info_path: trash_root.path().join(TRASH_INFO_DIR_NAME).join("test.txt.trashinfo"),
             original_path,
             deletion_date: String::new(),
+            mode: None,
+            parent_mode: None,
         };
 
-        let result = restore_item(&entry);
+        let result = restore_item(&entry, None);
         assert!(result.is_err());
         if let Some(err) = result.err() {
             assert!(
@@ -331,6 +1025,42 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_scan_trash_dirs_skips_unreadable_info_dir() -> Result<(), AppError> {
+        let trash_roots = [tempdir()?, tempdir()?, tempdir()?];
+        for (i, trash_root) in trash_roots.iter().enumerate() {
+            let info_dir = trash_root.path().join(TRASH_INFO_DIR_NAME);
+            fs::create_dir_all(trash_root.path().join(TRASH_FILES_DIR_NAME))?;
+            fs::create_dir_all(&info_dir)?;
+            fs::write(
+                info_dir.join(format!("file{}.txt{}", i, TRASH_INFO_SUFFIX)),
+                format!(
+                    "[Trash Info]\nPath=/home/user/file{}.txt\nDeletionDate=2024-01-01T12:00:00\n",
+                    i
+                ),
+            )?;
+        }
+        let unreadable = trash_roots[1].path().join(TRASH_INFO_DIR_NAME);
+        fs::set_permissions(&unreadable, fs::Permissions::from_mode(0o000))?;
+
+        let trash_dirs: Vec<PathBuf> = trash_roots.iter().map(|root| root.path().to_path_buf()).collect();
+        let scan = scan_trash_dirs(&trash_dirs);
+        fs::set_permissions(&unreadable, fs::Permissions::from_mode(0o755))?;
+
+        let mut found: Vec<PathBuf> = scan.entries.into_iter().map(|entry| entry.original_path).collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                PathBuf::from("/home/user/file0.txt"),
+                PathBuf::from("/home/user/file2.txt")
+            ]
+        );
+        assert_eq!(scan.errors.len(), 1);
+        assert!(matches!(&scan.errors[0], AppError::Io { path, .. } if *path == unreadable));
+        Ok(())
+    }
+
     #[test]
     fn test_find_trash_entries_in_dirs() -> Result<(), AppError> {
         let trash_root = tempdir()?;
@@ -356,8 +1086,35 @@ mod tests {
         // A file that is not a .trashinfo file
         File::create(info_dir.join("not-a-trashinfo.log"))?;
 
+        // Files with the keys but a missing or wrong header are not trash info files
+        fs::write(
+            info_dir.join(format!("headerless.txt{}", TRASH_INFO_SUFFIX)),
+            "Path=/home/user/headerless.txt\nDeletionDate=2024-01-04T12:00:00\n",
+        )?;
+        fs::write(
+            info_dir.join(format!("desktop.txt{}", TRASH_INFO_SUFFIX)),
+            "[Desktop Entry]\nPath=/home/user/desktop.txt\nDeletionDate=2024-01-05T12:00:00\n",
+        )?;
+
         let trash_dirs = vec![trash_root.path().to_path_buf()];
-        let entries = find_trash_entries_in_dirs(&trash_dirs)?;
+        let scan = scan_trash_dirs(&trash_dirs);
+        let mut rejected: Vec<&Path> = scan
+            .errors
+            .iter()
+            .map(|error| match error {
+                AppError::TrashInfoParse { path, .. } => path.as_path(),
+                other => panic!("Unexpected scan error: {}", other),
+            })
+            .collect();
+        rejected.sort();
+        assert_eq!(
+            rejected,
+            [
+                info_dir.join(format!("desktop.txt{}", TRASH_INFO_SUFFIX)),
+                info_dir.join(format!("headerless.txt{}", TRASH_INFO_SUFFIX))
+            ]
+        );
+        let entries = scan.entries;
 
         assert_eq!(entries.len(), 2, "Should find exactly two valid entries");
 
@@ -386,6 +1143,659 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_copy_out_item_keeps_trash_entry() -> Result<(), AppError> {
+        let trash_dir = tempdir()?;
+        let out_dir = tempdir()?;
+        let trashed_path = trash_dir.path().join("files/project");
+        let info_path = trash_dir.path().join("info/project.trashinfo");
+        fs::create_dir_all(trashed_path.join("src"))?;
+        fs::create_dir_all(info_path.parent().unwrap())?;
+        fs::write(trashed_path.join("src/main.rs"), "fn main() {}")?;
+        File::create(&info_path)?;
+        let entry = TrashEntry {
+            trashed_path: trashed_path.clone(),
+            info_path: info_path.clone(),
+            original_path: PathBuf::from("/home/user/project"),
+            deletion_date: "2024-01-01T12:00:00".to_string(),
+            mode: None,
+            parent_mode: None,
+        };
+
+        // Into an existing directory, under the original name.
+        let copied = copy_out_item(&entry, out_dir.path(), None)?;
+        assert_eq!(copied, out_dir.path().join("project"));
+        assert_eq!(fs::read_to_string(copied.join("src/main.rs"))?, "fn main() {}");
+        assert!(trashed_path.join("src/main.rs").exists());
+        assert!(info_path.exists());
+
+        // To an explicit new path.
+        let renamed = out_dir.path().join("project-copy");
+        assert_eq!(copy_out_item(&entry, &renamed, None)?, renamed);
+
+        // An existing target is never overwritten.
+        let result = copy_out_item(&entry, out_dir.path(), None);
+        assert!(matches!(result, Err(AppError::RestoreCollision { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_item_reapplies_recorded_mode() -> Result<(), AppError> {
+        let trash_dir = tempdir()?;
+        let restore_dir = tempdir()?;
+        let trashed_path = trash_dir.path().join("files/secret.txt");
+        let info_path = trash_dir.path().join("info/secret.txt.trashinfo");
+        fs::create_dir_all(trashed_path.parent().unwrap())?;
+        fs::create_dir_all(info_path.parent().unwrap())?;
+        fs::write(&trashed_path, "secret")?;
+        // The data file no longer has the recorded mode, e.g. after being copied in with the default umask.
+        fs::set_permissions(&trashed_path, fs::Permissions::from_mode(0o644))?;
+        fs::write(
+            &info_path,
+            "[Trash Info]\nPath=/tmp/secret.txt\nDeletionDate=2024-01-01T12:00:00\nMode=0600\n",
+        )?;
+
+        let mut entries = find_trash_entries_in_dirs(&[trash_dir.path().to_path_buf()]);
+        assert_eq!(entries[0].mode, Some(0o600));
+        let original_path = restore_dir.path().join("secret.txt");
+        entries[0].original_path = original_path.clone();
+
+        // Restore across filesystems, through the copy path.
+        let cross_device = |_: &Path, _: &Path| Err(io::Error::from(ErrorKind::CrossesDevices));
+        restore_item_with(&entries[0], None, cross_device)?;
+        assert_eq!(fs::read_to_string(&original_path)?, "secret");
+        assert!(!trashed_path.exists(), "The copy path should remove the trashed item");
+        assert_eq!(fs::metadata(&original_path)?.permissions().mode() & 0o777, 0o600);
+
+        // Info files without the key (e.g. from other tools) are still read.
+        fs::write(&trashed_path, "secret")?;
+        fs::write(
+            &info_path,
+            "[Trash Info]\nPath=/tmp/secret.txt\nDeletionDate=2024-01-01T12:00:00\n",
+        )?;
+        let entries = find_trash_entries_in_dirs(&[trash_dir.path().to_path_buf()]);
+        assert_eq!(entries[0].mode, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_item_recreates_missing_parents() -> Result<(), AppError> {
+        struct TestCase {
+            parent_mode: Option<u32>,
+            expected_parent_mode: u32,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                parent_mode: None,
+                expected_parent_mode: RECREATED_DIR_MODE,
+            },
+            TestCase {
+                parent_mode: Some(0o750),
+                expected_parent_mode: 0o750,
+            },
+            TestCase {
+                // Applied only after the move, since it does not even let the owner write.
+                parent_mode: Some(0o555),
+                expected_parent_mode: 0o555,
+            },
+        ];
+
+        for case in test_cases {
+            let trash_dir = tempdir()?;
+            let restore_root = tempdir()?;
+            fs::set_permissions(restore_root.path(), fs::Permissions::from_mode(0o711))?;
+            let trashed_path = trash_dir.path().join("files/notes.txt");
+            let info_path = trash_dir.path().join("info/notes.txt.trashinfo");
+            fs::create_dir_all(trashed_path.parent().unwrap())?;
+            fs::create_dir_all(info_path.parent().unwrap())?;
+            fs::write(&trashed_path, "notes")?;
+            fs::write(
+                &info_path,
+                "[Trash Info]\nPath=/tmp/notes.txt\nDeletionDate=2024-01-01T12:00:00\n",
+            )?;
+
+            let grandparent = restore_root.path().join("projects");
+            let parent = grandparent.join("private");
+            let entry = TrashEntry {
+                trashed_path,
+                info_path,
+                original_path: parent.join("notes.txt"),
+                deletion_date: "2024-01-01T12:00:00".to_string(),
+                mode: None,
+                parent_mode: case.parent_mode,
+            };
+            restore_item(&entry, None)?;
+
+            let mode_of = |path: &Path| fs::metadata(path).map(|metadata| metadata.permissions().mode() & 0o7777);
+            assert_eq!(fs::read_to_string(parent.join("notes.txt"))?, "notes");
+            assert_eq!(mode_of(&parent)?, case.expected_parent_mode, "{:?}", case.parent_mode);
+            // Ancestors whose mode was never recorded stay private, whatever the umask.
+            assert_eq!(mode_of(&grandparent)?, RECREATED_DIR_MODE);
+            assert!(mode_of(&grandparent)? & !0o755 == 0);
+            // An existing ancestor is left as it was.
+            assert_eq!(mode_of(restore_root.path())?, 0o711);
+            fs::set_permissions(&parent, fs::Permissions::from_mode(0o700))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_by_path_with_newline_in_name() -> Result<(), AppError> {
+        let trash_dir = tempdir()?;
+        let restore_root = tempdir()?;
+        let restore_dir = restore_root.path().canonicalize()?;
+        let original_path = restore_dir.join("line1\nline2.txt");
+        let trashed_path = trash_dir.path().join("files/line1\nline2.txt");
+        fs::create_dir_all(trashed_path.parent().unwrap())?;
+        fs::create_dir_all(trash_dir.path().join("info"))?;
+        fs::write(&trashed_path, "data")?;
+        fs::write(
+            trash_dir.path().join("info/line1\nline2.txt.trashinfo"),
+            format!(
+                "[Trash Info]\nPath={}\nDeletionDate=2024-01-01T12:00:00\n",
+                PathEncoding::default().encode(original_path.as_os_str())
+            ),
+        )?;
+        // A look-alike entry that a line-based match would confuse with the real one.
+        fs::write(
+            trash_dir.path().join("info/line1.trashinfo"),
+            format!(
+                "[Trash Info]\nPath={}\nDeletionDate=2024-01-02T12:00:00\n",
+                PathEncoding::default().encode(restore_dir.join("line1").as_os_str())
+            ),
+        )?;
+
+        let entries = find_trash_entries_in_dirs(&[trash_dir.path().to_path_buf()]);
+        let argument = original_path.to_str().unwrap();
+        let entry = find_entry_for_path(&entries, &absolute_original_path(Path::new(argument))).unwrap();
+        assert_eq!(entry.trashed_path, trashed_path);
+
+        assert_eq!(restore_item(entry, None)?, original_path);
+        assert_eq!(fs::read_to_string(&original_path)?, "data");
+        assert!(find_entry_for_path(&entries, &restore_dir.join("missing")).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_entry_for_name() {
+        let entry = |path: &str, date: &str| TrashEntry {
+            trashed_path: PathBuf::new(),
+            info_path: PathBuf::new(),
+            original_path: PathBuf::from(path),
+            deletion_date: date.to_string(),
+            mode: None,
+            parent_mode: None,
+        };
+        let entries = vec![
+            entry("/home/user/report.pdf", "2024-01-01T10:00:00"),
+            entry("/home/user/a/notes.txt", "2024-01-01T10:00:00"),
+            entry("/home/user/b/notes.txt", "2024-02-01T10:00:00"),
+        ];
+
+        let found = find_entry_for_name(&entries, "report.pdf").unwrap();
+        assert_eq!(found.original_path, Path::new("/home/user/report.pdf"));
+        assert!(find_entry_for_name(&entries, "report")
+            .unwrap_err()
+            .contains("No trashed item"));
+
+        let ambiguous = find_entry_for_name(&entries, "notes.txt").unwrap_err();
+        let newest = ambiguous.find("2024-02-01T10:00:00  /home/user/b/notes.txt").unwrap();
+        let oldest = ambiguous.find("2024-01-01T10:00:00  /home/user/a/notes.txt").unwrap();
+        assert!(
+            newest < oldest,
+            "Candidates should be listed newest first: {}",
+            ambiguous
+        );
+    }
+
+    #[test]
+    fn test_handle_restore_glob() -> Result<(), AppError> {
+        let trash_dir = tempdir()?;
+        let restore_root = tempdir()?;
+        let restore_dir = restore_root.path().canonicalize()?;
+        fs::create_dir_all(trash_dir.path().join("files"))?;
+        fs::create_dir_all(trash_dir.path().join("info"))?;
+        for name in ["app.log", "db.log", "busy.log", "notes.txt", "log"] {
+            fs::write(trash_dir.path().join("files").join(name), name)?;
+            fs::write(
+                trash_dir.path().join(format!("info/{}.trashinfo", name)),
+                format!(
+                    "[Trash Info]\nPath={}\nDeletionDate=2024-01-01T12:00:00\n",
+                    PathEncoding::default().encode(restore_dir.join(name).as_os_str())
+                ),
+            )?;
+        }
+        // Its original path is taken again, so it stays in the trash.
+        fs::write(restore_dir.join("busy.log"), "new")?;
+
+        let opts = RestoreOptions {
+            all_trash: false,
+            trash_dir: Some(trash_dir.path().to_path_buf()),
+            porcelain: false,
+            fuzzy_pair: false,
+            limit_rate: None,
+            copy_out: None,
+            print0: false,
+            confirm: false,
+        };
+        let result = handle_restore_glob(&["*.log".to_string()], opts);
+
+        assert!(matches!(result, Err(AppError::Ignorable)), "{:?}", result);
+        assert_eq!(fs::read_to_string(restore_dir.join("app.log"))?, "app.log");
+        assert_eq!(fs::read_to_string(restore_dir.join("db.log"))?, "db.log");
+        assert_eq!(fs::read_to_string(restore_dir.join("busy.log"))?, "new");
+        for name in ["busy.log", "notes.txt", "log"] {
+            assert!(
+                trash_dir.path().join("files").join(name).exists(),
+                "{} stays in the trash",
+                name
+            );
+        }
+        assert!(!restore_dir.join("notes.txt").exists() && !restore_dir.join("log").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_handle_restore_glob_restores_newest_of_same_path() -> Result<(), AppError> {
+        let trash_dir = tempdir()?;
+        let restore_root = tempdir()?;
+        let restore_dir = restore_root.path().canonicalize()?;
+        fs::create_dir_all(trash_dir.path().join("files"))?;
+        fs::create_dir_all(trash_dir.path().join("info"))?;
+        // Both were trashed from the same path; the trash listing order does not follow the dates.
+        for (name, date) in [("app.log", "2024-05-01T12:00:00"), ("app.2.log", "2024-01-01T12:00:00")] {
+            fs::write(trash_dir.path().join("files").join(name), date)?;
+            fs::write(
+                trash_dir.path().join(format!("info/{}.trashinfo", name)),
+                format!(
+                    "[Trash Info]\nPath={}\nDeletionDate={}\n",
+                    PathEncoding::default().encode(restore_dir.join("app.log").as_os_str()),
+                    date
+                ),
+            )?;
+        }
+
+        let opts = RestoreOptions {
+            all_trash: false,
+            trash_dir: Some(trash_dir.path().to_path_buf()),
+            porcelain: false,
+            fuzzy_pair: false,
+            limit_rate: None,
+            copy_out: None,
+            print0: false,
+            confirm: false,
+        };
+        let result = handle_restore_glob(&["*.log".to_string()], opts);
+
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(fs::read_to_string(restore_dir.join("app.log"))?, "2024-05-01T12:00:00");
+        assert!(
+            trash_dir.path().join("files/app.2.log").exists(),
+            "the older one stays in the trash"
+        );
+        assert!(trash_dir.path().join("info/app.2.log.trashinfo").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_raw_encoded_path_with_percent() -> Result<(), AppError> {
+        let trash_dir = tempdir()?;
+        let restore_root = tempdir()?;
+        let restore_dir = restore_root.path().canonicalize()?;
+        fs::create_dir_all(trash_dir.path().join("files"))?;
+        fs::create_dir_all(trash_dir.path().join("info"))?;
+        let original_path = restore_dir.join("100%.txt");
+        fs::write(trash_dir.path().join("files/100%.txt"), "done")?;
+        fs::write(
+            trash_dir.path().join("info/100%.txt.trashinfo"),
+            format!(
+                "[Trash Info]\nPath={}\nDeletionDate=2024-01-01T12:00:00\n",
+                PathEncoding::Raw.encode(original_path.as_os_str())
+            ),
+        )?;
+
+        let opts = RestoreOptions {
+            all_trash: false,
+            trash_dir: Some(trash_dir.path().to_path_buf()),
+            porcelain: false,
+            fuzzy_pair: false,
+            limit_rate: None,
+            copy_out: None,
+            print0: false,
+            confirm: false,
+        };
+        let result = handle_restore_glob(&["100%.txt".to_string()], opts);
+
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(fs::read_to_string(&original_path)?, "done");
+        assert!(!trash_dir.path().join("info/100%.txt.trashinfo").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_entries_by_glob() {
+        let entry = |path: &str| TrashEntry {
+            trashed_path: PathBuf::new(),
+            info_path: PathBuf::new(),
+            original_path: PathBuf::from(path),
+            deletion_date: "2024-01-01T10:00:00".to_string(),
+            mode: None,
+            parent_mode: None,
+        };
+        let entries = vec![
+            entry("/home/user/build.log"),
+            entry("/home/user/logs/report.pdf"),
+            entry("/home/user/photo [1].jpg"),
+            entry("/home/user/Notes.TXT"),
+        ];
+
+        struct TestCase {
+            patterns: &'static [&'static str],
+            expected: Vec<&'static str>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                patterns: &["*.log"],
+                expected: vec!["/home/user/build.log"],
+            },
+            TestCase {
+                // Only the file name is matched, never the directories above it.
+                patterns: &["logs*"],
+                expected: vec![],
+            },
+            TestCase {
+                patterns: &["*.pdf", "photo?[[]1].jpg"],
+                expected: vec!["/home/user/logs/report.pdf", "/home/user/photo [1].jpg"],
+            },
+            TestCase {
+                patterns: &["*.txt"],
+                expected: vec![],
+            },
+        ];
+
+        for case in test_cases {
+            let patterns: Vec<Pattern> = case.patterns.iter().map(|p| Pattern::new(p).unwrap()).collect();
+            let selected: Vec<PathBuf> = select_entries_by_glob(&entries, &patterns)
+                .into_iter()
+                .map(|entry| entry.original_path)
+                .collect();
+            let expected: Vec<PathBuf> = case.expected.iter().map(PathBuf::from).collect();
+            assert_eq!(selected, expected, "{:?}", case.patterns);
+        }
+    }
+
+    #[test]
+    fn test_find_entry_to_put_back() {
+        let entry = |name: &str, path: &str, date: &str| TrashEntry {
+            trashed_path: PathBuf::from(format!("/trash/files/{}", name)),
+            info_path: PathBuf::from(format!("/trash/info/{}.trashinfo", name)),
+            original_path: PathBuf::from(path),
+            deletion_date: date.to_string(),
+            mode: None,
+            parent_mode: None,
+        };
+        let entries = vec![
+            entry("report.pdf", "/home/user/report.pdf", "2024-01-01T10:00:00"),
+            entry("notes.txt", "/home/user/notes.txt", "2024-01-01T10:00:00"),
+            entry("notes.2.txt", "/home/user/notes.txt", "2024-02-01T10:00:00"),
+        ];
+
+        let found = find_entry_to_put_back(&entries, Path::new("/home/user/report.pdf")).unwrap();
+        assert_eq!(found.trashed_path, Path::new("/trash/files/report.pdf"));
+
+        // Only the whole path matches, not a prefix or the file name alone.
+        for missing in ["/home/user", "/home/report.pdf", "report.pdf"] {
+            let message = find_entry_to_put_back(&entries, Path::new(missing)).unwrap_err();
+            assert!(message.starts_with("No trashed item found"), "{}: {}", missing, message);
+        }
+
+        let ambiguous = find_entry_to_put_back(&entries, Path::new("/home/user/notes.txt")).unwrap_err();
+        assert!(ambiguous.contains("was trashed 2 times"), "{}", ambiguous);
+        let newest = ambiguous.find("2024-02-01T10:00:00  /trash/files/notes.2.txt").unwrap();
+        let oldest = ambiguous.find("2024-01-01T10:00:00  /trash/files/notes.txt").unwrap();
+        assert!(
+            newest < oldest,
+            "Candidates should be listed newest first: {}",
+            ambiguous
+        );
+    }
+
+    #[test]
+    fn test_find_latest_entry() {
+        let entry = |name: &str, date: &str| TrashEntry {
+            trashed_path: PathBuf::from(format!("/trash/files/{}", name)),
+            info_path: PathBuf::from(format!("/trash/info/{}.trashinfo", name)),
+            original_path: PathBuf::from(format!("/home/user/{}", name)),
+            deletion_date: date.to_string(),
+            mode: None,
+            parent_mode: None,
+        };
+        assert!(find_latest_entry(&[]).is_none());
+
+        let entries = vec![
+            entry("old.txt", "2024-01-01T10:00:00"),
+            entry("broken.txt", "yesterday"),
+            entry("new-b.txt", "2024-03-01T09:30:00"),
+            entry("new-a.txt", "2024-03-01T09:30:00"),
+        ];
+        // Same second and no info files to compare: the trashed path decides, in either order.
+        let latest = find_latest_entry(&entries).unwrap().trashed_path.clone();
+        assert_eq!(latest, Path::new("/trash/files/new-b.txt"));
+        let reversed: Vec<TrashEntry> = entries.into_iter().rev().collect();
+        assert_eq!(find_latest_entry(&reversed).unwrap().trashed_path, latest);
+    }
+
+    #[test]
+    fn test_parse_deletion_date_accepts_foreign_formats() {
+        let local = |value: &str| {
+            DateTime::parse_from_rfc3339(value)
+                .unwrap()
+                .with_timezone(&Local)
+                .naive_local()
+        };
+        struct TestCase {
+            input: &'static str,
+            expected: Option<NaiveDateTime>,
+        }
+        let test_cases = vec![
+            TestCase {
+                input: "2024-01-01T12:00:00",
+                expected: NaiveDateTime::parse_from_str("2024-01-01T12:00:00", TRASH_INFO_DATE_FORMAT).ok(),
+            },
+            TestCase {
+                input: "2024-01-01T12:00:00.250",
+                expected: NaiveDateTime::parse_from_str("2024-01-01T12:00:00.250", "%Y-%m-%dT%H:%M:%S%.f").ok(),
+            },
+            TestCase {
+                input: "2024-01-01T12:00:00+09:00",
+                expected: Some(local("2024-01-01T03:00:00Z")),
+            },
+            TestCase {
+                input: "2024-01-01T12:00:00+0900",
+                expected: Some(local("2024-01-01T03:00:00Z")),
+            },
+            TestCase {
+                input: "2024-01-01T03:00:00Z",
+                expected: Some(local("2024-01-01T03:00:00Z")),
+            },
+            TestCase {
+                input: "2024-01-01T03:00:00.5z",
+                expected: Some(local("2024-01-01T03:00:00.5Z")),
+            },
+            TestCase {
+                input: "last tuesday",
+                expected: None,
+            },
+        ];
+        for case in test_cases {
+            assert_eq!(
+                parse_deletion_date(case.input),
+                case.expected,
+                "Failed on {}",
+                case.input
+            );
+        }
+    }
+
+    #[test]
+    fn test_restored_message() -> Result<(), AppError> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("file.txt");
+        File::create(&file_path)?;
+        let dir_path = dir.path().join("project");
+        fs::create_dir_all(dir_path.join("src"))?;
+        File::create(dir_path.join("a.txt"))?;
+        File::create(dir_path.join("src/b.txt"))?;
+        let single_path = dir.path().join("single");
+        fs::create_dir(&single_path)?;
+        File::create(single_path.join("only"))?;
+
+        assert_eq!(
+            restored_message(&file_path),
+            format!("Restored: {}", file_path.display())
+        );
+        assert_eq!(
+            restored_message(&dir_path),
+            format!("Restored directory (2 items): {}", dir_path.display())
+        );
+        assert_eq!(
+            restored_message(&single_path),
+            format!("Restored directory (1 item): {}", single_path.display())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("report.pdf", "report.pdf"), 0);
+        assert_eq!(edit_distance("report.pdf", "report1.pdf"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_pair_mangled_entries() -> Result<(), AppError> {
+        let trash_root = tempdir()?;
+        let files_dir = trash_root.path().join(TRASH_FILES_DIR_NAME);
+        let info_dir = trash_root.path().join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(&files_dir)?;
+        fs::create_dir_all(&info_dir)?;
+
+        // The data file was renamed by a sync tool, but its info file was not.
+        fs::write(
+            info_dir.join("report.pdf.trashinfo"),
+            "[Trash Info]\nPath=/home/user/report.pdf\nDeletionDate=2024-01-01T12:00:00\n",
+        )?;
+        File::create(files_dir.join("report.pdf.sync-conflict"))?;
+        // A correctly paired entry must not be stolen.
+        fs::write(
+            info_dir.join("report.pdx.trashinfo"),
+            "[Trash Info]\nPath=/home/user/report.pdx\nDeletionDate=2024-01-01T12:00:00\n",
+        )?;
+        File::create(files_dir.join("report.pdx"))?;
+
+        let mut entries = find_trash_entries_in_dirs(&[trash_root.path().to_path_buf()]);
+        pair_mangled_entries(&mut entries);
+
+        let report = entries
+            .iter()
+            .find(|e| e.original_path == Path::new("/home/user/report.pdf"))
+            .unwrap();
+        assert_eq!(report.trashed_path, files_dir.join("report.pdf.sync-conflict"));
+        let other = entries
+            .iter()
+            .find(|e| e.original_path == Path::new("/home/user/report.pdx"))
+            .unwrap();
+        assert_eq!(other.trashed_path, files_dir.join("report.pdx"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_by_copy_across_devices() -> Result<(), AppError> {
+        // Two tempdirs stand in for two filesystems; the copy routine is injected, so the
+        // fallback path runs without needing an actual second device.
+        let trash_fs = tempdir()?;
+        let home_fs = tempdir()?;
+        let src = trash_fs.path().join("project");
+        fs::create_dir_all(src.join("sub"))?;
+        fs::write(src.join("sub/notes.txt"), "notes")?;
+        fs::set_permissions(src.join("sub/notes.txt"), fs::Permissions::from_mode(0o640))?;
+        let mtime = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_234_567_890);
+        File::options()
+            .write(true)
+            .open(src.join("sub/notes.txt"))?
+            .set_modified(mtime)?;
+
+        let dst = home_fs.path().join("project");
+        move_by_copy(&src, &dst, |src, dst| {
+            copy_recursive(src, dst, &mut Throttle::new(None))
+        })?;
+        assert!(!src.exists());
+        let restored = fs::metadata(dst.join("sub/notes.txt"))?;
+        assert_eq!(fs::read_to_string(dst.join("sub/notes.txt"))?, "notes");
+        assert_eq!(restored.permissions().mode() & 0o777, 0o640);
+        assert_eq!(restored.modified()?, mtime);
+
+        // A copy that fails midway leaves neither a partial tree nor a damaged source.
+        let dst_again = home_fs.path().join("project-again");
+        let result = move_by_copy(&dst, &dst_again, |src, dst| {
+            fs::create_dir_all(dst.join("sub"))?;
+            fs::copy(src.join("sub/notes.txt"), dst.join("sub/notes.txt"))?;
+            Err(AppError::Message("disk full".to_string()))
+        });
+        assert!(matches!(result, Err(AppError::Message(_))));
+        assert!(dst_again.symlink_metadata().is_err());
+        assert_eq!(fs::read_to_string(dst.join("sub/notes.txt"))?, "notes");
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_item_across_devices_with_fifo() -> Result<(), AppError> {
+        use std::ffi::CString;
+        use std::os::unix::fs::FileTypeExt;
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let trash_dir = tempdir()?;
+        let restore_dir = tempdir()?;
+        let trashed_path = trash_dir.path().join("files/project");
+        let info_path = trash_dir.path().join("info/project.trashinfo");
+        fs::create_dir_all(&trashed_path)?;
+        fs::create_dir_all(info_path.parent().unwrap())?;
+        fs::write(trashed_path.join("notes.txt"), "notes")?;
+        let fifo = CString::new(trashed_path.join("pipe").as_os_str().as_bytes()).expect("no NUL in a temp path");
+        // SAFETY: `fifo` is a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o600) }, 0, "mkfifo failed");
+        File::create(&info_path)?;
+        let original_path = restore_dir.path().join("project");
+        let entry = TrashEntry {
+            trashed_path: trashed_path.clone(),
+            info_path,
+            original_path: original_path.clone(),
+            deletion_date: String::new(),
+            mode: None,
+            parent_mode: None,
+        };
+
+        // Reading the FIFO would block forever, so the restore runs under a timeout.
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let cross_device = |_: &Path, _: &Path| Err(io::Error::from(ErrorKind::CrossesDevices));
+            let _ = sender.send(restore_item_with(&entry, None, cross_device));
+        });
+        receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("restoring a FIFO must not block")?;
+
+        assert!(!trashed_path.exists());
+        assert_eq!(fs::read_to_string(original_path.join("notes.txt"))?, "notes");
+        assert!(fs::symlink_metadata(original_path.join("pipe"))?.file_type().is_fifo());
+        Ok(())
+    }
+
     #[test]
     fn test_restore_item_fails_if_trashed_file_is_missing() -> Result<(), AppError> {
         let trash_root = tempdir()?;
@@ -401,11 +1811,17 @@ mod tests {
         let entry = TrashEntry {
             trashed_path: trash_root.path().join(TRASH_FILES_DIR_NAME).join("missing_file.txt"),
             info_path,
-            original_path: original_root.path().join("missing_file.txt"),
+            original_path: original_root.path().join("gone").join("missing_file.txt"),
             deletion_date: String::new(),
+            mode: None,
+            parent_mode: None,
         };
 
-        let result = restore_item(&entry);
+        let result = restore_item(&entry, None);
+        assert!(
+            !original_root.path().join("gone").exists(),
+            "A failed restore should not recreate the original parent"
+        );
         assert!(
             result.is_err(),
             "Expected an error because the source file in trash is missing"
@@ -440,6 +1856,8 @@ mod tests {
             info_path: info_path.clone(),
             original_path: original_root.path().join("test.txt"),
             deletion_date: String::new(),
+            mode: None,
+            parent_mode: None,
         };
 
         // Make the `info` directory read-only to prevent `remove_file` from succeeding.
@@ -447,7 +1865,7 @@ mod tests {
         perms.set_mode(0o555); // r-xr-xr-x
         fs::set_permissions(info_dir, perms)?;
 
-        let result = restore_item(&entry);
+        let result = restore_item(&entry, None);
 
         assert!(result.is_ok(), "Restore should succeed even if info file cleanup fails");
         // The original file should be restored.
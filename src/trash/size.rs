@@ -0,0 +1,186 @@
+use std::fs;
+use std::path::Path;
+
+use crate::trash::error::AppError;
+use crate::trash::walk::{WalkEvent, Walker};
+
+/// Parses a human-friendly size such as `500`, `10K`, `1.5M`, `2GiB` or `3gb` into bytes.
+/// Units are binary multiples (1K = 1024 bytes), and a trailing `B`/`iB` is optional.
+pub fn parse_size(input: &str) -> Result<u64, AppError> {
+    let trimmed = input.trim();
+    let split_at = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split_at);
+
+    let value: f64 = number
+        .parse()
+        .map_err(|_| AppError::Message(format!("Invalid size '{}'", input)))?;
+    let unit = unit.trim().to_ascii_lowercase();
+    let unit = unit
+        .strip_suffix("ib")
+        .or_else(|| unit.strip_suffix('b'))
+        .unwrap_or(&unit);
+    let multiplier: u64 = match unit {
+        "" => 1,
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        "t" => 1 << 40,
+        _ => return Err(AppError::Message(format!("Invalid size unit in '{}'", input))),
+    };
+    Ok((value * multiplier as f64) as u64)
+}
+
+/// Returns the total size in bytes of `path`, recursing into directories.
+/// Symbolic links are not followed, and unreadable subdirectories are skipped with a warning.
+pub fn dir_size(path: &Path) -> u64 {
+    tree_summary(path, u64::MAX).total_size
+}
+
+/// The number of items below a path and their total size in bytes.
+#[derive(Debug, PartialEq)]
+pub(crate) struct TreeSummary {
+    pub(crate) item_count: u64,
+    pub(crate) total_size: u64,
+    /// The walk stopped after `max_items` items, so both numbers are lower bounds.
+    pub(crate) truncated: bool,
+}
+
+/// Counts the items below `path` (not `path` itself) and sums their sizes, stopping
+/// early once `max_items` items have been seen so a huge tree is not walked in full.
+/// Symbolic links are not followed, and unreadable subdirectories are skipped with a warning.
+pub(crate) fn tree_summary(path: &Path, max_items: u64) -> TreeSummary {
+    tree_summary_reporting(path, max_items, |e| eprintln!("warning: {}. Skipping.", e))
+}
+
+/// Like `tree_summary`, but each unreadable subdirectory is handed to `report` instead of being
+/// printed, for callers that cannot write to stderr, such as the restore UI's preview.
+pub(crate) fn tree_summary_reporting(path: &Path, max_items: u64, mut report: impl FnMut(AppError)) -> TreeSummary {
+    let mut summary = TreeSummary {
+        item_count: 0,
+        total_size: 0,
+        truncated: false,
+    };
+    if fs::symlink_metadata(path).is_err() {
+        return summary;
+    }
+    for event in Walker::new(path) {
+        match event {
+            Ok(WalkEvent::Visit(entry)) => {
+                if !entry.metadata.is_dir() {
+                    summary.total_size += entry.metadata.len();
+                }
+                if entry.path == path {
+                    continue;
+                }
+                if summary.item_count == max_items {
+                    summary.truncated = true;
+                    break;
+                }
+                summary.item_count += 1;
+            }
+            Ok(WalkEvent::Leave(_)) => {}
+            Err(e) => report(e),
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_size() {
+        struct TestCase {
+            input: &'static str,
+            expected: u64,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                input: "0",
+                expected: 0,
+            },
+            TestCase {
+                input: "512",
+                expected: 512,
+            },
+            TestCase {
+                input: "10K",
+                expected: 10 * 1024,
+            },
+            TestCase {
+                input: "10M",
+                expected: 10 * 1024 * 1024,
+            },
+            TestCase {
+                input: "1.5G",
+                expected: 3 * 512 * 1024 * 1024,
+            },
+            TestCase {
+                input: "2GiB",
+                expected: 2 * 1024 * 1024 * 1024,
+            },
+            TestCase {
+                input: "3mb",
+                expected: 3 * 1024 * 1024,
+            },
+        ];
+
+        for case in test_cases {
+            assert_eq!(
+                parse_size(case.input).unwrap(),
+                case.expected,
+                "Failed on: {}",
+                case.input
+            );
+        }
+
+        assert!(parse_size("ten").is_err());
+        assert!(parse_size("10X").is_err());
+        assert!(parse_size("").is_err());
+    }
+
+    #[test]
+    fn test_dir_size_sums_nested_files() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let nested = root.path().join("a/b");
+        fs::create_dir_all(&nested)?;
+        fs::write(root.path().join("a/one.bin"), vec![0u8; 100])?;
+        fs::write(nested.join("two.bin"), vec![0u8; 250])?;
+        File::create(nested.join("empty.bin"))?;
+
+        assert_eq!(dir_size(root.path()), 350);
+        assert_eq!(dir_size(&nested.join("two.bin")), 250);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tree_summary_stops_at_max_items() -> Result<(), AppError> {
+        let root = tempdir()?;
+        fs::create_dir(root.path().join("sub"))?;
+        for name in ["a", "b", "sub/c"] {
+            fs::write(root.path().join(name), vec![0u8; 10])?;
+        }
+
+        let full = tree_summary(root.path(), 100);
+        assert_eq!(
+            full,
+            TreeSummary {
+                item_count: 4,
+                total_size: 30,
+                truncated: false,
+            }
+        );
+        let partial = tree_summary(root.path(), 2);
+        assert_eq!(partial.item_count, 2);
+        assert!(partial.truncated);
+        assert_eq!(tree_summary(&root.path().join("missing"), 100).item_count, 0);
+        Ok(())
+    }
+}
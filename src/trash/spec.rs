@@ -2,11 +2,17 @@
 pub const TRASH_INFO_HEADER: &str = "[Trash Info]";
 pub const TRASH_INFO_PATH_KEY: &str = "Path";
 pub const TRASH_INFO_DATE_KEY: &str = "DeletionDate";
+/// Extension key (not part of the spec) holding the original permission bits in octal.
+pub const TRASH_INFO_MODE_KEY: &str = "Mode";
+/// Extension key (not part of the spec) holding the permission bits of the item's parent directory in octal.
+pub const TRASH_INFO_PARENT_MODE_KEY: &str = "ParentMode";
 pub const TRASH_INFO_EXTENSION: &str = "trashinfo";
 pub const TRASH_INFO_SUFFIX: &str = ".trashinfo";
 pub const TRASH_INFO_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
 pub const TRASH_FILES_DIR_NAME: &str = "files";
 pub const TRASH_INFO_DIR_NAME: &str = "info";
+/// Cache of the sizes of trashed directories, in the trash root (spec version 1.0).
+pub const DIRECTORY_SIZES_FILE_NAME: &str = "directorysizes";
 
 #[cfg(test)]
 mod tests {
@@ -17,10 +23,13 @@ mod tests {
         assert_eq!(TRASH_INFO_HEADER, "[Trash Info]");
         assert_eq!(TRASH_INFO_PATH_KEY, "Path");
         assert_eq!(TRASH_INFO_DATE_KEY, "DeletionDate");
+        assert_eq!(TRASH_INFO_MODE_KEY, "Mode");
+        assert_eq!(TRASH_INFO_PARENT_MODE_KEY, "ParentMode");
         assert_eq!(TRASH_INFO_EXTENSION, "trashinfo");
         assert_eq!(TRASH_INFO_SUFFIX, ".trashinfo");
         assert_eq!(TRASH_INFO_DATE_FORMAT, "%Y-%m-%dT%H:%M:%S");
         assert_eq!(TRASH_FILES_DIR_NAME, "files");
         assert_eq!(TRASH_INFO_DIR_NAME, "info");
+        assert_eq!(DIRECTORY_SIZES_FILE_NAME, "directorysizes");
     }
 }
@@ -0,0 +1,303 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use humansize::{format_size, BINARY};
+
+use crate::trash::error::AppError;
+use crate::trash::locations::get_target_trash_dirs;
+use crate::trash::porcelain::write_record;
+use crate::trash::restoring::scan_trash_dirs;
+use crate::trash::size::dir_size;
+use crate::trash::spec::TRASH_FILES_DIR_NAME;
+
+/// How often the watch loop checks for Ctrl-C while waiting for the next redraw.
+const INTERRUPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Default)]
+pub struct StatsOptions {
+    pub all_trash: bool,
+    pub porcelain: bool,
+    /// When set, redraw the summary at this interval until interrupted.
+    pub watch: Option<Duration>,
+}
+
+#[derive(Default)]
+pub struct DiskUsageOptions {
+    pub all_trash: bool,
+    /// Print exact byte counts instead of e.g. `1.2 MiB`.
+    pub bytes: bool,
+}
+
+/// The item count and total size of one trash directory.
+#[derive(Debug, PartialEq)]
+struct TrashStats {
+    trash_dir: PathBuf,
+    item_count: usize,
+    total_size: u64,
+}
+
+/// Prints the item count and total size of each trash directory, once or repeatedly with `--watch`.
+pub fn handle_stats<W: Write>(writer: &mut W, opts: StatsOptions) -> Result<(), AppError> {
+    let Some(interval) = opts.watch else {
+        let stats = collect_stats(&get_target_trash_dirs(opts.all_trash)?);
+        write_stats(writer, &stats, opts.porcelain)?;
+        writer.flush()?;
+        return Ok(());
+    };
+
+    install_interrupt_handler();
+    while !INTERRUPTED.load(Ordering::Relaxed) {
+        let stats = collect_stats(&get_target_trash_dirs(opts.all_trash)?);
+        // Clear the screen and move the cursor home, like `watch`.
+        write!(writer, "\x1B[2J\x1B[H")?;
+        if !opts.porcelain {
+            writeln!(
+                writer,
+                "Every {}s: trash stats    {}\n",
+                interval.as_secs_f64(),
+                Local::now().format("%Y-%m-%d %H:%M:%S")
+            )?;
+        }
+        write_stats(writer, &stats, opts.porcelain)?;
+        writer.flush()?;
+
+        let next_redraw = Instant::now() + interval;
+        while !INTERRUPTED.load(Ordering::Relaxed) && Instant::now() < next_redraw {
+            thread::sleep(INTERRUPT_POLL_INTERVAL.min(next_redraw.saturating_duration_since(Instant::now())));
+        }
+    }
+    Ok(())
+}
+
+/// Prints the number of entries in the trash directories as a single integer, for status bars
+/// and prompts. Only items with a readable info file count, as in the listing; nothing else is
+/// printed, not even warnings about unreadable ones.
+pub fn handle_count<W: Write>(writer: &mut W, all_trash: bool) -> Result<(), AppError> {
+    write_count(writer, &get_target_trash_dirs(all_trash)?)
+}
+
+fn write_count<W: Write>(writer: &mut W, trash_dirs: &[PathBuf]) -> Result<(), AppError> {
+    writeln!(writer, "{}", scan_trash_dirs(trash_dirs).entries.len())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Prints how much space each trash directory uses, like `du -c`: one `SIZE<TAB>DIR` line per
+/// trash and a `SIZE<TAB>total` line, without looking at the individual items.
+pub fn handle_disk_usage<W: Write>(writer: &mut W, opts: DiskUsageOptions) -> Result<(), AppError> {
+    write_disk_usage(writer, &get_target_trash_dirs(opts.all_trash)?, opts.bytes)
+}
+
+fn write_disk_usage<W: Write>(writer: &mut W, trash_dirs: &[PathBuf], bytes: bool) -> Result<(), AppError> {
+    let format = |size: u64| {
+        if bytes {
+            size.to_string()
+        } else {
+            format_size(size, BINARY)
+        }
+    };
+    let mut total = 0;
+    for trash_dir in trash_dirs {
+        let size = dir_size(&trash_dir.join(TRASH_FILES_DIR_NAME));
+        total += size;
+        writeln!(writer, "{}\t{}", format(size), trash_dir.display())?;
+    }
+    writeln!(writer, "{}\ttotal", format(total))?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Makes Ctrl-C end the watch loop instead of killing the process mid-redraw.
+fn install_interrupt_handler() {
+    extern "C" fn on_interrupt(_: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::Relaxed);
+    }
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+fn collect_stats(trash_dirs: &[PathBuf]) -> Vec<TrashStats> {
+    trash_dirs.iter().map(|trash_dir| trash_stats(trash_dir)).collect()
+}
+
+fn trash_stats(trash_dir: &Path) -> TrashStats {
+    let files_dir = trash_dir.join(TRASH_FILES_DIR_NAME);
+    let item_count = fs::read_dir(&files_dir).map(|entries| entries.count()).unwrap_or(0);
+    TrashStats {
+        trash_dir: trash_dir.to_path_buf(),
+        item_count,
+        total_size: dir_size(&files_dir),
+    }
+}
+
+fn write_stats<W: Write>(writer: &mut W, stats: &[TrashStats], porcelain: bool) -> Result<(), AppError> {
+    if porcelain {
+        for s in stats {
+            write_record(
+                writer,
+                &[
+                    &s.trash_dir.display().to_string(),
+                    &s.item_count.to_string(),
+                    &s.total_size.to_string(),
+                ],
+            )?;
+        }
+        return Ok(());
+    }
+
+    for s in stats {
+        writeln!(
+            writer,
+            "{}: {} items, {}",
+            s.trash_dir.display(),
+            s.item_count,
+            format_size(s.total_size, BINARY)
+        )?;
+    }
+    if stats.len() > 1 {
+        let item_count: usize = stats.iter().map(|s| s.item_count).sum();
+        let total_size: u64 = stats.iter().map(|s| s.total_size).sum();
+        writeln!(
+            writer,
+            "Total: {} items, {}",
+            item_count,
+            format_size(total_size, BINARY)
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trash::spec::TRASH_INFO_DIR_NAME;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_trash_stats_and_output() -> Result<(), AppError> {
+        let trash_dir = tempdir()?;
+        let files_dir = trash_dir.path().join(TRASH_FILES_DIR_NAME);
+        fs::create_dir_all(files_dir.join("dir"))?;
+        fs::write(files_dir.join("a.txt"), vec![0u8; 1000])?;
+        fs::write(files_dir.join("dir/b.txt"), vec![0u8; 24])?;
+        let empty_trash_dir = tempdir()?;
+
+        let stats = collect_stats(&[trash_dir.path().to_path_buf(), empty_trash_dir.path().to_path_buf()]);
+        assert_eq!(stats[0].item_count, 2);
+        assert_eq!(stats[0].total_size, 1024);
+        assert_eq!(stats[1].item_count, 0);
+        assert_eq!(stats[1].total_size, 0);
+
+        let mut output = Vec::new();
+        write_stats(&mut output, &stats, false)?;
+        let output = String::from_utf8(output)?;
+        assert!(output.contains(&format!("{}: 2 items, 1 KiB", trash_dir.path().display())));
+        assert!(output.ends_with("Total: 2 items, 1 KiB\n"));
+
+        let mut porcelain = Vec::new();
+        write_stats(&mut porcelain, &stats[..1], true)?;
+        assert_eq!(
+            String::from_utf8(porcelain)?,
+            format!("{}\t2\t1024\n", trash_dir.path().display())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_count() -> Result<(), AppError> {
+        let trash_dir = tempdir()?;
+        let other_trash_dir = tempdir()?;
+        for (trash, names) in [
+            (&trash_dir, &["a.txt", "b.txt"][..]),
+            (&other_trash_dir, &["c.txt"][..]),
+        ] {
+            let files_dir = trash.path().join(TRASH_FILES_DIR_NAME);
+            let info_dir = trash.path().join(TRASH_INFO_DIR_NAME);
+            fs::create_dir_all(&files_dir)?;
+            fs::create_dir_all(&info_dir)?;
+            for name in names {
+                fs::write(files_dir.join(name), "data")?;
+                fs::write(
+                    info_dir.join(format!("{}.trashinfo", name)),
+                    format!(
+                        "[Trash Info]\nPath=/home/user/{}\nDeletionDate=2024-01-01T12:00:00\n",
+                        name
+                    ),
+                )?;
+            }
+        }
+        // Neither a data file without an info file nor an info file without the header counts.
+        fs::write(trash_dir.path().join(TRASH_FILES_DIR_NAME).join("orphan.txt"), "data")?;
+        fs::write(
+            trash_dir.path().join(TRASH_INFO_DIR_NAME).join("bogus.txt.trashinfo"),
+            "Path=/home/user/bogus.txt\n",
+        )?;
+
+        let mut output = Vec::new();
+        write_count(&mut output, &[trash_dir.path().to_path_buf()])?;
+        assert_eq!(String::from_utf8(output)?, "2\n");
+
+        let mut output = Vec::new();
+        write_count(
+            &mut output,
+            &[trash_dir.path().to_path_buf(), other_trash_dir.path().to_path_buf()],
+        )?;
+        assert_eq!(String::from_utf8(output)?, "3\n");
+
+        let mut output = Vec::new();
+        write_count(&mut output, &[])?;
+        assert_eq!(String::from_utf8(output)?, "0\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_disk_usage() -> Result<(), AppError> {
+        let home_trash = tempdir()?;
+        let usb_trash = tempdir()?;
+        let home_files = home_trash.path().join(TRASH_FILES_DIR_NAME);
+        fs::create_dir_all(home_files.join("project/src"))?;
+        fs::write(home_files.join("movie.mkv"), vec![0u8; 2048])?;
+        fs::write(home_files.join("project/src/main.rs"), vec![0u8; 1024])?;
+        fs::create_dir_all(usb_trash.path().join(TRASH_FILES_DIR_NAME))?;
+        fs::write(
+            usb_trash.path().join(TRASH_FILES_DIR_NAME).join("photo.jpg"),
+            vec![0u8; 1024],
+        )?;
+        let trash_dirs = [home_trash.path().to_path_buf(), usb_trash.path().to_path_buf()];
+
+        let mut output = Vec::new();
+        write_disk_usage(&mut output, &trash_dirs, true)?;
+        assert_eq!(
+            String::from_utf8(output)?,
+            format!(
+                "3072\t{}\n1024\t{}\n4096\ttotal\n",
+                home_trash.path().display(),
+                usb_trash.path().display()
+            )
+        );
+
+        let mut output = Vec::new();
+        write_disk_usage(&mut output, &trash_dirs, false)?;
+        assert_eq!(
+            String::from_utf8(output)?,
+            format!(
+                "3 KiB\t{}\n1 KiB\t{}\n4 KiB\ttotal\n",
+                home_trash.path().display(),
+                usb_trash.path().display()
+            )
+        );
+        Ok(())
+    }
+}
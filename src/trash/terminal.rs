@@ -0,0 +1,35 @@
+use std::io::{self, IsTerminal};
+
+/// The width assumed for the grid layout when no terminal size can be detected,
+/// e.g. when a terminal is forced with `--assume-tty` while writing to a pipe.
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
+
+/// Decides whether output should get terminal treatment (colors, grid layout).
+/// `tty_override` comes from `--assume-tty` (`Some(true)`) or `--no-tty` (`Some(false)`);
+/// without it, stdout is checked.
+pub fn resolve_tty(tty_override: Option<bool>) -> bool {
+    tty_override.unwrap_or_else(|| io::stdout().is_terminal())
+}
+
+/// Returns the width available for the grid layout.
+pub fn terminal_width() -> usize {
+    term_size::dimensions()
+        .map(|(w, _)| w)
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_tty_override_wins() {
+        assert!(resolve_tty(Some(true)));
+        assert!(!resolve_tty(Some(false)));
+    }
+
+    #[test]
+    fn test_terminal_width_is_never_zero() {
+        assert!(terminal_width() > 0);
+    }
+}
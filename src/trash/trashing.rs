@@ -1,49 +1,540 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
 use std::fs::{self};
-use std::io::{self, ErrorKind};
+use std::io::{self, BufRead, ErrorKind, Write};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use chrono::Local;
+use humansize::{format_size, BINARY};
 
 use crate::trash::color::colorize_path;
+use crate::trash::confirm::confirm_input;
+use crate::trash::copy::{copy_recursive, copy_verified, move_by_copy, remove_any, Throttle};
+use crate::trash::directory_sizes::record_directory_size;
 use crate::trash::error::AppError;
-use crate::trash::locations::{resolve_target_trash, TargetTrash};
+use crate::trash::git::tracking_repository;
+use crate::trash::home_path::display_path;
+use crate::trash::info_file::data_name_for;
+use crate::trash::locations::{resolve_target_trash, Mounts, TargetTrash};
+use crate::trash::porcelain::{write_record, DRY_RUN_FIELD};
+use crate::trash::quota::enforce_max_size;
+use crate::trash::size::{dir_size, tree_summary};
 use crate::trash::spec::{
-    TRASH_INFO_DATE_FORMAT, TRASH_INFO_DATE_KEY, TRASH_INFO_HEADER, TRASH_INFO_PATH_KEY, TRASH_INFO_SUFFIX,
+    TRASH_INFO_DATE_FORMAT, TRASH_INFO_DATE_KEY, TRASH_INFO_HEADER, TRASH_INFO_MODE_KEY, TRASH_INFO_PARENT_MODE_KEY,
+    TRASH_INFO_PATH_KEY, TRASH_INFO_SUFFIX,
 };
-use crate::trash::url_escape::trash_spec_url_encode;
+use crate::trash::url_escape::PathEncoding;
+use crate::trash::verbose::trace;
 
 /// The starting number for the counter when resolving filename collisions in the trash.
 /// This matches the behavior of popular file managers like Nautilus and Nemo.
 const COLLISION_COUNTER_START: u32 = 2;
 
-pub fn handle_move_to_trash(files: &[String]) -> Result<(), AppError> {
-    let mounts = mountpoints::mountpaths()?;
+/// System directories that are never trashed without `--allow-protected`, in addition to `$HOME` and the working directory.
+const PROTECTED_PATHS: &[&str] = &[
+    "/", "/bin", "/boot", "/dev", "/etc", "/home", "/lib", "/lib64", "/opt", "/proc", "/root", "/sbin", "/sys", "/usr",
+    "/var",
+];
+
+/// The default number of taken names tried before giving up on finding a free one.
+pub const DEFAULT_MAX_COLLISIONS: u32 = 10_000;
+
+/// How many items are counted at most when summarizing a directory for the `-i` prompt.
+const CONFIRM_MAX_ITEMS: u64 = 100_000;
+
+/// Prefix of the entries in a trash directory that hold a `--copy` still in progress.
+/// It is followed by the ID of the copying process and a counter.
+const COPY_STAGING_PREFIX: &str = ".tt-copy-";
+
+static COPY_STAGING_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+pub struct TrashOptions {
+    pub porcelain: bool,
+    /// How to percent-encode the `Path` key, for byte-identical info files with other tools.
+    pub path_encoding: PathEncoding,
+    /// How many taken names may be tried for one item before trashing it fails.
+    pub max_collisions: u32,
+    /// Ask before trashing each item; for a directory, show how much it contains.
+    pub interactive: bool,
+    /// Ask before trashing a directory larger than this many bytes, showing its size.
+    pub confirm_larger_than: Option<u64>,
+    /// Trash directories too; without it they are refused, as `rm` does without `-r`.
+    pub recursive: bool,
+    /// Record the item's and its parent's permission bits in its info file for restore to re-apply.
+    pub record_mode: bool,
+    /// Copy and then delete items that cannot be renamed into the trash across filesystems.
+    pub copy_fallback: bool,
+    /// Cap those copies (and the ones made by `copy`) at this many bytes per second.
+    pub limit_rate: Option<u64>,
+    /// Put a copy into the trash and keep the original, as a snapshot.
+    pub copy: bool,
+    /// Trash protected paths (`/`, `$HOME`, ...) too, and tracked files with `protect_tracked`.
+    pub allow_protected: bool,
+    /// Only report where each item would go; nothing is created, moved or written.
+    pub dry_run: bool,
+    /// Print `'src' -> 'dest'` for each item as it is moved, with its final name in the trash.
+    pub verbose: bool,
+    /// Refuse to trash items tracked by a git repository (only warn with `allow_protected`).
+    pub protect_tracked: bool,
+    /// After trashing, evict the oldest items of each trash used until it is no larger than this.
+    pub max_size: Option<u64>,
+}
+
+impl Default for TrashOptions {
+    fn default() -> Self {
+        TrashOptions {
+            porcelain: false,
+            path_encoding: PathEncoding::default(),
+            max_collisions: DEFAULT_MAX_COLLISIONS,
+            interactive: false,
+            confirm_larger_than: None,
+            recursive: false,
+            record_mode: false,
+            copy_fallback: false,
+            limit_rate: None,
+            copy: false,
+            allow_protected: false,
+            dry_run: false,
+            verbose: false,
+            protect_tracked: false,
+            max_size: None,
+        }
+    }
+}
+
+/// The names taken in one trash directory, read once so that `find_available_dest_path` checks
+/// candidates in memory instead of stat-ing each of them. Names chosen during the run are added
+/// as they are reserved, so two inputs with the same base name never get the same one.
+#[derive(Default)]
+struct TakenNames {
+    /// Entries of `files/`.
+    data: HashSet<OsString>,
+    /// Entries of `info/`, without the `.trashinfo` extension.
+    info: HashSet<OsString>,
+    /// The case-folded forms of both, only kept for case-insensitive filesystems.
+    folded: Option<HashSet<String>>,
+}
+
+impl TakenNames {
+    fn read(trash_files_path: &Path, trash_info_path: &Path, case_insensitive: bool) -> Self {
+        let read_names = |dir: &Path| -> Vec<OsString> {
+            fs::read_dir(dir)
+                .map(|entries| entries.filter_map(Result::ok).map(|entry| entry.file_name()).collect())
+                .unwrap_or_default()
+        };
+        let data: HashSet<OsString> = read_names(trash_files_path).into_iter().collect();
+        let info: HashSet<OsString> = read_names(trash_info_path)
+            .into_iter()
+            .filter_map(|name| data_name_for(&name).map(OsStr::to_os_string))
+            .collect();
+        let folded = case_insensitive.then(|| {
+            data.iter()
+                .chain(&info)
+                .map(|name| fold_case(&name.to_string_lossy()))
+                .collect()
+        });
+        TakenNames { data, info, folded }
+    }
+
+    /// Why `name` cannot be used, or `None` if it is free.
+    fn rejection(&self, name: &OsStr) -> Option<&'static str> {
+        if self.data.contains(name) {
+            Some("data exists")
+        } else if self.info.contains(name) {
+            Some("info exists")
+        } else if self
+            .folded
+            .as_ref()
+            .is_some_and(|folded| folded.contains(&fold_case(&name.to_string_lossy())))
+        {
+            Some("an existing entry differs only in case")
+        } else {
+            None
+        }
+    }
+
+    fn reserve(&mut self, name: &OsStr) {
+        if let Some(folded) = &mut self.folded {
+            folded.insert(fold_case(&name.to_string_lossy()));
+        }
+        self.data.insert(name.to_os_string());
+    }
+}
+
+/// The `TakenNames` of every trash directory used in a run, keyed by its `files/` directory.
+/// Without a mount table (`Default`), every filesystem is taken to be case-sensitive.
+#[derive(Default)]
+struct NameSnapshots {
+    taken: HashMap<PathBuf, TakenNames>,
+    mounts: Mounts,
+}
+
+impl NameSnapshots {
+    fn new(mounts: &Mounts) -> Self {
+        NameSnapshots {
+            taken: HashMap::new(),
+            mounts: mounts.clone(),
+        }
+    }
+
+    /// Returns the names taken in `target_trash`, reading them on first use.
+    /// On case-insensitive filesystems (FAT, exFAT, ...), names differing only in case collide too.
+    fn for_trash(&mut self, target_trash: &TargetTrash) -> &mut TakenNames {
+        let trash_files_path = target_trash.files_path();
+        let mounts = &self.mounts;
+        self.taken.entry(trash_files_path.clone()).or_insert_with(|| {
+            let case_insensitive = mounts.is_case_insensitive(&trash_files_path);
+            TakenNames::read(&trash_files_path, &target_trash.info_path(), case_insensitive)
+        })
+    }
+}
+
+pub fn handle_move_to_trash(files: &[String], opts: &TrashOptions) -> Result<(), AppError> {
+    let mounts = Mounts::read()?;
+    let mut writer = io::stdout();
     let mut trashed: Vec<String> = Vec::new();
+    let mut dest_paths: Vec<PathBuf> = Vec::new();
+    let mut failed = 0;
+    let mut prompt = io::stderr();
+    let mut answers = io::stdin().lock();
+    for (file, outcome) in trash_files(&mut writer, &mut prompt, &mut answers, files, &mounts, opts) {
+        if let TrashOutcome::Trashed { dest_path } = &outcome {
+            dest_paths.push(dest_path.clone());
+        }
+        match outcome {
+            TrashOutcome::Trashed { dest_path } if opts.dry_run && !opts.porcelain => {
+                println!("Would trash: {} -> {}", file, dest_path.display());
+                trashed.push(file.clone());
+            }
+            TrashOutcome::Trashed { dest_path } if opts.porcelain => {
+                let dest_path = dest_path.display().to_string();
+                if opts.dry_run {
+                    write_record(&mut writer, &[file, &dest_path, DRY_RUN_FIELD])?;
+                } else {
+                    write_record(&mut writer, &[file, &dest_path])?;
+                }
+                trashed.push(file.clone());
+            }
+            TrashOutcome::Trashed { dest_path } => trashed.push(colorize_path(file, &dest_path).to_string()),
+            TrashOutcome::Failed(message) => {
+                eprintln!("{}", message);
+                failed += 1;
+            }
+            TrashOutcome::Declined => {}
+        }
+    }
+    let verb = if opts.copy {
+        "Copied to trash (originals kept)"
+    } else {
+        "Trashed"
+    };
+    match summary_line(verb, &trashed, failed) {
+        Some(line) if !opts.porcelain && !opts.dry_run => println!("{}", line),
+        Some(_) => {}
+        None => {
+            eprintln!("No files were trashed.");
+            // Declining every `-i` prompt is not a failure, as with `rm -i`.
+            return Err(if failed == 0 {
+                AppError::NothingToDo
+            } else {
+                AppError::Ignorable
+            });
+        }
+    }
+    if let (Some(max_size), false) = (opts.max_size, opts.dry_run) {
+        evict_over_max_size(&dest_paths, max_size, opts.porcelain);
+    }
+    Ok(())
+}
+
+/// Enforces `--max-size` on each trash that received one of `dest_paths`, printing what was evicted.
+/// The items are already trashed, so a failure is only reported.
+fn evict_over_max_size(dest_paths: &[PathBuf], max_size: u64, porcelain: bool) {
+    let mut by_trash: Vec<(PathBuf, Vec<PathBuf>)> = Vec::new();
+    for dest_path in dest_paths {
+        let Some(trash_root) = dest_path.parent().and_then(Path::parent) else {
+            continue;
+        };
+        match by_trash.iter_mut().find(|(root, _)| root == trash_root) {
+            Some((_, paths)) => paths.push(dest_path.clone()),
+            None => by_trash.push((trash_root.to_path_buf(), vec![dest_path.clone()])),
+        }
+    }
+    for (trash_root, just_trashed) in by_trash {
+        match enforce_max_size(&trash_root, &just_trashed, max_size) {
+            Ok(evicted) => {
+                for item in evicted {
+                    let line = format!(
+                        "Evicted: {} ({})",
+                        display_path(&item.original_path),
+                        format_size(item.size, BINARY)
+                    );
+                    // The porcelain output only lists what was trashed.
+                    if porcelain {
+                        eprintln!("{}", line);
+                    } else {
+                        println!("{}", line);
+                    }
+                }
+            }
+            Err(e) => eprintln!(
+                "warning: Failed to enforce the trash size limit on {}: {}",
+                trash_root.display(),
+                e
+            ),
+        }
+    }
+}
+
+/// Reads the paths to trash from `reader`, one per line or, with `nul_separated`, one per NUL byte.
+/// Whole records are kept, so names with spaces survive; empty records are skipped. In line mode
+/// a trailing carriage return is dropped. A record that is not valid UTF-8 is skipped with a warning
+/// so that one bad name does not abort the whole run.
+pub fn read_file_args<R: BufRead>(reader: &mut R, nul_separated: bool) -> Result<Vec<String>, AppError> {
+    let separator = if nul_separated { b'\0' } else { b'\n' };
+    let mut files = Vec::new();
+    let mut record = Vec::new();
+    while reader.read_until(separator, &mut record)? > 0 {
+        if record.last() == Some(&separator) {
+            record.pop();
+        }
+        if !nul_separated && record.last() == Some(&b'\r') {
+            record.pop();
+        }
+        if !record.is_empty() {
+            match String::from_utf8(std::mem::take(&mut record)) {
+                Ok(file) => files.push(file),
+                Err(e) => eprintln!(
+                    "warning: Skipping a path that is not valid UTF-8: {}",
+                    String::from_utf8_lossy(e.as_bytes())
+                ),
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Builds the final success line starting with `verb`, or `None` when nothing was trashed
+/// so that a bare `Trashed: ` line is never printed. When some items `failed`, the line
+/// says how many of the attempted items made it.
+fn summary_line(verb: &str, trashed: &[String], failed: usize) -> Option<String> {
+    if trashed.is_empty() {
+        return None;
+    }
+    if failed == 0 {
+        return Some(format!("{}: {}", verb, trashed.join(", ")));
+    }
+    let attempted = trashed.len() + failed;
+    let noun = if attempted == 1 { "item" } else { "items" };
+    Some(format!(
+        "{} {} of {} {}: {}",
+        verb,
+        trashed.len(),
+        attempted,
+        noun,
+        trashed.join(", ")
+    ))
+}
+
+/// The result of trying to trash a single command-line argument.
+#[derive(Debug)]
+enum TrashOutcome {
+    Trashed {
+        dest_path: PathBuf,
+    },
+    Failed(String),
+    /// The user answered no to the `-i` prompt.
+    Declined,
+}
+
+/// Trashes each of `files` in order and reports the outcome for every argument.
+/// The same source given more than once (e.g. `foo ./foo`) is only trashed once.
+/// With `opts.verbose`, each move is written to `writer` right after it happened.
+/// With `opts.interactive`, each item is first confirmed by writing a prompt to `prompt`
+/// and reading the answer from `answers`.
+fn trash_files<'a, W: Write, P: Write, R: BufRead>(
+    writer: &mut W,
+    prompt: &mut P,
+    answers: &mut R,
+    files: &'a [String],
+    mounts: &Mounts,
+    opts: &TrashOptions,
+) -> Vec<(&'a String, TrashOutcome)> {
+    let mut trashed_sources: HashSet<PathBuf> = HashSet::new();
+    let mut names = NameSnapshots::new(mounts);
+    let mut outcomes = Vec::with_capacity(files.len());
     for file in files {
-        let path = Path::new(file);
-        if !path.exists() {
-            eprintln!("Failed to access path: '{}' does not exist.", path.display());
+        let path = match strip_trailing_slashes(file) {
+            Ok(path) => path,
+            Err(message) => {
+                outcomes.push((file, TrashOutcome::Failed(message)));
+                continue;
+            }
+        };
+        let source_key = source_identity(path);
+        if source_key.as_ref().is_some_and(|key| trashed_sources.contains(key)) {
+            let message = format!("Skipped '{}': it was already trashed in this run.", path.display());
+            outcomes.push((file, TrashOutcome::Failed(message)));
             continue;
         }
-        match resolve_target_trash(path, &mounts) {
-            Ok(target_trash) => {
-                if let Err(e) = target_trash.ensure_structure_exists() {
-                    eprintln!("Failed to prepare trash directory for '{}': {}", path.display(), e);
+        if !opts.recursive && is_real_dir(path) {
+            let message = format!("Failed to trash '{}': it is a directory (use --recursive).", file);
+            outcomes.push((file, TrashOutcome::Failed(message)));
+            continue;
+        }
+        // A dry run changes nothing, so there is nothing to confirm.
+        let answer = match (opts.interactive, opts.confirm_larger_than) {
+            _ if opts.dry_run => None,
+            (true, _) if is_real_dir(path) => Some(confirm_trash_directory(prompt, answers, path)),
+            (true, _) => Some(confirm_input(prompt, answers, format!("trash '{}'? [Y/n] ", file))),
+            (false, Some(threshold)) if is_real_dir(path) => confirm_large_directory(prompt, answers, path, threshold),
+            (false, _) => None,
+        };
+        if let Some(answer) = answer {
+            match answer {
+                Ok(true) => {}
+                Ok(false) => {
+                    outcomes.push((file, TrashOutcome::Declined));
                     continue;
                 }
-                if let Err(e) = trash_item(path, &target_trash) {
-                    eprintln!("Failed to trash '{}': {}", path.display(), e);
-                } else {
-                    trashed.push(colorize_path(&file, path).to_string());
+                Err(e) => {
+                    outcomes.push((file, TrashOutcome::Failed(format!("Failed to read answer: {}", e))));
+                    continue;
                 }
             }
-            Err(e) => eprintln!("Could not determine trash location for '{}': {}", path.display(), e),
         }
+        let outcome = trash_file(path, mounts, &mut names, opts);
+        if let TrashOutcome::Trashed { dest_path } = &outcome {
+            if opts.verbose && !opts.dry_run && !opts.porcelain {
+                // The move already happened, so a closed stdout must not turn it into a failure.
+                let _ = writeln!(writer, "'{}' -> '{}'", file, dest_path.display());
+            }
+            if let Some(key) = source_key {
+                trashed_sources.insert(key);
+            }
+        }
+        outcomes.push((file, outcome));
+    }
+    outcomes
+}
+
+/// Whether `path` is a directory itself rather than a symbolic link to one.
+fn is_real_dir(path: &Path) -> bool {
+    path.symlink_metadata().is_ok_and(|metadata| metadata.is_dir())
+}
+
+/// Asks whether to trash the directory `path`, showing how many items it holds and their size.
+/// Only an explicit `y`/`yes` trashes it; anything else, including end of input, keeps it.
+fn confirm_trash_directory<W: Write, R: BufRead>(
+    writer: &mut W,
+    reader: &mut R,
+    path: &Path,
+) -> Result<bool, AppError> {
+    let summary = tree_summary(path, CONFIRM_MAX_ITEMS);
+    let plus = if summary.truncated { "+" } else { "" };
+    let noun = if summary.item_count == 1 { "item" } else { "items" };
+    write!(
+        writer,
+        "trash directory '{}' ({}{} {}, {}{})? [y/N] ",
+        path.display(),
+        summary.item_count,
+        plus,
+        noun,
+        format_size(summary.total_size, BINARY),
+        plus
+    )?;
+    writer.flush()?;
+    let mut input = String::new();
+    reader.read_line(&mut input)?;
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Asks whether to trash the directory `path` when its contents add up to more than `threshold` bytes,
+/// so a large tree does not fill the trash by surprise. Returns `None` without asking below it.
+fn confirm_large_directory<W: Write, R: BufRead>(
+    writer: &mut W,
+    reader: &mut R,
+    path: &Path,
+    threshold: u64,
+) -> Option<Result<bool, AppError>> {
+    let size = dir_size(path);
+    (size > threshold).then(|| {
+        let message = format!(
+            "trash directory '{}' ({})? [Y/n] ",
+            path.display(),
+            format_size(size, BINARY)
+        );
+        confirm_input(writer, reader, message)
+    })
+}
+
+fn trash_file(path: &Path, mounts: &Mounts, names: &mut NameSnapshots, opts: &TrashOptions) -> TrashOutcome {
+    if !path.exists() {
+        return TrashOutcome::Failed(format!("Failed to access path: '{}' does not exist.", path.display()));
+    }
+    let target_trash = match resolve_target_trash(path, mounts) {
+        Ok(target_trash) => target_trash,
+        Err(e) => {
+            return TrashOutcome::Failed(format!(
+                "Could not determine trash location for '{}': {}",
+                path.display(),
+                e
+            ))
+        }
+    };
+    if let Err(e) = target_trash.ensure_writable_filesystem() {
+        return TrashOutcome::Failed(format!("Failed to trash '{}': {}", path.display(), e));
     }
-    if !trashed.is_empty() {
-        println!("Trashed: {}", trashed.join(", "));
+    // A dry run must not even create the trash directory; a missing one simply has no collisions.
+    if !opts.dry_run {
+        if let Err(e) = target_trash.ensure_structure_exists() {
+            return TrashOutcome::Failed(format!(
+                "Failed to prepare trash directory for '{}': {}",
+                path.display(),
+                e
+            ));
+        }
+    }
+    match trash_item(path, &target_trash, names, opts) {
+        Ok(dest_path) => TrashOutcome::Trashed { dest_path },
+        Err(e) => TrashOutcome::Failed(format!("Failed to trash '{}': {}", path.display(), e)),
+    }
+}
+
+/// Removes trailing slashes from a command-line argument, so `dir/` trashes `dir` itself.
+/// A trailing slash promises a directory, so an existing non-directory (`file.txt/`)
+/// is reported instead of failing later with a confusing "does not exist".
+fn strip_trailing_slashes(file: &str) -> Result<&Path, String> {
+    let trimmed = file.trim_end_matches('/');
+    if trimmed.is_empty() || trimmed.len() == file.len() {
+        return Ok(Path::new(file));
+    }
+    let path = Path::new(trimmed);
+    if path.symlink_metadata().is_ok() && !path.is_dir() {
+        return Err(format!(
+            "Failed to trash '{}': not a directory (remove the trailing slash to trash the file).",
+            file
+        ));
+    }
+    Ok(path)
+}
+
+/// Returns an absolute path identifying `path` itself, without following a final symlink.
+/// Only the parent directory is canonicalized, so this still works after the item has been moved away.
+fn source_identity(path: &Path) -> Option<PathBuf> {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(file_name)) => {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            parent.canonicalize().ok().map(|parent| parent.join(file_name))
+        }
+        _ => path.canonicalize().ok(),
     }
-    Ok(())
 }
 
 /// Checks whether the specified file path is within the root directory of the given trash bin or within its files directory.
@@ -54,13 +545,45 @@ fn is_path_in_trash_dir(source_path: &Path, trash_path: &Path) -> bool {
 
 /// Moves a file or directory to the trash, creating a corresponding .trashinfo file.
 /// This is the main entry point for trashing an item.
-fn trash_item(source_path: &Path, target_trash: &TargetTrash) -> Result<(), AppError> {
+/// Returns the destination path inside `Trash/files` on success.
+fn trash_item(
+    source_path: &Path,
+    target_trash: &TargetTrash,
+    names: &mut NameSnapshots,
+    opts: &TrashOptions,
+) -> Result<PathBuf, AppError> {
     if !source_path.exists() {
         return Err(AppError::Io {
             path: source_path.to_path_buf(),
             source: io::Error::new(ErrorKind::NotFound, "source file not found"),
         });
     }
+    if !opts.allow_protected {
+        let home = dirs::home_dir().and_then(|home| home.canonicalize().ok());
+        let cwd = std::env::current_dir().ok();
+        if let Some(source) =
+            source_identity(source_path).filter(|source| is_protected_path(source, home.as_deref(), cwd.as_deref()))
+        {
+            return Err(AppError::ProtectedPath { path: source });
+        }
+    }
+    if opts.protect_tracked {
+        if let Some(source) = source_identity(source_path) {
+            if let Some(repository) = tracking_repository(&source) {
+                if !opts.allow_protected {
+                    return Err(AppError::TrackedByGit {
+                        path: source,
+                        repository,
+                    });
+                }
+                eprintln!(
+                    "warning: '{}' is tracked by the git repository at '{}'.",
+                    source.display(),
+                    repository.display()
+                );
+            }
+        }
+    }
     if is_path_in_trash_dir(source_path, target_trash.root_path()) {
         return Err(AppError::AlreadyInTrash {
             path: source_path.to_path_buf(),
@@ -69,15 +592,61 @@ fn trash_item(source_path: &Path, target_trash: &TargetTrash) -> Result<(), AppE
     let trash_files_path = target_trash.files_path();
     let trash_info_path = target_trash.info_path();
 
-    // Determine the final destination path in `Trash/files`, handling collisions.
-    let dest_path = find_available_dest_path(source_path, &trash_files_path)?;
+    // The lexical check above misses a source reached through a symlink or a relative path
+    // into `Trash/files`; moving it there would be a no-op rename that still writes an info file.
+    if is_same_dir(source_path.parent(), Some(&trash_files_path)) {
+        return Err(AppError::SourceInTrashFilesDir {
+            path: source_path.to_path_buf(),
+        });
+    }
+
+    let info_content = if opts.dry_run {
+        None
+    } else {
+        Some(trash_info_content(source_path, opts.path_encoding, opts.record_mode)?)
+    };
+    // A copy is made before its name is claimed, so an interrupted copy never gets an info file.
+    let staged_copy = if opts.copy && !opts.dry_run {
+        Some(stage_copy(source_path, target_trash.root_path(), opts.limit_rate)?)
+    } else {
+        None
+    };
+    let discard_staged_copy = || {
+        if let Some(staged) = &staged_copy {
+            let _ = remove_any(staged);
+        }
+    };
+
+    // Determine the final destination path in `Trash/files`, handling collisions. Outside a dry run,
+    // the name is claimed by creating its .trashinfo file, so it is ours before anything is moved.
+    let dest_path = find_available_dest_path(
+        source_path,
+        &trash_files_path,
+        &trash_info_path,
+        names.for_trash(target_trash),
+        opts.max_collisions,
+        |info_file_path| match &info_content {
+            Some(content) => create_trash_info_file(info_file_path, content),
+            None => is_info_file_free(info_file_path),
+        },
+    )
+    .inspect_err(|_| discard_staged_copy())?;
 
-    // Create the corresponding .trashinfo file.
-    create_trash_info_file(source_path, &dest_path, &trash_info_path)?;
+    if opts.dry_run {
+        return Ok(dest_path);
+    }
 
-    // Move the actual file/directory to `Trash/files`.
+    // Move (or copy) the actual file/directory to `Trash/files`.
     // This is done *after* creating the info file, as per the spec.
-    if let Err(e) = fs::rename(source_path, &dest_path) {
+    let result = match &staged_copy {
+        Some(staged) => fs::rename(staged, &dest_path).map_err(|source| AppError::Io {
+            path: dest_path.clone(),
+            source,
+        }),
+        None => move_to_trash_files(source_path, &dest_path, opts, |from, to| fs::rename(from, to)),
+    };
+    if let Err(e) = result {
+        discard_staged_copy();
         // If the move fails for any reason, we must try to clean up the .trashinfo file
         // we just created to avoid an inconsistent state in the trash.
         let info_file_path = determine_info_file_path(&dest_path, &trash_info_path);
@@ -89,25 +658,151 @@ fn trash_item(source_path: &Path, target_trash: &TargetTrash) -> Result<(), AppE
                 cleanup_err
             );
         }
+        return Err(e);
+    }
 
-        // Now, return the appropriate error to the caller.
-        if e.kind() == ErrorKind::CrossesDevices {
-            return Err(AppError::CrossDeviceMove {
-                path: source_path.to_path_buf(),
-            });
-        } else {
-            return Err(AppError::Io {
-                path: source_path.to_path_buf(),
-                source: e,
-            });
+    if is_real_dir(&dest_path) {
+        cache_directory_size(target_trash.root_path(), &dest_path, &trash_info_path);
+    }
+
+    Ok(dest_path)
+}
+
+/// Copies `source_path` for `--copy` into `trash_root`, beside `files/`, and verifies the copy.
+/// A copy interrupted there is not a trash entry, and a later `--copy` removes it, together with
+/// any other copy whose process is no longer running.
+fn stage_copy(source_path: &Path, trash_root: &Path, limit_rate: Option<u64>) -> Result<PathBuf, AppError> {
+    remove_stale_copies(trash_root);
+    let staged = trash_root.join(format!(
+        "{}{}-{}",
+        COPY_STAGING_PREFIX,
+        std::process::id(),
+        COPY_STAGING_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    copy_verified(source_path, &staged, |src, dst| {
+        copy_recursive(src, dst, &mut Throttle::new(limit_rate))
+    })
+    .map_err(|e| classify_storage_full(e, &staged))?;
+    Ok(staged)
+}
+
+/// Removes the staged copies in `trash_root` left behind by processes that are no longer running.
+fn remove_stale_copies(trash_root: &Path) {
+    let Ok(entries) = fs::read_dir(trash_root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let pid = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(COPY_STAGING_PREFIX))
+            .and_then(|rest| rest.split('-').next())
+            .and_then(|pid| pid.parse::<libc::pid_t>().ok());
+        if pid.is_none_or(is_process_running) {
+            continue;
+        }
+        if let Err(e) = remove_any(&entry.path()) {
+            eprintln!(
+                "warning: Failed to remove the interrupted copy '{}': {}",
+                entry.path().display(),
+                e
+            );
         }
     }
+}
 
-    Ok(())
+fn is_process_running(pid: libc::pid_t) -> bool {
+    // SAFETY: signal 0 only checks whether the process exists; nothing is sent.
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+/// Adds a trashed directory to the trash's `directorysizes` cache. The cache only saves file managers
+/// from recomputing sizes, so a failure is reported but does not fail the trashing.
+fn cache_directory_size(trash_root: &Path, dest_path: &Path, trash_info_path: &Path) {
+    let Some(name) = dest_path.file_name() else {
+        return;
+    };
+    let info_mtime = fs::metadata(determine_info_file_path(dest_path, trash_info_path))
+        .map(|metadata| metadata.mtime())
+        .unwrap_or(0);
+    if let Err(e) = record_directory_size(trash_root, name, dir_size(dest_path), info_mtime) {
+        eprintln!("warning: Failed to update the directory size cache: {}", e);
+    }
+}
+
+/// Whether the absolute, symlink-free `source` is one of `PROTECTED_PATHS`, `home` or `cwd`.
+fn is_protected_path(source: &Path, home: Option<&Path>, cwd: Option<&Path>) -> bool {
+    PROTECTED_PATHS
+        .iter()
+        .map(Path::new)
+        .chain(home)
+        .chain(cwd)
+        .any(|protected| protected == source)
+}
+
+/// Whether two directories are the same once symlinks and relative components are resolved.
+/// An empty parent (a bare file name) means the current directory.
+fn is_same_dir(a: Option<&Path>, b: Option<&Path>) -> bool {
+    let canonical = |dir: Option<&Path>| {
+        let dir = dir.filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        dir.canonicalize().ok()
+    };
+    matches!((canonical(a), canonical(b)), (Some(a), Some(b)) if a == b)
+}
+
+/// Moves `source_path` to `dest_path` with `rename`. When they are on different filesystems
+/// and `opts.copy_fallback` is set, the item is copied and verified instead, and the original
+/// is removed only afterwards; a partial copy is removed again if anything goes wrong.
+fn move_to_trash_files<F>(source_path: &Path, dest_path: &Path, opts: &TrashOptions, rename: F) -> Result<(), AppError>
+where
+    F: FnOnce(&Path, &Path) -> io::Result<()>,
+{
+    match rename(source_path, dest_path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::CrossesDevices && opts.copy_fallback => {
+            trace(|| {
+                format!(
+                    "'{}' is on another filesystem; copying it into the trash",
+                    source_path.display()
+                )
+            });
+            move_by_copy(source_path, dest_path, |src, dst| {
+                copy_recursive(src, dst, &mut Throttle::new(opts.limit_rate))
+            })
+            .map_err(|e| classify_storage_full(e, dest_path))
+        }
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => Err(AppError::CrossDeviceMove {
+            path: source_path.to_path_buf(),
+        }),
+        Err(source) => Err(AppError::Io {
+            path: source_path.to_path_buf(),
+            source,
+        }),
+    }
 }
 
-/// Finds an available path in the trash/files directory, handling name collisions.
-fn find_available_dest_path(source_path: &Path, trash_files_path: &Path) -> Result<PathBuf, AppError> {
+/// Finds an available path in the trash/files directory, handling name collisions, and reserves it in `taken`.
+/// A name is taken if either its data file or its .trashinfo file already exists, or if it was
+/// reserved earlier in the run. When `taken` folds case, a name is also taken if an existing entry
+/// matches it when case-folded, so a later `rename` cannot clobber it on a case-insensitive filesystem.
+///
+/// A name that looks free is finally handed to `claim_info_file` with its .trashinfo path. It returns
+/// `false` when that file already exists, i.e. another program took the name since the snapshot, and
+/// the search moves on to the next counter. Creating the info file exclusively there closes the window
+/// in which two concurrent runs could pick the same name.
+/// After `max_collisions` taken names, this fails instead of searching on.
+fn find_available_dest_path<F>(
+    source_path: &Path,
+    trash_files_path: &Path,
+    trash_info_path: &Path,
+    taken: &mut TakenNames,
+    max_collisions: u32,
+    mut claim_info_file: F,
+) -> Result<PathBuf, AppError>
+where
+    F: FnMut(&Path) -> Result<bool, AppError>,
+{
     let file_name = source_path
         .file_name()
         .ok_or_else(|| AppError::Message(format!("Source path '{}' has no filename", source_path.display())))?;
@@ -117,7 +812,34 @@ fn find_available_dest_path(source_path: &Path, trash_files_path: &Path) -> Resu
     // like Nautilus, Nemo, and Thunar. When "file.txt" exists, the next one
     // becomes "file.2.txt", not "file.1.txt".
     let mut counter = COLLISION_COUNTER_START;
-    while dest_path.exists() {
+    let mut collisions = 0;
+    loop {
+        let name = dest_path.file_name().unwrap_or_default();
+        // The snapshot may be stale if another program trashed something meanwhile,
+        // so the name that looks free is confirmed on disk before it is used.
+        let rejection = match taken.rejection(name) {
+            Some(rejection) => Some(rejection),
+            None if dest_path.symlink_metadata().is_ok() => Some("data created by another program"),
+            None if !claim_info_file(&determine_info_file_path(&dest_path, trash_info_path))? => {
+                Some("info created by another program")
+            }
+            None => None,
+        };
+        if rejection.is_some() {
+            taken.reserve(name);
+        }
+        let Some(rejection) = rejection else {
+            break;
+        };
+        trace(|| format!("rejected trash name '{}': {}", dest_path.display(), rejection));
+        collisions += 1;
+        if collisions >= max_collisions {
+            return Err(AppError::TooManyCollisions {
+                path: source_path.to_path_buf(),
+                attempts: collisions,
+            });
+        }
+
         let filename_str = file_name.to_string_lossy();
 
         // Find the first dot to separate the base name from the full extension. This ensures that for a file like "archive.tar.gz", the counter is inserted
@@ -144,46 +866,140 @@ fn find_available_dest_path(source_path: &Path, trash_files_path: &Path) -> Resu
         counter += 1;
     }
 
+    taken.reserve(dest_path.file_name().unwrap_or_default());
+    trace(|| format!("chose trash name '{}'", dest_path.display()));
     Ok(dest_path)
 }
 
+/// Folds a file name for case-insensitive comparison.
+fn fold_case(name: &str) -> String {
+    name.to_lowercase()
+}
+
 /// Builds the content for a .trashinfo file.
 /// This is a pure function, making it easy to test.
-fn build_trash_info_content(original_abs_path: &Path, deletion_date: &str) -> String {
-    format!(
+pub(crate) fn build_trash_info_content(
+    original_abs_path: &Path,
+    deletion_date: &str,
+    path_encoding: PathEncoding,
+    mode: Option<u32>,
+    parent_mode: Option<u32>,
+) -> String {
+    let mut content = format!(
         "{}\n{}={}\n{}={}\n",
         TRASH_INFO_HEADER,
         TRASH_INFO_PATH_KEY,
-        trash_spec_url_encode(original_abs_path.to_string_lossy().as_ref()),
+        path_encoding.encode(original_abs_path.as_os_str()),
         TRASH_INFO_DATE_KEY,
         deletion_date,
-    )
+    );
+    if let Some(mode) = mode {
+        content.push_str(&format!("{}={:04o}\n", TRASH_INFO_MODE_KEY, mode));
+    }
+    if let Some(parent_mode) = parent_mode {
+        content.push_str(&format!("{}={:04o}\n", TRASH_INFO_PARENT_MODE_KEY, parent_mode));
+    }
+    content
 }
 
 /// Determines the full path for the .trashinfo file.
 /// This is a pure function, making it easy to test.
-fn determine_info_file_path(dest_path: &Path, trash_info_path: &Path) -> PathBuf {
+pub(crate) fn determine_info_file_path(dest_path: &Path, trash_info_path: &Path) -> PathBuf {
     let info_filename_osstr = dest_path.file_name().unwrap();
     let mut info_filename = info_filename_osstr.to_owned();
     info_filename.push(TRASH_INFO_SUFFIX);
     trash_info_path.join(info_filename)
 }
 
-/// Creates a .trashinfo file for a given trashed item.
-fn create_trash_info_file(original_path: &Path, dest_path: &Path, trash_info_path: &Path) -> Result<(), AppError> {
-    let original_abs_path = original_path.canonicalize()?;
+/// Builds the .trashinfo content for trashing `original_path` now. The `Mode` and `ParentMode`
+/// extension keys are only written with `record_mode`, since other tools read these files too.
+fn trash_info_content(
+    original_path: &Path,
+    path_encoding: PathEncoding,
+    record_mode: bool,
+) -> Result<String, AppError> {
+    // Record where a symlink lives rather than where it points, so a restore puts the link back in place.
+    let original_abs_path = match source_identity(original_path) {
+        Some(path) => path,
+        None => original_path.canonicalize()?,
+    };
     let deletion_date = Local::now().format(TRASH_INFO_DATE_FORMAT).to_string();
-    let info_content = build_trash_info_content(&original_abs_path, &deletion_date);
-    let info_file_path = determine_info_file_path(dest_path, trash_info_path);
+    // Compatibility modes always leave the mode out to stay byte-identical with the other tool.
+    // Symlinks have no mode of their own.
+    let record_mode = record_mode && path_encoding == PathEncoding::TrashTool;
+    let mode = fs::symlink_metadata(original_path)
+        .ok()
+        .filter(|metadata| record_mode && !metadata.file_type().is_symlink())
+        .map(|metadata| metadata.permissions().mode() & 0o7777);
+    // So is the parent's, for a restore that has to recreate it after it was deleted too.
+    let parent_mode = original_abs_path
+        .parent()
+        .filter(|_| record_mode)
+        .and_then(|parent| fs::metadata(parent).ok())
+        .map(|metadata| metadata.permissions().mode() & 0o7777);
+    Ok(build_trash_info_content(
+        &original_abs_path,
+        &deletion_date,
+        path_encoding,
+        mode,
+        parent_mode,
+    ))
+}
 
-    fs::write(info_file_path, info_content)?;
-    Ok(())
+/// Creates the .trashinfo file at `info_file_path` with `content`, only if it does not exist yet.
+/// Returns `false` when it already exists, as trash-cli does to reserve a name atomically.
+fn create_trash_info_file(info_file_path: &Path, content: &str) -> Result<bool, AppError> {
+    let mut file = match fs::OpenOptions::new().write(true).create_new(true).open(info_file_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => return Ok(false),
+        Err(source) => return Err(info_file_error(info_file_path, source)),
+    };
+    if let Err(source) = file.write_all(content.as_bytes()) {
+        // A failed write may leave a truncated info file behind; it must not outlive the failure.
+        let _ = fs::remove_file(info_file_path);
+        return Err(info_file_error(info_file_path, source));
+    }
+    Ok(true)
+}
+
+/// The dry-run counterpart of `create_trash_info_file`: whether the info file could be created.
+fn is_info_file_free(info_file_path: &Path) -> Result<bool, AppError> {
+    Ok(info_file_path.symlink_metadata().is_err())
+}
+
+fn info_file_error(info_file_path: &Path, source: io::Error) -> AppError {
+    if is_storage_full(&source) {
+        AppError::TrashFull {
+            path: info_file_path.to_path_buf(),
+        }
+    } else {
+        AppError::Io {
+            path: info_file_path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+/// Whether an I/O error means the filesystem has no space left (`ENOSPC`).
+fn is_storage_full(error: &io::Error) -> bool {
+    error.kind() == ErrorKind::StorageFull || error.raw_os_error() == Some(libc::ENOSPC)
+}
+
+/// Turns an error caused by a full filesystem into `AppError::TrashFull` for `dest_path`.
+fn classify_storage_full(error: AppError, dest_path: &Path) -> AppError {
+    match &error {
+        AppError::Io { source, .. } | AppError::GenericIo(source) if is_storage_full(source) => AppError::TrashFull {
+            path: dest_path.to_path_buf(),
+        },
+        _ => error,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::trash::spec::{TRASH_FILES_DIR_NAME, TRASH_INFO_DIR_NAME};
+    use crate::trash::restoring::{find_trash_entries_in_dirs, restore_item};
+    use crate::trash::spec::{DIRECTORY_SIZES_FILE_NAME, TRASH_FILES_DIR_NAME, TRASH_INFO_DIR_NAME};
     use std::fs::{self, File};
     use std::os::unix::fs::PermissionsExt;
     use tempfile::tempdir;
@@ -198,11 +1014,14 @@ mod tests {
         let temp_trash_root = tempdir()?;
         let trash_files_path = temp_trash_root.path().join(TRASH_FILES_DIR_NAME);
         fs::create_dir_all(&trash_files_path)?;
+        let trash_info_path = temp_trash_root.path().join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(&trash_info_path)?;
 
         struct TestCase<'a> {
             description: &'a str,
             source_filename: &'a str,
             existing_files: &'a [&'a str],
+            existing_infos: &'a [&'a str],
             expected_filename: &'a str,
         }
 
@@ -211,38 +1030,51 @@ mod tests {
                 description: "Should return the original filename when no collision exists",
                 source_filename: "test1.txt",
                 existing_files: &[],
+                existing_infos: &[],
                 expected_filename: "test1.txt",
             },
             TestCase {
                 description: "Should append '.2' on the first collision",
                 source_filename: "test2.txt",
                 existing_files: &["test2.txt"],
+                existing_infos: &[],
                 expected_filename: "test2.2.txt",
             },
             TestCase {
                 description: "Should find the next available number, skipping existing ones",
                 source_filename: "test3.txt",
                 existing_files: &["test3.txt", "test3.1.txt"],
+                existing_infos: &[],
                 expected_filename: "test3.2.txt",
             },
             TestCase {
                 description: "Should handle collisions for files without extensions",
                 source_filename: "no_ext",
                 existing_files: &["no_ext"],
+                existing_infos: &[],
                 expected_filename: "no_ext.2",
             },
             TestCase {
                 description: "Should handle collisions for filenames with multiple dots",
                 source_filename: "archive.tar.gz",
                 existing_files: &["archive.tar.gz"],
+                existing_infos: &[],
                 expected_filename: "archive.2.tar.gz",
             },
             TestCase {
                 description: "Should handle collisions for dotfiles",
                 source_filename: ".config",
                 existing_files: &[".config"],
+                existing_infos: &[],
                 expected_filename: ".config.2",
             },
+            TestCase {
+                description: "Should skip names whose info file exists without data",
+                source_filename: "orphan.txt",
+                existing_files: &[],
+                existing_infos: &["orphan.txt.trashinfo"],
+                expected_filename: "orphan.2.txt",
+            },
         ];
 
         for case in test_cases {
@@ -252,9 +1084,20 @@ mod tests {
             for f in case.existing_files {
                 File::create(trash_files_path.join(f))?;
             }
+            for f in case.existing_infos {
+                File::create(trash_info_path.join(f))?;
+            }
 
             let expected_path = trash_files_path.join(case.expected_filename);
-            let actual_path = find_available_dest_path(&source_path, &trash_files_path)?;
+            let mut taken = TakenNames::read(&trash_files_path, &trash_info_path, false);
+            let actual_path = find_available_dest_path(
+                &source_path,
+                &trash_files_path,
+                &trash_info_path,
+                &mut taken,
+                DEFAULT_MAX_COLLISIONS,
+                is_info_file_free,
+            )?;
 
             assert_eq!(actual_path, expected_path, "Failed on: {}", case.description);
         }
@@ -262,17 +1105,93 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_find_available_dest_path_gives_up_after_max_collisions() -> Result<(), AppError> {
+        let temp_trash_root = tempdir()?;
+        let trash_files_path = temp_trash_root.path().join(TRASH_FILES_DIR_NAME);
+        let trash_info_path = temp_trash_root.path().join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(&trash_files_path)?;
+        fs::create_dir_all(&trash_info_path)?;
+        for name in ["a.txt", "a.2.txt", "a.3.txt"] {
+            File::create(trash_files_path.join(name))?;
+        }
+        let source_path = temp_trash_root.path().join("a.txt");
+
+        let find = |max_collisions| {
+            let mut taken = TakenNames::read(&trash_files_path, &trash_info_path, false);
+            find_available_dest_path(
+                &source_path,
+                &trash_files_path,
+                &trash_info_path,
+                &mut taken,
+                max_collisions,
+                is_info_file_free,
+            )
+        };
+        let found = find(4)?;
+        assert_eq!(found, trash_files_path.join("a.4.txt"));
+
+        let result = find(3);
+        assert!(matches!(result, Err(AppError::TooManyCollisions { attempts: 3, .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_available_dest_path_case_insensitive() -> Result<(), AppError> {
+        // A case-sensitive tempdir stands in for a case-insensitive `files/` directory:
+        // the existing "Report.TXT" must block "report.txt" only when folding is enabled.
+        let temp_trash_root = tempdir()?;
+        let trash_files_path = temp_trash_root.path().join(TRASH_FILES_DIR_NAME);
+        let trash_info_path = temp_trash_root.path().join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(&trash_files_path)?;
+        fs::create_dir_all(&trash_info_path)?;
+        File::create(trash_files_path.join("Report.TXT"))?;
+        File::create(trash_info_path.join("REPORT.2.txt.trashinfo"))?;
+        let source_path = temp_trash_root.path().join("report.txt");
+
+        let find = |case_insensitive| {
+            let mut taken = TakenNames::read(&trash_files_path, &trash_info_path, case_insensitive);
+            find_available_dest_path(
+                &source_path,
+                &trash_files_path,
+                &trash_info_path,
+                &mut taken,
+                DEFAULT_MAX_COLLISIONS,
+                is_info_file_free,
+            )
+        };
+        let sensitive = find(false)?;
+        assert_eq!(sensitive, trash_files_path.join("report.txt"));
+
+        let insensitive = find(true)?;
+        assert_eq!(insensitive, trash_files_path.join("report.3.txt"));
+        Ok(())
+    }
+
     #[test]
     fn test_build_trash_info_content() {
         let original_path = Path::new("/home/user/file.txt");
         let deletion_date = "2024-01-01T12:30:00";
 
         let expected_content = "[Trash Info]\nPath=/home/user/file.txt\nDeletionDate=2024-01-01T12:30:00\n";
-        let actual_content = build_trash_info_content(original_path, deletion_date);
+        let actual_content =
+            build_trash_info_content(original_path, deletion_date, PathEncoding::default(), None, None);
 
         assert_eq!(actual_content, expected_content);
     }
 
+    #[test]
+    fn test_build_trash_info_content_matches_trash_cli() {
+        // A sample info file written by `trash-put` for the same path and time.
+        let trash_cli_sample =
+            "[Trash Info]\nPath=/home/user/My%20Docs/%5Bdraft%5D%20notes%20%28v2%29.txt\nDeletionDate=2024-01-01T12:30:00\n";
+        let original_path = Path::new("/home/user/My Docs/[draft] notes (v2).txt");
+
+        let content =
+            build_trash_info_content(original_path, "2024-01-01T12:30:00", PathEncoding::TrashCli, None, None);
+        assert_eq!(content, trash_cli_sample);
+    }
+
     #[test]
     fn test_determine_info_file_path() {
         let trash_info_path = Path::new("/home/user/.local/share/Trash/info");
@@ -291,19 +1210,26 @@ mod tests {
         let temp_root = tempdir()?;
         let original_path = temp_root.path().join("original_file.txt");
         File::create(&original_path)?;
+        fs::set_permissions(&original_path, fs::Permissions::from_mode(0o600))?;
 
         let trash_root = tempdir()?;
         let trash_info_path = trash_root.path().join(TRASH_INFO_DIR_NAME);
         fs::create_dir_all(&trash_info_path)?; // ensure_structure_exists() の役割を模倣
 
         let dest_path = trash_root.path().join(TRASH_FILES_DIR_NAME).join("original_file.txt");
+        let info_file_path = determine_info_file_path(&dest_path, &trash_info_path);
 
-        create_trash_info_file(&original_path, &dest_path, &trash_info_path)?;
+        // By default, nothing beyond the keys of the specification is written.
+        let plain = trash_info_content(&original_path, PathEncoding::default(), false)?;
+        assert!(!plain.contains(TRASH_INFO_MODE_KEY));
+
+        let content = trash_info_content(&original_path, PathEncoding::default(), true)?;
+        assert!(create_trash_info_file(&info_file_path, &content)?);
 
         let expected_info_file_path = trash_info_path.join(format!("original_file.txt{}", TRASH_INFO_SUFFIX));
         assert!(expected_info_file_path.exists(), ".trashinfo file should be created.");
 
-        let info_content = fs::read_to_string(expected_info_file_path)?;
+        let info_content = fs::read_to_string(&expected_info_file_path)?;
         let original_abs_path = original_path.canonicalize()?;
 
         let expected_start = format!("{}\n", TRASH_INFO_HEADER);
@@ -312,7 +1238,61 @@ mod tests {
         assert!(info_content.starts_with(&expected_start));
         assert!(info_content.contains(&expected_path_line));
         assert!(info_content.contains(&expected_date_prefix));
+        let parent_mode = fs::metadata(temp_root.path())?.permissions().mode() & 0o7777;
+        assert!(info_content.ends_with(&format!(
+            "{}=0600\n{}={:04o}\n",
+            TRASH_INFO_MODE_KEY, TRASH_INFO_PARENT_MODE_KEY, parent_mode
+        )));
+
+        // An existing info file is never overwritten: the name belongs to whoever created it first.
+        assert!(!create_trash_info_file(&info_file_path, "[Trash Info]\n")?);
+        assert_eq!(fs::read_to_string(&expected_info_file_path)?, info_content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_available_dest_path_skips_info_file_created_after_snapshot() -> Result<(), AppError> {
+        let temp_trash_root = tempdir()?;
+        let trash_files_path = temp_trash_root.path().join(TRASH_FILES_DIR_NAME);
+        let trash_info_path = temp_trash_root.path().join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(&trash_files_path)?;
+        fs::create_dir_all(&trash_info_path)?;
+        let source_path = temp_trash_root.path().join("race.txt");
+        File::create(&source_path)?;
 
+        // Another run claims "race.txt" between our snapshot and our pick.
+        let mut taken = TakenNames::read(&trash_files_path, &trash_info_path, false);
+        let other_info = trash_info_path.join(format!("race.txt{}", TRASH_INFO_SUFFIX));
+        fs::write(&other_info, "other run\n")?;
+
+        let mut claimed = Vec::new();
+        let dest_path = find_available_dest_path(
+            &source_path,
+            &trash_files_path,
+            &trash_info_path,
+            &mut taken,
+            DEFAULT_MAX_COLLISIONS,
+            |info_file_path| {
+                claimed.push(info_file_path.to_path_buf());
+                create_trash_info_file(info_file_path, "ours\n")
+            },
+        )?;
+
+        assert_eq!(dest_path, trash_files_path.join("race.2.txt"));
+        assert_eq!(
+            claimed,
+            vec![
+                other_info.clone(),
+                trash_info_path.join(format!("race.2.txt{}", TRASH_INFO_SUFFIX))
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(&other_info)?,
+            "other run\n",
+            "the other run's info file is untouched"
+        );
+        assert_eq!(fs::read_to_string(&claimed[1])?, "ours\n");
         Ok(())
     }
 
@@ -334,11 +1314,17 @@ mod tests {
             crate::trash::locations::TrashType::Home,
         );
         target_trash.ensure_structure_exists()?;
-        trash_item(&source_path, &target_trash)?;
+        let dest_path = trash_item(
+            &source_path,
+            &target_trash,
+            &mut NameSnapshots::default(),
+            &TrashOptions::default(),
+        )?;
 
         assert!(!source_path.exists(), "Source file should be moved, not copied.");
 
         let trashed_file_path = trash_files_path.join("file_to_trash.txt");
+        assert_eq!(dest_path, trashed_file_path, "Should return the destination path.");
         assert!(trashed_file_path.exists(), "File should exist in trash/files.");
 
         let info_file_path = trash_info_path.join(format!("file_to_trash.txt{}", TRASH_INFO_SUFFIX));
@@ -351,55 +1337,1139 @@ mod tests {
     }
 
     #[test]
-    #[cfg(unix)]
-    fn test_trash_item_cleans_up_info_file_on_rename_failure() -> Result<(), AppError> {
+    fn test_trash_item_records_directory_size() -> Result<(), AppError> {
         let source_root = tempdir()?;
         let trash_root = tempdir()?;
-
-        let source_path = source_root.path().join("file.txt");
-        File::create(&source_path)?;
-
-        let trash_files_path = trash_root.path().join(TRASH_FILES_DIR_NAME);
-        let trash_info_path = trash_root.path().join(TRASH_INFO_DIR_NAME);
-
-        // Make the `files` directory read-only to cause `fs::rename` to fail.
-        fs::create_dir_all(&trash_files_path)?;
-        let mut perms = fs::metadata(&trash_files_path)?.permissions();
-        perms.set_mode(0o555); // r-xr-xr-x
-        fs::set_permissions(&trash_files_path, perms)?;
+        let source_path = source_root.path().join("photos");
+        fs::create_dir(&source_path)?;
+        fs::write(source_path.join("a.jpg"), [0u8; 300])?;
+        File::create(source_root.path().join("notes.txt"))?;
 
         let target_trash = TargetTrash::new(
             trash_root.path().to_path_buf(),
             crate::trash::locations::TrashType::Home,
         );
         target_trash.ensure_structure_exists()?;
-        let result = trash_item(&source_path, &target_trash);
-
-        assert!(result.is_err(), "Expected trash_item to fail.");
+        let dest_path = trash_item(
+            &source_path,
+            &target_trash,
+            &mut NameSnapshots::default(),
+            &TrashOptions::default(),
+        )?;
+        trash_item(
+            &source_root.path().join("notes.txt"),
+            &target_trash,
+            &mut NameSnapshots::default(),
+            &TrashOptions::default(),
+        )?;
+
+        let cache = fs::read_to_string(trash_root.path().join(DIRECTORY_SIZES_FILE_NAME))?;
+        let info_mtime = fs::metadata(determine_info_file_path(&dest_path, &target_trash.info_path()))?.mtime();
+        assert_eq!(cache, format!("{} {} photos\n", dir_size(&dest_path), info_mtime));
+        Ok(())
+    }
 
-        assert!(
-            source_path.exists(),
-            "Source file should still exist after a failed move."
-        );
+    #[test]
+    fn test_trash_item_copy_keeps_original() -> Result<(), AppError> {
+        let source_root = tempdir()?;
+        let trash_root = tempdir()?;
+        let source_path = source_root.path().join("draft");
+        fs::create_dir(&source_path)?;
+        fs::write(source_path.join("chapter1.md"), "first version")?;
 
-        let expected_info_path = trash_info_path.join(format!("file.txt{}", TRASH_INFO_SUFFIX));
-        assert!(
-            !expected_info_path.exists(),
-            "The .trashinfo file should be cleaned up after a rename failure."
+        let target_trash = TargetTrash::new(
+            trash_root.path().to_path_buf(),
+            crate::trash::locations::TrashType::Home,
         );
+        target_trash.ensure_structure_exists()?;
+        let opts = TrashOptions {
+            copy: true,
+            ..TrashOptions::default()
+        };
+        let dest_path = trash_item(&source_path, &target_trash, &mut NameSnapshots::default(), &opts)?;
 
-        // Teardown
-        let mut perms = fs::metadata(&trash_files_path)?.permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(&trash_files_path, perms)?;
-
+        assert_eq!(fs::read_to_string(source_path.join("chapter1.md"))?, "first version");
+        assert_eq!(fs::read_to_string(dest_path.join("chapter1.md"))?, "first version");
+        let info = fs::read_to_string(determine_info_file_path(&dest_path, &target_trash.info_path()))?;
+        assert!(info.contains(&format!("Path={}", source_path.canonicalize()?.display())));
         Ok(())
     }
 
     #[test]
-    fn test_is_path_in_trash_dir_location() {
-        let trash_path = Path::new("/home/user/.local/share/Trash");
-
+    fn test_trash_item_copy_removes_interrupted_copies() -> Result<(), AppError> {
+        let source_root = tempdir()?;
+        let trash_root = tempdir()?;
+        let source_path = source_root.path().join("notes.txt");
+        fs::write(&source_path, "notes")?;
+        let target_trash = TargetTrash::new(
+            trash_root.path().to_path_buf(),
+            crate::trash::locations::TrashType::Home,
+        );
+        target_trash.ensure_structure_exists()?;
+
+        // A copy whose process was killed midway, and one that is still running (this process).
+        let mut child = std::process::Command::new("true").spawn()?;
+        let dead_pid = child.id();
+        child.wait()?;
+        let interrupted = trash_root.path().join(format!("{}{}-0", COPY_STAGING_PREFIX, dead_pid));
+        fs::create_dir(&interrupted)?;
+        fs::write(interrupted.join("partial.bin"), "part")?;
+        let in_progress = trash_root
+            .path()
+            .join(format!("{}{}-999999", COPY_STAGING_PREFIX, std::process::id()));
+        fs::create_dir(&in_progress)?;
+
+        let opts = TrashOptions {
+            copy: true,
+            ..TrashOptions::default()
+        };
+        let dest_path = trash_item(&source_path, &target_trash, &mut NameSnapshots::default(), &opts)?;
+        assert_eq!(fs::read_to_string(&dest_path)?, "notes");
+        assert!(!interrupted.exists(), "the interrupted copy should be removed");
+        assert!(in_progress.exists(), "a copy still in progress must be kept");
+
+        // Neither the interrupted copy nor the staged one became a trash entry.
+        assert_eq!(fs::read_dir(target_trash.files_path())?.count(), 1);
+        assert_eq!(fs::read_dir(target_trash.info_path())?.count(), 1);
+        let staged: Vec<_> = fs::read_dir(trash_root.path())?
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(COPY_STAGING_PREFIX))
+            .map(|entry| entry.path())
+            .collect();
+        assert_eq!(staged, vec![in_progress]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_and_restore_file_named_like_info_file() -> Result<(), AppError> {
+        let source_root = tempdir()?;
+        let trash_root = tempdir()?;
+        let source_path = source_root.path().join("foo.trashinfo");
+        fs::write(&source_path, "not an info file")?;
+
+        let target_trash = TargetTrash::new(
+            trash_root.path().to_path_buf(),
+            crate::trash::locations::TrashType::Home,
+        );
+        target_trash.ensure_structure_exists()?;
+        let dest_path = trash_item(
+            &source_path,
+            &target_trash,
+            &mut NameSnapshots::default(),
+            &TrashOptions::default(),
+        )?;
+        assert_eq!(dest_path, target_trash.files_path().join("foo.trashinfo"));
+        assert!(target_trash.info_path().join("foo.trashinfo.trashinfo").exists());
+
+        let entries = find_trash_entries_in_dirs(&[trash_root.path().to_path_buf()]);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].trashed_path, dest_path);
+        assert_eq!(
+            entries[0].original_path,
+            source_root.path().canonicalize()?.join("foo.trashinfo")
+        );
+
+        restore_item(&entries[0], None)?;
+        assert_eq!(fs::read_to_string(&source_path)?, "not an info file");
+        assert_eq!(fs::read_dir(target_trash.info_path())?.count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_item_same_name_twice_in_one_run() -> Result<(), AppError> {
+        let source_root = tempdir()?;
+        let trash_root = tempdir()?;
+        let first = source_root.path().join("a/notes.txt");
+        let second = source_root.path().join("b/notes.txt");
+        for path in [&first, &second] {
+            fs::create_dir_all(path.parent().unwrap())?;
+            File::create(path)?;
+        }
+        let target_trash = TargetTrash::new(
+            trash_root.path().to_path_buf(),
+            crate::trash::locations::TrashType::Home,
+        );
+        target_trash.ensure_structure_exists()?;
+
+        for dry_run in [true, false] {
+            let opts = TrashOptions {
+                dry_run,
+                ..Default::default()
+            };
+            let mut names = NameSnapshots::default();
+            let first_dest = trash_item(&first, &target_trash, &mut names, &opts)?;
+            let second_dest = trash_item(&second, &target_trash, &mut names, &opts)?;
+            assert_eq!(
+                first_dest,
+                target_trash.files_path().join("notes.txt"),
+                "dry run: {}",
+                dry_run
+            );
+            assert_eq!(
+                second_dest,
+                target_trash.files_path().join("notes.2.txt"),
+                "dry run: {}",
+                dry_run
+            );
+        }
+        assert!(!first.exists() && !second.exists());
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "benchmark; run with `cargo test -- --ignored --nocapture`"]
+    fn bench_find_available_dest_path_in_crowded_trash() -> Result<(), AppError> {
+        const EXISTING: u32 = 2_000;
+        const BATCH: u32 = 200;
+        let trash_root = tempdir()?;
+        let trash_files_path = trash_root.path().join(TRASH_FILES_DIR_NAME);
+        let trash_info_path = trash_root.path().join(TRASH_INFO_DIR_NAME);
+        fs::create_dir_all(&trash_files_path)?;
+        fs::create_dir_all(&trash_info_path)?;
+        File::create(trash_files_path.join("log.txt"))?;
+        for counter in COLLISION_COUNTER_START..EXISTING {
+            File::create(trash_files_path.join(format!("log.{}.txt", counter)))?;
+        }
+        let source_path = trash_root.path().join("log.txt");
+
+        let start = std::time::Instant::now();
+        let mut taken = TakenNames::read(&trash_files_path, &trash_info_path, false);
+        for _ in 0..BATCH {
+            find_available_dest_path(
+                &source_path,
+                &trash_files_path,
+                &trash_info_path,
+                &mut taken,
+                DEFAULT_MAX_COLLISIONS,
+                is_info_file_free,
+            )?;
+        }
+        println!(
+            "chose {} names past {} collisions each in {:?}",
+            BATCH,
+            EXISTING,
+            start.elapsed()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_and_restore_non_utf8_file_name() -> Result<(), AppError> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let source_root = tempdir()?;
+        let trash_root = tempdir()?;
+        // "café.txt" in Latin-1, which is not valid UTF-8.
+        let file_name = std::ffi::OsStr::from_bytes(b"caf\xE9.txt");
+        let source_path = source_root.path().join(file_name);
+        fs::write(&source_path, "menu")?;
+
+        let target_trash = TargetTrash::new(
+            trash_root.path().to_path_buf(),
+            crate::trash::locations::TrashType::Home,
+        );
+        target_trash.ensure_structure_exists()?;
+        let dest_path = trash_item(
+            &source_path,
+            &target_trash,
+            &mut NameSnapshots::default(),
+            &TrashOptions::default(),
+        )?;
+        assert_eq!(dest_path, target_trash.files_path().join(file_name));
+
+        let entries = find_trash_entries_in_dirs(&[trash_root.path().to_path_buf()]);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].original_path,
+            source_root.path().canonicalize()?.join(file_name)
+        );
+
+        restore_item(&entries[0], None)?;
+        assert_eq!(fs::read_to_string(&source_path)?, "menu");
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_item_dry_run_changes_nothing() -> Result<(), AppError> {
+        let source_root = tempdir()?;
+        let trash_root = tempdir()?;
+        let source_path = source_root.path().join("report.txt");
+        File::create(&source_path)?;
+
+        let target_trash = TargetTrash::new(
+            trash_root.path().to_path_buf(),
+            crate::trash::locations::TrashType::Home,
+        );
+        target_trash.ensure_structure_exists()?;
+        File::create(target_trash.files_path().join("report.txt"))?;
+
+        let opts = TrashOptions {
+            dry_run: true,
+            ..TrashOptions::default()
+        };
+        let dest_path = trash_item(&source_path, &target_trash, &mut NameSnapshots::default(), &opts)?;
+
+        assert_eq!(dest_path, target_trash.files_path().join("report.2.txt"));
+        assert!(source_path.exists(), "Source should stay in place.");
+        assert!(!dest_path.exists());
+        assert_eq!(
+            fs::read_dir(target_trash.info_path())?.count(),
+            0,
+            "No info file should be written."
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_trash_item_cleans_up_info_file_on_rename_failure() -> Result<(), AppError> {
+        let source_root = tempdir()?;
+        let trash_root = tempdir()?;
+
+        let source_path = source_root.path().join("file.txt");
+        File::create(&source_path)?;
+
+        let trash_files_path = trash_root.path().join(TRASH_FILES_DIR_NAME);
+        let trash_info_path = trash_root.path().join(TRASH_INFO_DIR_NAME);
+
+        // Make the `files` directory read-only to cause `fs::rename` to fail.
+        fs::create_dir_all(&trash_files_path)?;
+        let mut perms = fs::metadata(&trash_files_path)?.permissions();
+        perms.set_mode(0o555); // r-xr-xr-x
+        fs::set_permissions(&trash_files_path, perms)?;
+
+        let target_trash = TargetTrash::new(
+            trash_root.path().to_path_buf(),
+            crate::trash::locations::TrashType::Home,
+        );
+        target_trash.ensure_structure_exists()?;
+        let result = trash_item(
+            &source_path,
+            &target_trash,
+            &mut NameSnapshots::default(),
+            &TrashOptions::default(),
+        );
+
+        assert!(result.is_err(), "Expected trash_item to fail.");
+
+        assert!(
+            source_path.exists(),
+            "Source file should still exist after a failed move."
+        );
+
+        let expected_info_path = trash_info_path.join(format!("file.txt{}", TRASH_INFO_SUFFIX));
+        assert!(
+            !expected_info_path.exists(),
+            "The .trashinfo file should be cleaned up after a rename failure."
+        );
+
+        // Teardown
+        let mut perms = fs::metadata(&trash_files_path)?.permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&trash_files_path, perms)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_to_trash_files_cross_device_fallback() -> Result<(), AppError> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::fs::FileTypeExt;
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let source_root = tempdir()?;
+        let trash_root = tempdir()?;
+        let cross_device = |_: &Path, _: &Path| Err(io::Error::from(ErrorKind::CrossesDevices));
+
+        let source_path = source_root.path().join("dir");
+        fs::create_dir(&source_path)?;
+        fs::write(source_path.join("inner.txt"), "inner")?;
+        let dest_path = trash_root.path().join("dir");
+
+        // Without the fallback, a cross-device move is refused and nothing changes.
+        let result = move_to_trash_files(&source_path, &dest_path, &TrashOptions::default(), cross_device);
+        assert!(matches!(result, Err(AppError::CrossDeviceMove { .. })));
+        assert!(source_path.join("inner.txt").exists());
+        assert!(!dest_path.exists());
+
+        let opts = TrashOptions {
+            copy_fallback: true,
+            ..TrashOptions::default()
+        };
+        move_to_trash_files(&source_path, &dest_path, &opts, cross_device)?;
+        assert!(!source_path.exists(), "The original should be removed after the copy");
+        assert_eq!(fs::read_to_string(dest_path.join("inner.txt"))?, "inner");
+
+        // A FIFO inside is recreated, not read, which would block until something writes to it.
+        let with_fifo = source_root.path().join("with-fifo");
+        fs::create_dir(&with_fifo)?;
+        let fifo = CString::new(with_fifo.join("pipe").as_os_str().as_bytes()).expect("no NUL in a temp path");
+        // SAFETY: `fifo` is a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o600) }, 0, "mkfifo failed");
+        let fifo_dest = trash_root.path().join("with-fifo");
+        let (sender, receiver) = mpsc::channel();
+        let (thread_source, thread_dest) = (with_fifo.clone(), fifo_dest.clone());
+        std::thread::spawn(move || {
+            let opts = TrashOptions {
+                copy_fallback: true,
+                ..TrashOptions::default()
+            };
+            let _ = sender.send(move_to_trash_files(&thread_source, &thread_dest, &opts, cross_device));
+        });
+        receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("copying a FIFO must not block")?;
+        assert!(!with_fifo.exists());
+        assert!(fs::symlink_metadata(fifo_dest.join("pipe"))?.file_type().is_fifo());
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_protected_path() {
+        let home = Path::new("/home/alice");
+        let cwd = Path::new("/srv/project");
+        struct TestCase {
+            path: &'static str,
+            expected: bool,
+        }
+        let test_cases = vec![
+            TestCase {
+                path: "/",
+                expected: true,
+            },
+            TestCase {
+                path: "/etc",
+                expected: true,
+            },
+            TestCase {
+                path: "/usr",
+                expected: true,
+            },
+            TestCase {
+                path: "/home/alice",
+                expected: true,
+            },
+            TestCase {
+                path: "/srv/project",
+                expected: true,
+            },
+            TestCase {
+                path: "/etc/hosts.bak",
+                expected: false,
+            },
+            TestCase {
+                path: "/home/alice/notes.txt",
+                expected: false,
+            },
+        ];
+        for case in test_cases {
+            assert_eq!(
+                is_protected_path(Path::new(case.path), Some(home), Some(cwd)),
+                case.expected,
+                "path: {}",
+                case.path
+            );
+        }
+    }
+
+    #[test]
+    fn test_trash_item_refuses_root() -> Result<(), AppError> {
+        let trash_root = tempdir()?;
+        let target_trash = TargetTrash::new(
+            trash_root.path().to_path_buf(),
+            crate::trash::locations::TrashType::Home,
+        );
+        target_trash.ensure_structure_exists()?;
+
+        let result = trash_item(
+            Path::new("/"),
+            &target_trash,
+            &mut NameSnapshots::default(),
+            &TrashOptions::default(),
+        );
+        assert!(matches!(result, Err(AppError::ProtectedPath { .. })), "{:?}", result);
+
+        // With --allow-protected the check is skipped; a dry run keeps `/` where it is.
+        let opts = TrashOptions {
+            allow_protected: true,
+            dry_run: true,
+            ..TrashOptions::default()
+        };
+        assert!(!matches!(
+            trash_item(Path::new("/"), &target_trash, &mut NameSnapshots::default(), &opts),
+            Err(AppError::ProtectedPath { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_item_protect_tracked() -> Result<(), AppError> {
+        let trash_root = tempdir()?;
+        let target_trash = TargetTrash::new(
+            trash_root.path().to_path_buf(),
+            crate::trash::locations::TrashType::Home,
+        );
+        target_trash.ensure_structure_exists()?;
+        let repo_root = tempdir()?;
+        let repo = repo_root.path().canonicalize()?;
+        fs::create_dir(repo.join(".git"))?;
+        fs::write(repo.join("main.rs"), "fn main() {}")?;
+        fs::write(repo.join("main.o"), "object")?;
+        // A version 2 index with the single entry `main.rs`, its stat data and object id zeroed.
+        let mut index = b"DIRC\0\0\0\x02\0\0\0\x01".to_vec();
+        index.extend_from_slice(&[0u8; 60]);
+        index.extend_from_slice(&7u16.to_be_bytes());
+        index.extend_from_slice(b"main.rs\0\0\0");
+        fs::write(repo.join(".git/index"), index)?;
+
+        let opts = TrashOptions {
+            protect_tracked: true,
+            ..TrashOptions::default()
+        };
+        let result = trash_item(
+            &repo.join("main.rs"),
+            &target_trash,
+            &mut NameSnapshots::default(),
+            &opts,
+        );
+        assert!(
+            matches!(&result, Err(AppError::TrackedByGit { repository, .. }) if *repository == repo),
+            "{:?}",
+            result
+        );
+        assert!(repo.join("main.rs").exists());
+
+        // Untracked build output is trashed as usual.
+        trash_item(
+            &repo.join("main.o"),
+            &target_trash,
+            &mut NameSnapshots::default(),
+            &opts,
+        )?;
+        assert!(!repo.join("main.o").exists());
+
+        // With --allow-protected, a tracked file is trashed after a warning.
+        let opts = TrashOptions {
+            allow_protected: true,
+            ..opts
+        };
+        trash_item(
+            &repo.join("main.rs"),
+            &target_trash,
+            &mut NameSnapshots::default(),
+            &opts,
+        )?;
+        assert!(!repo.join("main.rs").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_classify_storage_full() {
+        let dest_path = Path::new("/trash/files/big.iso");
+        assert!(is_storage_full(&io::Error::from_raw_os_error(libc::ENOSPC)));
+        assert!(is_storage_full(&io::Error::from(ErrorKind::StorageFull)));
+        assert!(!is_storage_full(&io::Error::from(ErrorKind::PermissionDenied)));
+
+        let full = AppError::Io {
+            path: PathBuf::from("/trash/files/big.iso/part"),
+            source: io::Error::from_raw_os_error(libc::ENOSPC),
+        };
+        assert!(matches!(
+            classify_storage_full(full, dest_path),
+            AppError::TrashFull { path } if path == dest_path
+        ));
+        let denied = AppError::GenericIo(io::Error::from(ErrorKind::PermissionDenied));
+        assert!(matches!(
+            classify_storage_full(denied, dest_path),
+            AppError::GenericIo(_)
+        ));
+    }
+
+    #[test]
+    fn test_trash_item_records_symlink_location_not_target() -> Result<(), AppError> {
+        let source_root = tempdir()?;
+        let trash_root = tempdir()?;
+        let target = source_root.path().join("target.txt");
+        File::create(&target)?;
+        let link = source_root.path().join("link");
+        std::os::unix::fs::symlink(&target, &link)?;
+
+        let target_trash = TargetTrash::new(
+            trash_root.path().to_path_buf(),
+            crate::trash::locations::TrashType::Home,
+        );
+        target_trash.ensure_structure_exists()?;
+        trash_item(
+            &link,
+            &target_trash,
+            &mut NameSnapshots::default(),
+            &TrashOptions::default(),
+        )?;
+
+        let info = fs::read_to_string(
+            trash_root
+                .path()
+                .join(TRASH_INFO_DIR_NAME)
+                .join(format!("link{}", TRASH_INFO_SUFFIX)),
+        )?;
+        let expected = source_root.path().canonicalize()?.join("link");
+        assert!(
+            info.contains(&format!("Path={}\n", expected.display())),
+            "Path should point at the link itself: {}",
+            info
+        );
+        assert!(target.exists(), "The link target must be left in place");
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_item_refuses_source_already_in_trash_files_dir() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let trash_root = root.path().join("trash");
+        let target_trash = TargetTrash::new(trash_root.clone(), crate::trash::locations::TrashType::Home);
+        target_trash.ensure_structure_exists()?;
+
+        // Reach `Trash/files` through a symlink, so the path does not lexically start with the trash root.
+        let alias = root.path().join("alias");
+        std::os::unix::fs::symlink(trash_root.join(TRASH_FILES_DIR_NAME), &alias)?;
+        let source_path = alias.join("foo.txt");
+        File::create(&source_path)?;
+
+        let result = trash_item(
+            &source_path,
+            &target_trash,
+            &mut NameSnapshots::default(),
+            &TrashOptions::default(),
+        );
+        assert!(
+            matches!(result, Err(AppError::SourceInTrashFilesDir { .. })),
+            "Unexpected result: {:?}",
+            result
+        );
+        assert!(source_path.exists());
+        assert_eq!(fs::read_dir(trash_root.join(TRASH_INFO_DIR_NAME))?.count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_identity_normalizes_equivalent_paths() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let dir = root.path().join("dir");
+        fs::create_dir(&dir)?;
+        File::create(dir.join("foo.txt"))?;
+
+        let direct = source_identity(&dir.join("foo.txt"));
+        let dotted = source_identity(&dir.join(".").join("foo.txt"));
+        let parent_hop = source_identity(&dir.join("..").join("dir").join("foo.txt"));
+
+        assert!(direct.is_some());
+        assert_eq!(direct, dotted);
+        assert_eq!(direct, parent_hop);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_files_same_basename_from_two_dirs() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let mount = root.path().canonicalize()?;
+        let sources = [mount.join("a/data.csv"), mount.join("b/data.csv")];
+        for (index, source) in sources.iter().enumerate() {
+            fs::create_dir_all(source.parent().unwrap())?;
+            fs::write(source, format!("table {}", index))?;
+        }
+
+        let mounts = Mounts::from_paths(vec![PathBuf::from("/"), mount.clone()]);
+        let files: Vec<String> = sources.iter().map(|source| source.display().to_string()).collect();
+        let outcomes = trash_files(
+            &mut io::sink(),
+            &mut io::sink(),
+            &mut io::empty(),
+            &files,
+            &mounts,
+            &TrashOptions::default(),
+        );
+
+        let trash_dir = mount.join(format!(".Trash-{}", users::get_current_uid()));
+        let dest_paths: Vec<&PathBuf> = outcomes
+            .iter()
+            .map(|(_, outcome)| match outcome {
+                TrashOutcome::Trashed { dest_path } => dest_path,
+                other => panic!("Expected both files to be trashed, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            dest_paths,
+            [
+                &trash_dir.join(TRASH_FILES_DIR_NAME).join("data.csv"),
+                &trash_dir.join(TRASH_FILES_DIR_NAME).join("data.2.csv")
+            ]
+        );
+
+        let mut entries = find_trash_entries_in_dirs(&[trash_dir]);
+        entries.sort_by(|a, b| a.trashed_path.cmp(&b.trashed_path));
+        let originals: Vec<&PathBuf> = entries.iter().map(|entry| &entry.original_path).collect();
+        assert_eq!(originals, [&sources[1], &sources[0]]);
+        assert_eq!(fs::read_to_string(dest_paths[0])?, "table 0");
+        assert_eq!(fs::read_to_string(dest_paths[1])?, "table 1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_files_verbose_prints_each_destination() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let mount = root.path().canonicalize()?;
+        let sources = [mount.join("a/notes.txt"), mount.join("b/notes.txt")];
+        for source in &sources {
+            fs::create_dir_all(source.parent().unwrap())?;
+            File::create(source)?;
+        }
+
+        let mounts = Mounts::from_paths(vec![PathBuf::from("/"), mount.clone()]);
+        let files: Vec<String> = sources.iter().map(|source| source.display().to_string()).collect();
+        let opts = TrashOptions {
+            verbose: true,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        trash_files(&mut output, &mut io::sink(), &mut io::empty(), &files, &mounts, &opts);
+
+        let trash_files_dir = mount
+            .join(format!(".Trash-{}", users::get_current_uid()))
+            .join(TRASH_FILES_DIR_NAME);
+        let expected = format!(
+            "'{}' -> '{}'\n'{}' -> '{}'\n",
+            files[0],
+            trash_files_dir.join("notes.txt").display(),
+            files[1],
+            trash_files_dir.join("notes.2.txt").display()
+        );
+        assert_eq!(String::from_utf8(output)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_files_interactive_asks_before_each_item() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let mount = root.path().canonicalize()?;
+        let sources = [mount.join("keep.txt"), mount.join("old.log"), mount.join("typo.txt")];
+        for source in &sources {
+            File::create(source)?;
+        }
+
+        let mounts = Mounts::from_paths(vec![PathBuf::from("/"), mount.clone()]);
+        let files: Vec<String> = sources.iter().map(|source| source.display().to_string()).collect();
+        let opts = TrashOptions {
+            interactive: true,
+            ..Default::default()
+        };
+        let mut prompt = Vec::new();
+        // An unclear answer is asked again; an empty one means yes.
+        let mut answers = "n
+maybe
+
+N
+"
+        .as_bytes();
+        let outcomes = trash_files(&mut io::sink(), &mut prompt, &mut answers, &files, &mounts, &opts);
+
+        assert!(matches!(outcomes[0].1, TrashOutcome::Declined), "{:?}", outcomes[0].1);
+        assert!(
+            matches!(outcomes[1].1, TrashOutcome::Trashed { .. }),
+            "{:?}",
+            outcomes[1].1
+        );
+        assert!(matches!(outcomes[2].1, TrashOutcome::Declined), "{:?}", outcomes[2].1);
+        assert!(sources[0].exists());
+        assert!(!sources[1].exists());
+        assert!(sources[2].exists());
+        assert_eq!(
+            String::from_utf8(prompt)?,
+            format!(
+                "trash '{0}'? [Y/n] trash '{1}'? [Y/n] trash '{1}'? [Y/n] trash '{2}'? [Y/n] ",
+                files[0], files[1], files[2]
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_files_dry_run_never_asks() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let mount = root.path().canonicalize()?;
+        let source = mount.join("notes.txt");
+        let dir = mount.join("big");
+        File::create(&source)?;
+        fs::create_dir(&dir)?;
+        fs::write(dir.join("data.bin"), vec![0u8; 2_000])?;
+
+        let mounts = Mounts::from_paths(vec![PathBuf::from("/"), mount.clone()]);
+        let files = vec![source.display().to_string(), dir.display().to_string()];
+        let opts = TrashOptions {
+            interactive: true,
+            confirm_larger_than: Some(1_000),
+            recursive: true,
+            dry_run: true,
+            ..Default::default()
+        };
+        let mut prompt = Vec::new();
+        let outcomes = trash_files(&mut io::sink(), &mut prompt, &mut io::empty(), &files, &mounts, &opts);
+
+        assert!(prompt.is_empty(), "{:?}", String::from_utf8(prompt));
+        for (file, outcome) in &outcomes {
+            assert!(
+                matches!(outcome, TrashOutcome::Trashed { .. }),
+                "{}: {:?}",
+                file,
+                outcome
+            );
+        }
+        assert!(source.exists() && dir.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_files_confirms_directories_larger_than_threshold() -> Result<(), AppError> {
+        struct TestCase {
+            threshold: Option<u64>,
+            answers: &'static str,
+            expected_prompt: bool,
+            expected_trashed: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                threshold: Some(1_000),
+                answers: "n\n",
+                expected_prompt: true,
+                expected_trashed: false,
+            },
+            TestCase {
+                threshold: Some(1_000),
+                answers: "y\n",
+                expected_prompt: true,
+                expected_trashed: true,
+            },
+            TestCase {
+                threshold: Some(10_000),
+                answers: "",
+                expected_prompt: false,
+                expected_trashed: true,
+            },
+            TestCase {
+                // -y or -f: never asked.
+                threshold: None,
+                answers: "",
+                expected_prompt: false,
+                expected_trashed: true,
+            },
+        ];
+
+        for case in test_cases {
+            let root = tempdir()?;
+            let mount = root.path().canonicalize()?;
+            let build = mount.join("build");
+            fs::create_dir_all(build.join("obj"))?;
+            fs::write(build.join("obj/main.o"), vec![0u8; 3_000])?;
+
+            let mounts = Mounts::from_paths(vec![PathBuf::from("/"), mount.clone()]);
+            let files = vec![build.display().to_string()];
+            let opts = TrashOptions {
+                recursive: true,
+                confirm_larger_than: case.threshold,
+                ..Default::default()
+            };
+            let mut prompt = Vec::new();
+            let mut answers = case.answers.as_bytes();
+            let outcomes = trash_files(&mut io::sink(), &mut prompt, &mut answers, &files, &mounts, &opts);
+
+            let prompt = String::from_utf8(prompt)?;
+            if case.expected_prompt {
+                assert_eq!(prompt, format!("trash directory '{}' (2.93 KiB)? [Y/n] ", files[0]));
+            } else {
+                assert!(prompt.is_empty(), "{}", prompt);
+            }
+            assert_eq!(!build.exists(), case.expected_trashed, "{:?}", outcomes[0].1);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_files_skips_duplicate_arguments() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let mount = root.path().canonicalize()?;
+        let file = mount.join("foo.txt");
+        File::create(&file)?;
+
+        // Treat the temp dir as its own filesystem so the private `$topdir/.Trash-$uid` is used.
+        let mounts = Mounts::from_paths(vec![PathBuf::from("/"), mount.clone()]);
+        let files = vec![
+            file.display().to_string(),
+            mount.join(".").join("foo.txt").display().to_string(),
+        ];
+        let outcomes = trash_files(
+            &mut io::sink(),
+            &mut io::sink(),
+            &mut io::empty(),
+            &files,
+            &mounts,
+            &TrashOptions::default(),
+        );
+
+        assert_eq!(outcomes.len(), 2);
+        assert!(matches!(outcomes[0].1, TrashOutcome::Trashed { .. }));
+        match &outcomes[1].1 {
+            TrashOutcome::Failed(message) => assert!(
+                message.contains("already trashed in this run"),
+                "Unexpected message: {}",
+                message
+            ),
+            other => panic!("Expected the duplicate to be skipped, got {:?}", other),
+        }
+
+        let trash_files_dir = mount
+            .join(format!(".Trash-{}", users::get_current_uid()))
+            .join(TRASH_FILES_DIR_NAME);
+        assert_eq!(
+            fs::read_dir(trash_files_dir)?.count(),
+            1,
+            "Only one item should be trashed"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_files_with_trailing_slash() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let mount = root.path().canonicalize()?;
+        File::create(mount.join("file.txt"))?;
+        fs::create_dir(mount.join("dir"))?;
+        File::create(mount.join("dir/inner.txt"))?;
+
+        let mounts = Mounts::from_paths(vec![PathBuf::from("/"), mount.clone()]);
+        let files = vec![
+            format!("{}/file.txt/", mount.display()),
+            format!("{}/dir//", mount.display()),
+        ];
+        let opts = TrashOptions {
+            recursive: true,
+            ..TrashOptions::default()
+        };
+        let outcomes = trash_files(
+            &mut io::sink(),
+            &mut io::sink(),
+            &mut io::empty(),
+            &files,
+            &mounts,
+            &opts,
+        );
+
+        match &outcomes[0].1 {
+            TrashOutcome::Failed(message) => assert!(message.contains("not a directory"), "{}", message),
+            other => panic!("Expected 'file.txt/' to fail, got {:?}", other),
+        }
+        assert!(mount.join("file.txt").exists(), "The file must be left in place");
+
+        let trash_files_dir = mount
+            .join(format!(".Trash-{}", users::get_current_uid()))
+            .join(TRASH_FILES_DIR_NAME);
+        match &outcomes[1].1 {
+            TrashOutcome::Trashed { dest_path } => assert_eq!(dest_path, &trash_files_dir.join("dir")),
+            other => panic!("Expected 'dir//' to be trashed, got {:?}", other),
+        }
+        assert!(trash_files_dir.join("dir/inner.txt").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_files_requires_recursive_for_directories() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let mount = root.path().canonicalize()?;
+        let mounts = Mounts::from_paths(vec![PathBuf::from("/"), mount.clone()]);
+
+        struct TestCase {
+            recursive: bool,
+            dir_trashed: bool,
+            description: &'static str,
+        }
+        let test_cases = vec![
+            TestCase {
+                recursive: false,
+                dir_trashed: false,
+                description: "directory refused without --recursive",
+            },
+            TestCase {
+                recursive: true,
+                dir_trashed: true,
+                description: "directory trashed with --recursive",
+            },
+        ];
+
+        for case in test_cases {
+            File::create(mount.join("file.txt"))?;
+            fs::create_dir(mount.join("dir"))?;
+            let files = vec![
+                mount.join("dir").display().to_string(),
+                mount.join("file.txt").display().to_string(),
+            ];
+            let opts = TrashOptions {
+                recursive: case.recursive,
+                ..TrashOptions::default()
+            };
+            let outcomes = trash_files(
+                &mut io::sink(),
+                &mut io::sink(),
+                &mut io::empty(),
+                &files,
+                &mounts,
+                &opts,
+            );
+
+            match &outcomes[0].1 {
+                TrashOutcome::Trashed { .. } => assert!(case.dir_trashed, "{}", case.description),
+                TrashOutcome::Failed(message) => {
+                    assert!(!case.dir_trashed, "{}: {}", case.description, message);
+                    assert!(message.contains("is a directory (use --recursive)"), "{}", message);
+                }
+                other => panic!("{}: unexpected outcome {:?}", case.description, other),
+            }
+            assert_eq!(mount.join("dir").exists(), !case.dir_trashed, "{}", case.description);
+            assert!(
+                matches!(outcomes[1].1, TrashOutcome::Trashed { .. }),
+                "{}: files are always trashed",
+                case.description
+            );
+            let _ = fs::remove_dir(mount.join("dir"));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_confirm_trash_directory() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let dir = root.path().join("photos");
+        fs::create_dir(&dir)?;
+        fs::write(dir.join("a.jpg"), vec![0u8; 2048])?;
+        fs::write(dir.join("b.jpg"), vec![0u8; 1024])?;
+
+        struct TestCase {
+            input: &'static str,
+            expected: bool,
+        }
+        let test_cases = vec![
+            TestCase {
+                input: "y\n",
+                expected: true,
+            },
+            TestCase {
+                input: "YES\n",
+                expected: true,
+            },
+            TestCase {
+                input: "\n",
+                expected: false,
+            },
+            TestCase {
+                input: "n\n",
+                expected: false,
+            },
+            TestCase {
+                input: "",
+                expected: false,
+            },
+        ];
+        for case in test_cases {
+            let mut prompt = Vec::new();
+            let answer = confirm_trash_directory(&mut prompt, &mut case.input.as_bytes(), &dir)?;
+            assert_eq!(answer, case.expected, "input {:?}", case.input);
+            assert_eq!(
+                String::from_utf8(prompt)?,
+                format!("trash directory '{}' (2 items, 3 KiB)? [y/N] ", dir.display())
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_file_args() -> Result<(), AppError> {
+        let lines = read_file_args(&mut "a file.txt\n\n./dir/b.tmp\nlast".as_bytes(), false)?;
+        assert_eq!(lines, vec!["a file.txt", "./dir/b.tmp", "last"]);
+
+        let records = read_file_args(&mut "one\ntwo\0three\0".as_bytes(), true)?;
+        assert_eq!(records, vec!["one\ntwo", "three"]);
+
+        let crlf = read_file_args(&mut "dos.txt\r\nunix.txt\n".as_bytes(), false)?;
+        assert_eq!(crlf, vec!["dos.txt", "unix.txt"]);
+
+        let mixed = read_file_args(&mut &b"good\0bad\xff\0also good\0"[..], true)?;
+        assert_eq!(mixed, vec!["good", "also good"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_line() {
+        let trashed = ["a.txt".to_string(), "b.txt".to_string()];
+        assert_eq!(
+            summary_line("Trashed", &[], 0),
+            None,
+            "No success line when nothing was trashed"
+        );
+        assert_eq!(
+            summary_line("Trashed", &[], 3),
+            None,
+            "No success line when every item failed"
+        );
+        assert_eq!(
+            summary_line("Trashed", &trashed, 0),
+            Some("Trashed: a.txt, b.txt".to_string())
+        );
+        assert_eq!(
+            summary_line("Trashed", &trashed, 1),
+            Some("Trashed 2 of 3 items: a.txt, b.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn test_trash_files_all_missing_trashes_nothing() -> Result<(), AppError> {
+        let root = tempdir()?;
+        let files = vec![
+            root.path().join("missing1").display().to_string(),
+            root.path().join("missing2").display().to_string(),
+        ];
+
+        let outcomes = trash_files(
+            &mut io::sink(),
+            &mut io::sink(),
+            &mut io::empty(),
+            &files,
+            &Mounts::from_paths(vec![PathBuf::from("/")]),
+            &TrashOptions::default(),
+        );
+
+        assert!(outcomes
+            .iter()
+            .all(|(_, outcome)| matches!(outcome, TrashOutcome::Failed(_))));
+        let trashed: Vec<String> = outcomes
+            .iter()
+            .filter(|(_, outcome)| matches!(outcome, TrashOutcome::Trashed { .. }))
+            .map(|(file, _)| file.to_string())
+            .collect();
+        assert_eq!(
+            summary_line("Trashed", &trashed, outcomes.len() - trashed.len()),
+            None,
+            "No misleading success line should be printed"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_path_in_trash_dir_location() {
+        let trash_path = Path::new("/home/user/.local/share/Trash");
+
         // Case 1: Path is inside the 'files' directory of the trash bin.
         let path_in_files = Path::new("/home/user/.local/share/Trash/files/some_file.txt");
         assert!(
@@ -448,7 +2518,12 @@ mod tests {
             trash_root.path().to_path_buf(),
             crate::trash::locations::TrashType::Home,
         );
-        let result = trash_item(&already_trashed_file, &target_trash);
+        let result = trash_item(
+            &already_trashed_file,
+            &target_trash,
+            &mut NameSnapshots::default(),
+            &TrashOptions::default(),
+        );
 
         assert!(
             result.is_err(),
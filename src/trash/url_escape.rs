@@ -1,6 +1,10 @@
-use std::str::Utf8Error;
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::Path;
 
-use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use percent_encoding::{percent_decode_str, percent_encode, AsciiSet, CONTROLS, NON_ALPHANUMERIC};
+
+use crate::trash::error::AppError;
 
 // Defines the encoding rules to be applied to the `Path` key in the Trash specification.
 // Based on RFC 2396 / 3986, this specifies characters that should normally be escaped in a path segment.
@@ -48,19 +52,102 @@ const PATH_ENCODE_SET: &AsciiSet = &CONTROLS
     .add(b'^')
     .add(b'`');
 
+// trash-cli (`urllib.parse.quote(path, "/")`) and GLib/gio (`g_uri_escape_string(path, "/", FALSE)`)
+// escape every byte except the RFC 3986 unreserved characters (alphanumerics and `- . _ ~`) and '/'.
+const STRICT_PATH_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// The convention used to percent-encode the `Path` key of newly written .trashinfo files.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PathEncoding {
+    /// This tool's own convention (`PATH_ENCODE_SET`).
+    #[default]
+    TrashTool,
+    /// Byte-identical to the `Path` values written by trash-cli.
+    TrashCli,
+    /// Byte-identical to the `Path` values written by GLib/gio (e.g. Nautilus, `gio trash`).
+    Gio,
+    /// Not spec-compliant, for debugging only: the path is written as plain UTF-8. Only control
+    /// characters are still escaped, since a raw line break would corrupt the info file.
+    Raw,
+}
+
+impl PathEncoding {
+    /// Returns the encoding matching the tool named by `--trash-info-compat`, if it is known.
+    pub fn for_tool(name: &str) -> Option<Self> {
+        match name {
+            "trash-tool" => Some(PathEncoding::TrashTool),
+            "trash-cli" => Some(PathEncoding::TrashCli),
+            "gio" => Some(PathEncoding::Gio),
+            _ => None,
+        }
+    }
+
+    /// URL-escapes a file path for the `Path` key using this convention.
+    /// Every byte of the path is kept, so names that are not valid UTF-8 survive a round trip.
+    pub fn encode(self, path: &OsStr) -> String {
+        match self {
+            PathEncoding::TrashTool => trash_spec_url_encode(path),
+            PathEncoding::TrashCli | PathEncoding::Gio => {
+                percent_encode(path.as_bytes(), STRICT_PATH_ENCODE_SET).to_string()
+            }
+            PathEncoding::Raw => path
+                .as_bytes()
+                .utf8_chunks()
+                .flat_map(|chunk| {
+                    let valid = chunk.valid().chars().map(|c| match c {
+                        c if c.is_ascii_control() => format!("%{:02X}", c as u8),
+                        c => c.to_string(),
+                    });
+                    // Bytes that are not UTF-8 cannot be written as plain text, so they are escaped.
+                    let invalid = chunk.invalid().iter().map(|byte| format!("%{:02X}", byte));
+                    valid.chain(invalid).collect::<Vec<_>>()
+                })
+                .collect(),
+        }
+    }
+}
+
 /// URL-escapes a file path according to the Trash specification.
-pub fn trash_spec_url_encode(path: &str) -> String {
-    // `utf8_percent_encode` converts non-ASCII characters into a UTF-8 byte sequence,
-    // and then escapes bytes that are in `PATH_ENCODE_SET` or exceed 0x7F.
+pub fn trash_spec_url_encode(path: &OsStr) -> String {
+    // `percent_encode` works on the raw bytes of the path (UTF-8 for most names, but any byte
+    // sequence is a valid name on Linux) and escapes bytes that are in `PATH_ENCODE_SET` or exceed 0x7F.
     // '/' is not included in `PATH_ENCODE_SET`, so it is not escaped.
-    utf8_percent_encode(path, PATH_ENCODE_SET).to_string()
+    percent_encode(path.as_bytes(), PATH_ENCODE_SET).to_string()
 }
 
 /// URL-decodes a file path according to the Trash specification.
-pub fn trash_spec_url_decode(encoded_path: &str) -> Result<String, Utf8Error> {
-    percent_decode_str(encoded_path)
-        .decode_utf8()
-        .map(|cow| cow.into_owned())
+/// The result is the raw bytes of the path, which need not be valid UTF-8.
+/// A `%` that does not start a valid escape is kept as is, so a damaged file still yields a path.
+pub fn trash_spec_url_decode(encoded_path: &str) -> OsString {
+    OsString::from_vec(percent_decode_str(encoded_path).collect())
+}
+
+/// Like `trash_spec_url_decode`, but fails with `AppError::TrashInfoParse` for the info file
+/// `info_path` if a `%` is not followed by two hex digits. A conforming writer escapes `%`
+/// itself as `%25`, so such a sequence means the file is corrupt rather than just unusual.
+pub(crate) fn trash_spec_url_decode_strict(encoded_path: &str, info_path: &Path) -> Result<OsString, AppError> {
+    if let Some(escape) = malformed_escape(encoded_path) {
+        return Err(AppError::TrashInfoParse {
+            path: info_path.to_path_buf(),
+            reason: format!("malformed percent escape '{}' in Path", escape),
+        });
+    }
+    Ok(trash_spec_url_decode(encoded_path))
+}
+
+/// Returns the first `%` sequence in `encoded_path` that is not `%` followed by two hex digits.
+fn malformed_escape(encoded_path: &str) -> Option<&str> {
+    encoded_path.match_indices('%').find_map(|(start, _)| {
+        let digits = encoded_path[start + 1..].chars().take(2);
+        let end = start + 1 + digits.clone().map(char::len_utf8).sum::<usize>();
+        let is_valid = digits.clone().count() == 2 && digits.into_iter().all(|c| c.is_ascii_hexdigit());
+        (!is_valid).then(|| &encoded_path[start..end])
+    })
 }
 
 #[cfg(test)]
@@ -110,7 +197,7 @@ mod tests {
 
         for case in test_cases {
             assert_eq!(
-                trash_spec_url_encode(case.input),
+                trash_spec_url_encode(OsStr::new(case.input)),
                 case.expected,
                 "Failed on: {}",
                 case.description
@@ -118,38 +205,153 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_path_encoding_compat() {
+        struct TestCase<'a> {
+            input: &'a str,
+            encoding: PathEncoding,
+            expected: &'a str,
+            description: &'a str,
+        }
+
+        // Expected values are what the named tool writes for the same path.
+        let test_cases = vec![
+            TestCase {
+                input: "/home/user/My Docs/résumé (final)!.txt",
+                encoding: PathEncoding::TrashCli,
+                expected: "/home/user/My%20Docs/r%C3%A9sum%C3%A9%20%28final%29%21.txt",
+                description: "trash-cli escapes parentheses and exclamation marks",
+            },
+            TestCase {
+                input: "/srv/a+b=c;d,e@f:g&h$i'j*k~l_m-n.o",
+                encoding: PathEncoding::Gio,
+                expected: "/srv/a%2Bb%3Dc%3Bd%2Ce%40f%3Ag%26h%24i%27j%2Ak~l_m-n.o",
+                description: "gio escapes sub-delimiters but keeps unreserved characters",
+            },
+            TestCase {
+                input: "/home/user/My Docs/résumé (final)!.txt",
+                encoding: PathEncoding::TrashTool,
+                expected: "/home/user/My%20Docs/r%C3%A9sum%C3%A9%20(final)!.txt",
+                description: "The default keeps parentheses and exclamation marks",
+            },
+            TestCase {
+                input: "/home/user/My Docs/résumé (final)!.txt",
+                encoding: PathEncoding::Raw,
+                expected: "/home/user/My Docs/résumé (final)!.txt",
+                description: "Raw writes plain UTF-8",
+            },
+            TestCase {
+                input: "/tmp/line1\nline2",
+                encoding: PathEncoding::Raw,
+                expected: "/tmp/line1%0Aline2",
+                description: "Raw still escapes line breaks",
+            },
+        ];
+
+        for case in test_cases {
+            let encoded = case.encoding.encode(OsStr::new(case.input));
+            assert_eq!(encoded, case.expected, "Failed on: {}", case.description);
+            assert_eq!(
+                trash_spec_url_decode(&encoded),
+                case.input,
+                "Round trip failed on: {}",
+                case.description
+            );
+        }
+    }
+
     #[test]
     fn test_trash_spec_url_decode() {
         // Test successful decoding
         assert_eq!(
             trash_spec_url_decode(
                 "/home/user/Documents/%E3%83%86%E3%82%B9%E3%83%88%20%E3%83%95%E3%82%A1%E3%82%A4%E3%83%AB.txt"
-            )
-            .unwrap(),
+            ),
             "/home/user/Documents/テスト ファイル.txt"
         );
         assert_eq!(
-            trash_spec_url_decode("/path/to/my%20file%20with%20spaces.txt").unwrap(),
+            trash_spec_url_decode("/path/to/my%20file%20with%20spaces.txt"),
             "/path/to/my file with spaces.txt"
         );
         assert_eq!(
-            trash_spec_url_decode("/path/to/file%25with%25.txt").unwrap(),
+            trash_spec_url_decode("/path/to/file%25with%25.txt"),
             "/path/to/file%with%.txt"
         );
         assert_eq!(
-            trash_spec_url_decode("/home/user/documents/report.pdf").unwrap(),
+            trash_spec_url_decode("/home/user/documents/report.pdf"),
             "/home/user/documents/report.pdf"
         );
 
         // Test that invalid percent-encoding sequences are passed through without error,
         // as this is the behavior of the `percent-encoding` crate.
+        assert_eq!(trash_spec_url_decode("/path/to/file%GG.txt"), "/path/to/file%GG.txt");
+
+        // Bytes that are not valid UTF-8 are decoded as they are.
         assert_eq!(
-            trash_spec_url_decode("/path/to/file%GG.txt").unwrap(),
-            "/path/to/file%GG.txt"
+            trash_spec_url_decode("/path/to/%C3%28.txt"),
+            OsStr::from_bytes(b"/path/to/\xC3(.txt")
         );
+    }
+
+    #[test]
+    fn test_trash_spec_url_decode_strict() {
+        struct TestCase {
+            input: &'static str,
+            expected: Result<&'static str, &'static str>,
+        }
+
+        let info_path = Path::new("/trash/info/a.trashinfo");
+        let test_cases = vec![
+            TestCase {
+                input: "/path/to/my%20file%2fname%25.txt",
+                expected: Ok("/path/to/my file/name%.txt"),
+            },
+            TestCase {
+                input: "/path/to/file%GG.txt",
+                expected: Err("malformed percent escape '%GG' in Path"),
+            },
+            TestCase {
+                input: "/path/to/file%A",
+                expected: Err("malformed percent escape '%A' in Path"),
+            },
+            TestCase {
+                input: "/path/to/100%",
+                expected: Err("malformed percent escape '%' in Path"),
+            },
+            TestCase {
+                input: "/path/to/%20%é.txt",
+                expected: Err("malformed percent escape '%é.' in Path"),
+            },
+        ];
+
+        for case in test_cases {
+            let result = trash_spec_url_decode_strict(case.input, info_path);
+            match (result, case.expected) {
+                (Ok(decoded), Ok(expected)) => assert_eq!(decoded, expected, "input: {}", case.input),
+                (Err(AppError::TrashInfoParse { path, reason }), Err(expected)) => {
+                    assert_eq!(path, info_path, "input: {}", case.input);
+                    assert_eq!(reason, expected, "input: {}", case.input);
+                }
+                (result, expected) => panic!("input: {}: got {:?}, expected {:?}", case.input, result, expected),
+            }
+        }
+        // The lenient decoder keeps malformed sequences.
+        assert_eq!(trash_spec_url_decode("/path/to/file%A"), "/path/to/file%A");
+    }
 
-        // Test invalid UTF-8 sequence
-        let invalid_utf8 = trash_spec_url_decode("/path/to/%C3%28.txt");
-        assert!(invalid_utf8.is_err(), "Should fail on invalid UTF-8 sequence");
+    #[test]
+    fn test_non_utf8_path_round_trip() {
+        // "café" in Latin-1, which is not valid UTF-8.
+        let path = OsStr::from_bytes(b"/home/user/caf\xE9 menu.txt");
+        let expected = [
+            (PathEncoding::TrashTool, "/home/user/caf%E9%20menu.txt"),
+            (PathEncoding::TrashCli, "/home/user/caf%E9%20menu.txt"),
+            (PathEncoding::Raw, "/home/user/caf%E9 menu.txt"),
+        ];
+        for (encoding, expected) in expected {
+            let encoded = encoding.encode(path);
+            assert_eq!(encoded, expected, "{:?}", encoding);
+            assert_eq!(trash_spec_url_decode(&encoded), path, "{:?}", encoding);
+        }
     }
 }
@@ -0,0 +1,21 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables verbose tracing for the rest of the run, based on `--verbose`.
+pub fn set_verbose(enabled: bool) {
+    VERBOSE.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether verbose tracing is enabled.
+pub(crate) fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+/// Prints a diagnostic line to stderr when `--verbose` is set.
+/// The message is built lazily so callers pay nothing when tracing is off.
+pub(crate) fn trace<F: FnOnce() -> String>(message: F) {
+    if is_verbose() {
+        eprintln!("verbose: {}", message());
+    }
+}
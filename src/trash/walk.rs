@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+use std::fs::{self, Metadata};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::trash::error::AppError;
+
+/// An item found while walking a tree. Symbolic links are reported as-is and never followed.
+#[derive(Clone)]
+pub(crate) struct WalkEntry {
+    pub(crate) path: PathBuf,
+    pub(crate) metadata: Metadata,
+}
+
+pub(crate) enum WalkEvent {
+    /// An item is reached. For a directory, this comes before any of its contents.
+    Visit(WalkEntry),
+    /// All contents of a directory have been visited.
+    Leave(WalkEntry),
+}
+
+enum Pending {
+    Visit(PathBuf),
+    Leave(WalkEntry),
+}
+
+/// Walks a directory tree iteratively with an explicit stack, so arbitrarily deep trees
+/// cannot overflow the call stack. Directories already seen (by device and inode) are
+/// reported as errors and skipped, which breaks loops created by bind mounts.
+///
+/// An error for one item (e.g. an unreadable directory) does not end the walk;
+/// callers decide whether to skip it or to abort.
+pub(crate) struct Walker {
+    stack: Vec<Pending>,
+    visited_dirs: HashSet<(u64, u64)>,
+}
+
+impl Walker {
+    pub(crate) fn new(root: &Path) -> Self {
+        Walker {
+            stack: vec![Pending::Visit(root.to_path_buf())],
+            visited_dirs: HashSet::new(),
+        }
+    }
+
+    fn visit(&mut self, path: PathBuf) -> Result<WalkEvent, AppError> {
+        let metadata = fs::symlink_metadata(&path).map_err(|source| AppError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        if !metadata.is_dir() {
+            return Ok(WalkEvent::Visit(WalkEntry { path, metadata }));
+        }
+
+        if !self.visited_dirs.insert((metadata.dev(), metadata.ino())) {
+            return Err(AppError::Message(format!(
+                "Directory loop detected at '{}'",
+                path.display()
+            )));
+        }
+        let children = fs::read_dir(&path)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(|source| AppError::Io {
+                path: path.clone(),
+                source,
+            })?;
+
+        let entry = WalkEntry { path, metadata };
+        self.stack.push(Pending::Leave(entry.clone()));
+        self.stack.extend(children.into_iter().rev().map(Pending::Visit));
+        Ok(WalkEvent::Visit(entry))
+    }
+}
+
+impl Iterator for Walker {
+    type Item = Result<WalkEvent, AppError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.stack.pop()? {
+            Pending::Leave(entry) => Some(Ok(WalkEvent::Leave(entry))),
+            Pending::Visit(path) => Some(self.visit(path)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_walker_visits_before_and_leaves_after_contents() -> Result<(), AppError> {
+        let dir = tempdir()?;
+        let root = dir.path().join("root");
+        fs::create_dir_all(root.join("sub"))?;
+        fs::write(root.join("sub/file.txt"), "x")?;
+        std::os::unix::fs::symlink("sub", root.join("link"))?;
+
+        let mut events = Vec::new();
+        for event in Walker::new(&root) {
+            let (kind, entry) = match event? {
+                WalkEvent::Visit(entry) => ("visit", entry),
+                WalkEvent::Leave(entry) => ("leave", entry),
+            };
+            events.push(format!(
+                "{} {}",
+                kind,
+                entry.path.strip_prefix(&root).unwrap().display()
+            ));
+        }
+
+        let position = |event: &str| events.iter().position(|e| e == event).unwrap();
+        assert_eq!(events.len(), 6);
+        assert!(position("visit sub") < position("visit sub/file.txt"));
+        assert!(position("visit sub/file.txt") < position("leave sub"));
+        assert_eq!(events.last().map(String::as_str), Some("leave "));
+        // The symlink is reported but not followed.
+        assert!(!events.iter().any(|e| e.starts_with("visit link/")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_walker_handles_very_deep_tree_on_small_stack() -> Result<(), AppError> {
+        const DEPTH: usize = 1500;
+        let dir = tempdir()?;
+        let root = dir.path().join("deep");
+        let mut deepest = root.clone();
+        for _ in 0..DEPTH {
+            deepest.push("d");
+        }
+        fs::create_dir_all(&deepest)?;
+        fs::write(deepest.join("leaf"), "leaf")?;
+
+        // A recursive walker would overflow a stack this small at this depth.
+        let walked = thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || {
+                Walker::new(&root)
+                    .filter(|event| matches!(event, Ok(WalkEvent::Visit(_))))
+                    .count()
+            })?
+            .join()
+            .unwrap();
+        assert_eq!(walked, DEPTH + 2);
+        Ok(())
+    }
+}